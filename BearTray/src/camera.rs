@@ -1,22 +0,0 @@
-use nalgebra_glm::Vec3;
-
-pub struct Camera {
-    pub eye: Vec3,
-    pub center: Vec3,
-    pub up: Vec3,
-}
-
-
-pub fn basis_change(&self, vector: &Vec3) -> Vec3 {
-    let forward = (self.center - self.eye).normalize();
-    let right = forward.cross(&self.up).normalize();
-    let up = right.cross(&forward).normalize();
-
-
-    let rotated = 
-    vector.x + right +
-    vector.y * up -
-    vector.z * forward;
-
-    rotated.normalize()
-}
\ No newline at end of file
@@ -1,14 +1,22 @@
-use nalgebra_glm::{Vec3};
-use crate::colors::Color;
-use crate::sphere::{Rayintersect, Sphere};
-use crate::rayintersect::{Intersect, RayIntersect, Material};
+use nalgebra_glm::Vec3;
+use render_core::Color;
+use crate::r_stations::{offset_origin, reflection, refract};
+use crate::rayintersect::{Intersect, RayIntersect};
+use crate::scene::Scene;
+use crate::shadow::in_shadow;
+
+// Bounces after which reflection and refraction rays give up and return the background
+const MAX_DEPTH: u32 = 3;
+
+pub fn cast_ray(origin: &Vec3, direction: &Vec3, scene: &Scene, depth: u32) -> Color {
+    if depth > MAX_DEPTH {
+        return scene.background;
+    }
 
-pub fn cast_ray(origin: &Vec3, direction: &Vec3, objects: &[Sphere]) -> Color {
     let mut intersect = Intersect::empty();
     let mut zbuffer = f32::INFINITY;
-    let mut pixel_color = 0x123442; // Default background color
 
-    for object in objects {
+    for object in &scene.objects {
         let i = object.ray_intersect(origin, direction);
         if i.is_intersecting 
         && i.distance < zbuffer{
@@ -17,11 +25,47 @@ pub fn cast_ray(origin: &Vec3, direction: &Vec3, objects: &[Sphere]) -> Color {
         }
     }
     if !(intersect.is_intersecting){
-        return Color::new(89, 0, 152);
+        return scene.background;
     } 
-    let difuse = intersect.material.diffuse;
+    let material = intersect.material;
+    let surface_color = material.pattern.color_at(material.diffuse, intersect.u, intersect.v, &scene.textures);
+
+    // Phong: ambient plus diffuse and specular from every light that isn't blocked
+    let view_dir = -direction;
+    let normal = intersect.facing_normal();
+    let mut diffuse = surface_color * scene.ambient;
+    let mut specular = Color::black();
+
+    for light in &scene.lights {
+        if in_shadow(&intersect, light, &scene.objects) {
+            continue;
+        }
+        let light_dir = (light.position - intersect.point).normalize();
+
+        let diffuse_intensity = normal.dot(&light_dir).max(0.0) * light.intensity;
+        diffuse = diffuse + surface_color.blend_multiply(&light.color) * diffuse_intensity;
+
+        let reflect_dir = reflection(&-light_dir, &normal);
+        let specular_intensity = view_dir.dot(&reflect_dir).max(0.0).powf(material.specular) * light.intensity;
+        specular = specular + light.color * specular_intensity;
+    }
+
+    let mut reflect_color = Color::black();
+    if material.albedo[2] > 0.0 {
+        let reflect_dir = reflection(direction, &intersect.normal).normalize();
+        let reflect_origin = offset_origin(&intersect, &reflect_dir);
+        reflect_color = cast_ray(&reflect_origin, &reflect_dir, scene, depth + 1);
+    }
+
+    let mut refract_color = Color::black();
+    if material.albedo[3] > 0.0 {
+        let refract_dir = refract(direction, &intersect.normal, material.refraction_index).normalize();
+        let refract_origin = offset_origin(&intersect, &refract_dir);
+        refract_color = cast_ray(&refract_origin, &refract_dir, scene, depth + 1);
+    }
 
-    return difuse;
-        
-    
+    diffuse * material.albedo[0]
+        + specular * material.albedo[1]
+        + reflect_color * material.albedo[2]
+        + refract_color * material.albedo[3]
 }
@@ -0,0 +1,197 @@
+use nalgebra_glm::Vec3;
+
+use crate::rayintersect::{Intersect, RayIntersect, Span};
+use crate::sdf::Sdf;
+use crate::sphere::Sphere;
+
+// Spheres, distance fields, or solids built from other shapes with boolean operations. The
+// surface where one shape cuts into another keeps the cutting shape's material.
+#[derive(Debug, Clone)]
+pub enum Shape {
+    Sphere(Sphere),
+    // Ray marched instead of intersected analytically
+    Field(Sdf),
+    // Inside either
+    Union(Box<Shape>, Box<Shape>),
+    // Inside both
+    Intersection(Box<Shape>, Box<Shape>),
+    // Inside the first but not the second
+    Difference(Box<Shape>, Box<Shape>),
+}
+
+impl RayIntersect for Shape {
+    fn ray_intersect(&self, origin: &Vec3, direction: &Vec3) -> Intersect {
+        match self {
+            Shape::Sphere(sphere) => sphere.ray_intersect(origin, direction),
+            Shape::Field(field) => field.ray_intersect(origin, direction),
+            _ => first_hit(&self.ray_spans(origin, direction)),
+        }
+    }
+
+    fn ray_spans(&self, origin: &Vec3, direction: &Vec3) -> Vec<Span> {
+        match self {
+            Shape::Sphere(sphere) => sphere.ray_spans(origin, direction),
+            Shape::Field(field) => field.ray_spans(origin, direction),
+            Shape::Union(a, b) => combine(a.ray_spans(origin, direction), b.ray_spans(origin, direction), direction, |a, b| a || b),
+            Shape::Intersection(a, b) => combine(a.ray_spans(origin, direction), b.ray_spans(origin, direction), direction, |a, b| a && b),
+            Shape::Difference(a, b) => combine(a.ray_spans(origin, direction), b.ray_spans(origin, direction), direction, |a, b| a && !b),
+        }
+    }
+}
+
+// Nearest boundary in front of the origin: where the ray enters a span, or
+// where it leaves the one it started in
+fn first_hit(spans: &[Span]) -> Intersect {
+    for span in spans {
+        if span.enter.distance > 0.0 {
+            return span.enter;
+        }
+        if span.exit.distance > 0.0 {
+            return span.exit;
+        }
+    }
+    Intersect::empty()
+}
+
+// Walks the boundaries of both span lists in order and keeps the stretches
+// where `inside(in_a, in_b)` holds
+fn combine(a: Vec<Span>, b: Vec<Span>, direction: &Vec3, inside: fn(bool, bool) -> bool) -> Vec<Span> {
+    // (boundary, belongs to a, enters its shape)
+    let mut events: Vec<(Intersect, bool, bool)> = Vec::with_capacity(2 * (a.len() + b.len()));
+    for (spans, from_a) in [(a, true), (b, false)] {
+        for span in spans {
+            events.push((span.enter, from_a, true));
+            events.push((span.exit, from_a, false));
+        }
+    }
+    events.sort_by(|x, y| x.0.distance.total_cmp(&y.0.distance));
+
+    let (mut in_a, mut in_b, mut was_inside) = (false, false, false);
+    let mut start: Option<Intersect> = None;
+    let mut result = Vec::new();
+
+    for (mut boundary, from_a, entering) in events {
+        if from_a {
+            in_a = entering;
+        } else {
+            in_b = entering;
+        }
+        let is_inside = inside(in_a, in_b);
+        if is_inside == was_inside {
+            continue;
+        }
+
+        // Leaving the subtracted shape enters the result, so the normal has
+        // to turn around to point out of the result again
+        if entering != is_inside {
+            boundary.normal = -boundary.normal;
+        }
+        boundary.front_face = direction.dot(&boundary.normal) < 0.0;
+
+        if is_inside {
+            start = Some(boundary);
+        } else if let Some(enter) = start.take() {
+            result.push(Span { enter, exit: boundary });
+        }
+        was_inside = is_inside;
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rayintersect::Material;
+
+    // Along -z from the origin the first sphere spans 4..6 and the second 5..7
+    fn spheres() -> (Box<Shape>, Box<Shape>) {
+        let sphere = |z: f32| {
+            Box::new(Shape::Sphere(Sphere {
+                center: Vec3::new(0.0, 0.0, z),
+                radius: 1.0,
+                material: Material::black(),
+            }))
+        };
+        (sphere(-5.0), sphere(-6.0))
+    }
+
+    fn forward() -> Vec3 {
+        Vec3::new(0.0, 0.0, -1.0)
+    }
+
+    // Distances, normal z and front_face of every boundary
+    fn boundaries(shape: &Shape, origin: &Vec3) -> Vec<(f32, f32, bool)> {
+        shape
+            .ray_spans(origin, &forward())
+            .iter()
+            .flat_map(|span| [span.enter, span.exit])
+            .map(|boundary| ((boundary.distance * 1e3).round() / 1e3, boundary.normal.z, boundary.front_face))
+            .collect()
+    }
+
+    #[test]
+    fn test_union_spans_both() {
+        let (a, b) = spheres();
+
+        let union = Shape::Union(a, b);
+
+        assert_eq!(boundaries(&union, &Vec3::zeros()), [(4.0, 1.0, true), (7.0, -1.0, false)]);
+    }
+
+    #[test]
+    fn test_intersection_keeps_the_overlap() {
+        let (a, b) = spheres();
+
+        let intersection = Shape::Intersection(a, b);
+
+        assert_eq!(boundaries(&intersection, &Vec3::zeros()), [(5.0, 1.0, true), (6.0, -1.0, false)]);
+    }
+
+    #[test]
+    fn test_difference_turns_the_cut_around() {
+        let (a, b) = spheres();
+
+        // The second sphere's near side becomes the far side of what is left
+        let front = Shape::Difference(a.clone(), b.clone());
+        assert_eq!(boundaries(&front, &Vec3::zeros()), [(4.0, 1.0, true), (5.0, -1.0, false)]);
+
+        // And the first sphere's far side the near side of the back part
+        let back = Shape::Difference(b, a);
+        assert_eq!(boundaries(&back, &Vec3::zeros()), [(6.0, 1.0, true), (7.0, -1.0, false)]);
+    }
+
+    #[test]
+    fn test_disjoint_shapes() {
+        let (a, _) = spheres();
+        let far = Box::new(Shape::Sphere(Sphere {
+            center: Vec3::new(0.0, 0.0, -10.0),
+            radius: 1.0,
+            material: Material::black(),
+        }));
+
+        assert_eq!(boundaries(&Shape::Union(a.clone(), far.clone()), &Vec3::zeros()).len(), 4);
+        assert!(boundaries(&Shape::Intersection(a.clone(), far.clone()), &Vec3::zeros()).is_empty());
+        assert!(!Shape::Intersection(a, far).ray_intersect(&Vec3::zeros(), &forward()).is_intersecting);
+    }
+
+    #[test]
+    fn test_ray_starting_inside_the_first_sphere() {
+        let (a, b) = spheres();
+        let origin = Vec3::new(0.0, 0.0, -4.5);
+
+        // The span it starts in reaches back behind the origin
+        assert_eq!(boundaries(&Shape::Union(a.clone(), b.clone()), &origin)[0].0, -0.5);
+
+        // Nearest boundary in front: leaving the union, the cut of the
+        // difference, entering the intersection
+        let hit = Shape::Union(a.clone(), b.clone()).ray_intersect(&origin, &forward());
+        assert_eq!((hit.distance, hit.normal.z, hit.front_face), (2.5, -1.0, false));
+
+        let hit = Shape::Difference(a.clone(), b.clone()).ray_intersect(&origin, &forward());
+        assert_eq!((hit.distance, hit.normal.z, hit.front_face), (0.5, -1.0, false));
+
+        let hit = Shape::Intersection(a, b).ray_intersect(&origin, &forward());
+        assert_eq!((hit.distance, hit.normal.z, hit.front_face), (0.5, 1.0, true));
+    }
+}
@@ -0,0 +1,18 @@
+use nalgebra_glm::Vec3;
+use render_core::Color;
+
+pub struct Light {
+    pub position: Vec3,
+    pub color: Color,
+    pub intensity: f32,
+}
+
+impl Light {
+    pub fn new(position: Vec3, color: Color, intensity: f32) -> Self {
+        Light {
+            position,
+            color,
+            intensity,
+        }
+    }
+}
@@ -1,18 +1,20 @@
 use castingray::cast_ray;
 mod sphere;
-use colors::Color;
-use sphere::Sphere;
-use minifb::{Key, Window, WindowOptions};
+use std::f32::consts::PI;
 use std::time::Duration;
-use nalgebra_glm::{normalize, Vec3};
-mod framebuffer;
-use framebuffer::Framebuffer;
+use render_core::{screenshot, window, Camera, Framebuffer, Key};
 mod castingray;
 mod rayintersect;
-mod colors;
-use rayintersect::{Intersect,RayIntersect,Material};
-
-pub fn render(framebuffer: &mut Framebuffer, objects: &[Sphere]) {
+mod csg;
+mod light;
+mod pattern;
+mod r_stations;
+mod scene;
+mod sdf;
+mod shadow;
+use scene::Scene;
+
+pub fn render(framebuffer: &mut Framebuffer, scene: &Scene, camera: &Camera) {
     let width = framebuffer.width as f32;
     let height = framebuffer.height as f32;
     let aspect_ratio = width / height;
@@ -23,15 +25,11 @@ pub fn render(framebuffer: &mut Framebuffer, objects: &[Sphere]) {
             let screen_x = (2.0 * x as f32) / width - 1.0;
             let screen_y = -(2.0 * y as f32) / height + 1.0;
 
-            // Adjust for aspect ratio
-            let adjusted_x = screen_x * aspect_ratio;
-
             // Calculate the direction of the ray for this pixel
-            let ray_direction = normalize(&Vec3::new(adjusted_x, screen_y, -1.0));
+            let ray_direction = camera.ray_direction(screen_x, screen_y, aspect_ratio);
 
             // Cast the ray and get the pixel color
-            let origin = Vec3::new(0.0, 0.0, 0.0); // Camera position
-            let pixel_color = cast_ray(&origin, &ray_direction, objects);
+            let pixel_color = cast_ray(&camera.eye, &ray_direction, scene, 0);
 
             // Draw the pixel on screen with the returned color
             framebuffer.set_foreground_color(pixel_color.to_hex());
@@ -48,99 +46,71 @@ fn main() {
     let frame_delay = Duration::from_millis(16);
 
     let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
-    let mut window = Window::new(
-        "KOALONSON ONSON",
-        window_width,
-        window_height,
-        WindowOptions::default(),
-    ).unwrap();
-
-    let marmle = Material{
-        diffuse: Color::new(250, 250, 250),
+    let mut window = match window::open("KOALONSON ONSON", window_width, window_height) {
+        Ok(window) => window,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
     };
 
-    let ivory = Material{
-        diffuse: Color::new(2, 0, 5),
-    };
-    let mout = Material{
-        diffuse: Color::new(60, 60, 60),
+    // Scene file from the command line, the bear by default
+    let scene_path = std::env::args().nth(1).unwrap_or_else(|| "scenes/bear.txt".to_string());
+    let scene = match Scene::load(&scene_path) {
+        Ok(scene) => scene,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
     };
 
-    let giz = Material{
-        diffuse: Color::new(152, 147, 140)
-    };
-    let griz = Material{
-        diffuse: Color::new(120, 120, 120)
-    };
+    let mut camera = scene.camera.clone();
+    let rotation_speed = PI / 50.0;
+    let zoom_speed = 0.1;
+    let fov_speed = PI / 90.0;
 
+    while window.is_open() && !window.is_key_down(Key::Escape) {
+        if window.is_key_down(Key::Left) {
+            camera.orbit(rotation_speed, 0.0);
+        }
+        if window.is_key_down(Key::Right) {
+            camera.orbit(-rotation_speed, 0.0);
+        }
+        if window.is_key_down(Key::Up) {
+            camera.orbit(0.0, -rotation_speed);
+        }
+        if window.is_key_down(Key::Down) {
+            camera.orbit(0.0, rotation_speed);
+        }
+
+        if window.is_key_down(Key::NumPadPlus) || window.is_key_down(Key::W) {
+            camera.zoom(zoom_speed);
+        }
+        if window.is_key_down(Key::NumPadMinus) || window.is_key_down(Key::S) {
+            camera.zoom(-zoom_speed);
+        }
 
-    let objects = [
-        Sphere {
-            center: Vec3::new(0.0, 0.0, -5.0), // Move the sphere away from the camera
-            radius: 3.3,
-            material: giz
-        },
-        Sphere {
-            center: Vec3::new(0.0, -0.6, -1.5), // Move the sphere away from the camera
-            radius: 0.4,
-            material: ivory
-        },
-        Sphere {
-            center: Vec3::new(0.0, -0.73, -1.6), // Move the sphere away from the camera
-            radius: 0.4,
-            material: mout
-        },
-        Sphere {
-            center: Vec3::new(-0.30, 0.24, -1.1), // Move the sphere away from the camera
-            radius: 0.08,
-            material: ivory
-        },
-        Sphere {
-            center: Vec3::new(0.22, 0.20, -1.0), // Move the sphere away from the camera
-            radius: 0.02,
-            material: marmle
-        },
-        Sphere {
-            center: Vec3::new(-0.26, 0.20, -1.0), // Move the sphere away from the camera
-            radius: 0.02,
-            material: marmle
-        },
-        Sphere {
-            center: Vec3::new(0.26, 0.24, -1.1), // Move the sphere away from the camera
-            radius: 0.08,
-            material: ivory
-        },
-        Sphere {
-            center: Vec3::new(-3.0, 2.0, -5.1), // Move the sphere away from the camera
-            radius: 1.8,
-            material: giz
-        },
-        Sphere {
-            center: Vec3::new(-2.8, 1.8, -4.6), // Move the sphere away from the camera
-            radius: 1.4,
-            material: griz
-        },
-        Sphere {
-            center: Vec3::new(3.0, 2.0, -5.1), // Move the sphere away from the camera
-            radius: 1.8,
-            material: giz
-        },
-        Sphere {
-            center: Vec3::new(2.8, 1.8, -4.6), // Move the sphere away from the camera
-            radius: 1.4,
-            material: griz
-        },
+        // Z narrows the view, X widens it
+        if window.is_key_down(Key::Z) {
+            camera.set_fov(camera.fov - fov_speed);
+        }
+        if window.is_key_down(Key::X) {
+            camera.set_fov(camera.fov + fov_speed);
+        }
 
+        // Back to the view the scene file starts with
+        if window.is_key_down(Key::R) {
+            camera = scene.camera.clone();
+        }
 
+        // Only trace again when the view changed
+        if camera.check_change() {
+            render(&mut framebuffer, &scene, &camera);
+            window.set_title(&format!("KOALONSON ONSON - FOV: {:.0}", camera.fov.to_degrees()));
+        }
 
-    ];
-   
-    while window.is_open() && !window.is_key_down(Key::Escape) {
-        framebuffer.clear();
-        render(&mut framebuffer, &objects);
-        window
-            .update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height)
-            .unwrap();
+        screenshot::handle_hotkey(window.as_ref(), &framebuffer);
+        window.present(&framebuffer);
         std::thread::sleep(frame_delay);
     }
 }
@@ -0,0 +1,129 @@
+use render_core::{Color, Texture};
+
+// Where a material's diffuse color comes from, looked up by texture
+// coordinates. Patterns alternate between the material's own color and
+// a second one; scale is how many repeats fit around the sphere.
+#[derive(Debug, Clone, Copy)]
+pub enum Pattern {
+    Solid,
+    Checker { color: Color, scale: f32 },
+    // Bands running around the sphere, like lines of latitude
+    Stripes { color: Color, scale: f32 },
+    // Smooth random blotches, a few octaves of value noise
+    Noise { color: Color, scale: f32 },
+    // Index into the scene's textures
+    Image(usize),
+}
+
+impl Pattern {
+    pub fn color_at(&self, base: Color, u: f32, v: f32, textures: &[Texture]) -> Color {
+        match *self {
+            Pattern::Solid => base,
+            Pattern::Checker { color, scale } => {
+                // Half as many rows as columns, v only spans pole to pole
+                let column = (u * scale).floor() as i32;
+                let row = (v * scale * 0.5).floor() as i32;
+                if (column + row) % 2 == 0 { base } else { color }
+            }
+            Pattern::Stripes { color, scale } => {
+                if (v * scale).floor() as i32 % 2 == 0 { base } else { color }
+            }
+            Pattern::Noise { color, scale } => base.lerp(&color, fractal_noise(u, v, scale)),
+            Pattern::Image(index) => {
+                // Textures store their top row first, v = 0 is the south pole.
+                // u wraps around so the seam at the back of a sphere joins up.
+                textures.get(index).map_or(base, |texture| texture.get_pixel_color(u.rem_euclid(1.0), 1.0 - v))
+            }
+        }
+    }
+}
+
+// Value noise in [0, 1], each octave twice as fine and half as strong. The
+// lattice repeats every `scale` cells along u so there is no seam.
+fn fractal_noise(u: f32, v: f32, scale: f32) -> f32 {
+    let mut total = 0.0;
+    let mut amplitude = 0.5;
+    let mut weight = 0.0;
+    let mut period = scale.max(1.0).round() as i32;
+
+    for _ in 0..4 {
+        total += value_noise(u * period as f32, v * period as f32 * 0.5, period) * amplitude;
+        weight += amplitude;
+        amplitude *= 0.5;
+        period *= 2;
+    }
+
+    total / weight
+}
+
+fn value_noise(x: f32, y: f32, period: i32) -> f32 {
+    let (cell_x, cell_y) = (x.floor(), y.floor());
+    let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+    let (tx, ty) = (smooth(x - cell_x), smooth(y - cell_y));
+    let (cell_x, cell_y) = (cell_x as i32, cell_y as i32);
+    let corner = |dx: i32, dy: i32| hash((cell_x + dx).rem_euclid(period), cell_y + dy);
+
+    let bottom = corner(0, 0) + (corner(1, 0) - corner(0, 0)) * tx;
+    let top = corner(0, 1) + (corner(1, 1) - corner(0, 1)) * tx;
+    bottom + (top - bottom) * ty
+}
+
+// Pseudo random value in [0, 1] for a lattice point
+fn hash(x: i32, y: i32) -> f32 {
+    let mut h = (x as u32).wrapping_mul(0x8da6_b343) ^ (y as u32).wrapping_mul(0xd816_3841);
+    h = (h ^ (h >> 13)).wrapping_mul(0x5bd1_e995);
+    h ^= h >> 15;
+    (h & 0xffff) as f32 / 65535.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_noise_stays_in_range_and_repeats() {
+        let samples: Vec<f32> = (0..400)
+            .map(|i| fractal_noise((i % 20) as f32 / 20.0, (i / 20) as f32 / 20.0, 6.0))
+            .collect();
+
+        assert!(samples.iter().all(|noise| (0.0..=1.0).contains(noise)));
+        // Blotches, not a flat color
+        let (low, high) = samples.iter().fold((1.0f32, 0.0f32), |(low, high), &noise| (low.min(noise), high.max(noise)));
+        assert!(high - low > 0.3, "{} to {}", low, high);
+        // Same input, same output
+        assert_eq!(fractal_noise(0.37, 0.61, 6.0), fractal_noise(0.37, 0.61, 6.0));
+    }
+
+    #[test]
+    fn test_noise_has_no_seam_around_the_sphere() {
+        for v in [0.0, 0.13, 0.5, 0.92] {
+            assert_eq!(fractal_noise(0.0, v, 5.0), fractal_noise(1.0, v, 5.0), "v = {}", v);
+            let step = (fractal_noise(0.999, v, 5.0) - fractal_noise(0.001, v, 5.0)).abs();
+            assert!(step < 0.05, "v = {}: {}", v, step);
+        }
+    }
+
+    #[test]
+    fn test_noise_scale_is_rounded_to_whole_repeats() {
+        assert_eq!(fractal_noise(0.3, 0.4, 0.2), fractal_noise(0.3, 0.4, 1.0));
+        assert_eq!(fractal_noise(0.3, 0.4, 4.4), fractal_noise(0.3, 0.4, 4.0));
+    }
+
+    #[test]
+    fn test_patterns_alternate_with_the_base_color() {
+        let (base, color) = (Color::new(200, 0, 0), Color::new(0, 0, 200));
+
+        let checker = Pattern::Checker { color, scale: 4.0 };
+        assert_eq!(checker.color_at(base, 0.1, 0.1, &[]), base);
+        assert_eq!(checker.color_at(base, 0.3, 0.1, &[]), color);
+        assert_eq!(checker.color_at(base, 0.3, 0.6, &[]), base);
+
+        let stripes = Pattern::Stripes { color, scale: 4.0 };
+        assert_eq!(stripes.color_at(base, 0.9, 0.1, &[]), base);
+        assert_eq!(stripes.color_at(base, 0.1, 0.3, &[]), color);
+
+        // A texture that isn't there falls back to the base color
+        assert_eq!(Pattern::Image(2).color_at(base, 0.5, 0.5, &[]), base);
+        assert_eq!(Pattern::Solid.color_at(base, 0.5, 0.5, &[]), base);
+    }
+}
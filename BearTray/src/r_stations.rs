@@ -0,0 +1,40 @@
+use nalgebra_glm::Vec3;
+
+use crate::rayintersect::Intersect;
+
+// Moves the hit point off the surface, to the side the new ray leaves from
+pub fn offset_origin(intersect: &Intersect, direction: &Vec3) -> Vec3 {
+    let offset = intersect.normal * 1e-4;
+    if direction.dot(&intersect.normal) < 0.0 {
+        intersect.point - offset
+    } else {
+        intersect.point + offset
+    }
+}
+
+pub fn reflection(incident: &Vec3, normal: &Vec3) -> Vec3 {
+    incident - 2.0 * incident.dot(normal) * normal
+}
+
+// Snell's law between air and a material with index eta_t. The normal points
+// out of the object, so a ray going against it is entering.
+pub fn refract(incident: &Vec3, normal: &Vec3, eta_t: f32) -> Vec3 {
+    let cosi = incident.dot(normal).clamp(-1.0, 1.0);
+
+    let (n_cosi, eta, n_normal) = if cosi < 0.0 {
+        // Entering the object
+        (-cosi, 1.0 / eta_t, *normal)
+    } else {
+        // Leaving it, back into air
+        (cosi, eta_t, -normal)
+    };
+
+    let k = 1.0 - eta * eta * (1.0 - n_cosi * n_cosi);
+
+    if k < 0.0 {
+        // Total internal reflection
+        reflection(incident, &n_normal)
+    } else {
+        eta * incident + (eta * n_cosi - k.sqrt()) * n_normal
+    }
+}
@@ -1,39 +1,104 @@
 use nalgebra_glm::Vec3;
-use crate::colors::Color;
+use render_core::Color;
+use crate::pattern::Pattern;
 
 #[derive(Debug, Clone, Copy)]
 pub struct Material {
     pub diffuse: Color,
+    // Phong exponent, higher makes a smaller, sharper highlight
+    pub specular: f32,
+    // Weights of diffuse, specular, reflection and refraction
+    pub albedo: [f32; 4],
+    pub refraction_index: f32,
+    // Detail painted over the diffuse color
+    pub pattern: Pattern,
+}
+
+impl Material {
+    pub fn new(diffuse: Color, specular: f32, albedo: [f32; 4], refraction_index: f32) -> Self {
+        Material {
+            diffuse,
+            specular,
+            albedo,
+            refraction_index,
+            pattern: Pattern::Solid,
+        }
+    }
+
+    pub fn with_pattern(mut self, pattern: Pattern) -> Self {
+        self.pattern = pattern;
+        self
+    }
+
+    pub fn black() -> Self {
+        Material::new(Color::black(), 0.0, [0.0, 0.0, 0.0, 0.0], 1.0)
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
 #[allow(dead_code)]
 pub struct Intersect {
+    pub point: Vec3,
+    // Unit length, pointing out of the object
+    pub normal: Vec3,
     pub distance: f32,
     pub is_intersecting: bool,
     pub material: Material,
+    // Texture coordinates in [0, 1]
+    pub u: f32,
+    pub v: f32,
+    // Whether the ray hit the outside of the surface, false from inside
+    pub front_face: bool,
 }
 
 impl Intersect {
-    pub fn new( distance: f32, material: Material) -> Self {
+    pub fn new(point: Vec3, normal: Vec3, distance: f32, material: Material, u: f32, v: f32, front_face: bool) -> Self {
         Intersect {
+            point,
+            normal,
             distance,
             is_intersecting: true,
             material,
+            u,
+            v,
+            front_face,
+        }
+    }
+
+    // Normal on the side the ray came from, for lighting
+    pub fn facing_normal(&self) -> Vec3 {
+        if self.front_face {
+            self.normal
+        } else {
+            -self.normal
         }
     }
 
     pub fn empty() -> Self {
         Intersect {
+            point: Vec3::zeros(),
+            normal: Vec3::zeros(),
             distance: 0.0,
             is_intersecting: false,
-            material: Material{
-              diffuse: Color::new(0, 0, 0),
-            },
+            material: Material::black(),
+            u: 0.0,
+            v: 0.0,
+            front_face: false,
         }
     }
 }
 
+// Stretch of a ray inside a solid, from where it goes in to where it comes out
+#[derive(Debug, Clone, Copy)]
+pub struct Span {
+    pub enter: Intersect,
+    pub exit: Intersect,
+}
+
 pub trait RayIntersect {
   fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect;
-}
\ No newline at end of file
+  // Every span along the whole line of the ray, sorted by distance. Spans
+  // behind the origin are included (negative distances) so CSG can tell
+  // whether the origin is inside.
+  fn ray_spans(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Vec<Span>;
+}
@@ -0,0 +1,395 @@
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use std::fs;
+use std::path::Path;
+
+use nalgebra_glm::Vec3;
+use render_core::{Camera, Color, Texture};
+
+use crate::csg::Shape;
+use crate::light::Light;
+use crate::pattern::Pattern;
+use crate::rayintersect::Material;
+use crate::sdf::Sdf;
+use crate::sphere::Sphere;
+
+pub struct Scene {
+    pub objects: Vec<Shape>,
+    pub lights: Vec<Light>,
+    // Color of rays that hit nothing
+    pub background: Color,
+    // Fraction of the diffuse color seen even where no light reaches
+    pub ambient: f32,
+    // Where the view starts
+    pub camera: Camera,
+    // Images that Pattern::Image indexes into
+    pub textures: Vec<Texture>,
+}
+
+impl Scene {
+    // One entry per line, blank lines and lines starting with # are skipped:
+    //   background <r> <g> <b>
+    //   ambient <intensity>
+    //   material <name> <r> <g> <b> <specular> <diffuse> <specular weight> <reflection> <refraction> <refraction index> [pattern]
+    //   texture <name> <image file>
+    //   sphere <x> <y> <z> <radius> <material name>
+    //   light <x> <y> <z> <r> <g> <b> <intensity>
+    //   camera <eye x> <eye y> <eye z> <center x> <center y> <center z> <fov degrees>
+    //   shape <name> sphere <x> <y> <z> <radius> <material name>
+    //   shape <name> union|intersection|difference <shape name> <shape name>
+    //   object <shape name>
+    //   field <name> sphere <x> <y> <z> <radius> <material name>
+    //   field <name> box <x> <y> <z> <half width> <half height> <half depth> <material name>
+    //   field <name> torus <x> <y> <z> <ring radius> <tube radius> <material name>
+    //   field <name> capsule <x1> <y1> <z1> <x2> <y2> <z2> <radius> <material name>
+    //   field <name> blend <field name> <field name> <smoothness>
+    // The optional material pattern is one of
+    //   checker|stripes|noise <r> <g> <b> <scale>
+    //   texture <texture name>
+    // and image files are found relative to the scene file.
+    // `shape` only names a solid, `object` places it in the scene. Fields are
+    // ray marched distance fields, every field is also a shape of the same
+    // name. Materials, shapes and fields have to be declared before they are used.
+    pub fn load(file_path: &str) -> Result<Scene, String> {
+        let contents = fs::read_to_string(file_path).map_err(|err| format!("{}: {}", file_path, err))?;
+        let mut scene = Scene {
+            objects: Vec::new(),
+            lights: Vec::new(),
+            background: Color::new(89, 0, 152),
+            ambient: 0.1,
+            camera: Camera::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0), Vec3::new(0.0, 1.0, 0.0), true).with_fov(PI / 2.0),
+            textures: Vec::new(),
+        };
+        let directory = Path::new(file_path).parent().unwrap_or(Path::new(""));
+        let mut materials: HashMap<String, Material> = HashMap::new();
+        let mut shapes: HashMap<String, Shape> = HashMap::new();
+        let mut fields: HashMap<String, Sdf> = HashMap::new();
+        let mut texture_names: HashMap<String, usize> = HashMap::new();
+
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: String| format!("{}:{}: {}", file_path, number + 1, message);
+            let words: Vec<&str> = line.split_whitespace().collect();
+
+            match words[0] {
+                "background" => {
+                    let values = parse_numbers(&words[1..], 3).map_err(error)?;
+                    scene.background = color(&values);
+                }
+                "ambient" => {
+                    scene.ambient = parse_numbers(&words[1..], 1).map_err(error)?[0];
+                }
+                "material" => {
+                    let name = words.get(1).ok_or_else(|| error("material needs a name".to_string()))?;
+                    let rest = words.get(2..).unwrap_or_default();
+                    let (numbers, pattern_words) = rest.split_at(rest.len().min(9));
+                    let values = parse_numbers(numbers, 9).map_err(error)?;
+                    let pattern = parse_pattern(pattern_words, &texture_names).map_err(error)?;
+                    let material = Material::new(
+                        color(&values[0..3]),
+                        values[3],
+                        [values[4], values[5], values[6], values[7]],
+                        values[8],
+                    )
+                    .with_pattern(pattern);
+                    materials.insert(name.to_string(), material);
+                }
+                "texture" => {
+                    if words.len() != 3 {
+                        return Err(error("expected texture <name> <image file>".to_string()));
+                    }
+                    let texture = Texture::load(&directory.join(words[2]).to_string_lossy()).map_err(error)?;
+                    texture_names.insert(words[1].to_string(), scene.textures.len());
+                    scene.textures.push(texture);
+                }
+                "sphere" => {
+                    let sphere = parse_sphere(&words[1..], &materials).map_err(error)?;
+                    scene.objects.push(Shape::Sphere(sphere));
+                }
+                "shape" => {
+                    let name = words.get(1).ok_or_else(|| error("shape needs a name".to_string()))?;
+                    let kind = words.get(2).copied().unwrap_or_default();
+                    let shape = if kind == "sphere" {
+                        Shape::Sphere(parse_sphere(words.get(3..).unwrap_or_default(), &materials).map_err(error)?)
+                    } else {
+                        if words.len() != 5 {
+                            return Err(error(format!("expected shape <name> {} <shape> <shape>", kind)));
+                        }
+                        let operand = |name: &str| {
+                            shapes.get(name).cloned().map(Box::new).ok_or_else(|| error(format!("unknown shape {}", name)))
+                        };
+                        let (a, b) = (operand(words[3])?, operand(words[4])?);
+                        match kind {
+                            "union" => Shape::Union(a, b),
+                            "intersection" => Shape::Intersection(a, b),
+                            "difference" => Shape::Difference(a, b),
+                            other => return Err(error(format!("unknown shape kind {}", other))),
+                        }
+                    };
+                    shapes.insert(name.to_string(), shape);
+                }
+                "object" => {
+                    let name = words.get(1).ok_or_else(|| error("object needs a shape name".to_string()))?;
+                    let shape = shapes.get(*name).ok_or_else(|| error(format!("unknown shape {}", name)))?;
+                    scene.objects.push(shape.clone());
+                }
+                "field" => {
+                    let name = words.get(1).ok_or_else(|| error("field needs a name".to_string()))?;
+                    let kind = words.get(2).copied().unwrap_or_default();
+                    let field = if kind == "blend" {
+                        if words.len() != 6 {
+                            return Err(error("expected field <name> blend <field> <field> <smoothness>".to_string()));
+                        }
+                        let operand = |name: &str| {
+                            fields.get(name).cloned().map(Box::new).ok_or_else(|| error(format!("unknown field {}", name)))
+                        };
+                        let smoothness = parse_numbers(&words[5..], 1).map_err(error)?[0];
+                        Sdf::SmoothUnion(operand(words[3])?, operand(words[4])?, smoothness)
+                    } else {
+                        parse_field(kind, words.get(3..).unwrap_or_default(), &materials).map_err(error)?
+                    };
+                    shapes.insert(name.to_string(), Shape::Field(field.clone()));
+                    fields.insert(name.to_string(), field);
+                }
+                "light" => {
+                    let values = parse_numbers(&words[1..], 7).map_err(error)?;
+                    scene.lights.push(Light::new(
+                        Vec3::new(values[0], values[1], values[2]),
+                        color(&values[3..6]),
+                        values[6],
+                    ));
+                }
+                "camera" => {
+                    let values = parse_numbers(&words[1..], 7).map_err(error)?;
+                    scene.camera = Camera::new(
+                        Vec3::new(values[0], values[1], values[2]),
+                        Vec3::new(values[3], values[4], values[5]),
+                        Vec3::new(0.0, 1.0, 0.0),
+                        true,
+                    )
+                    .with_fov(values[6].to_radians());
+                }
+                other => return Err(error(format!("unknown entry {}", other))),
+            }
+        }
+
+        Ok(scene)
+    }
+}
+
+// Nothing for a plain color, otherwise a pattern kind and what it needs
+fn parse_pattern(words: &[&str], texture_names: &HashMap<String, usize>) -> Result<Pattern, String> {
+    let Some(kind) = words.first() else {
+        return Ok(Pattern::Solid);
+    };
+    if *kind == "texture" {
+        let name = words.get(1).filter(|_| words.len() == 2).ok_or("expected texture <texture name>")?;
+        let index = texture_names.get(*name).ok_or_else(|| format!("unknown texture {}", name))?;
+        return Ok(Pattern::Image(*index));
+    }
+
+    let values = parse_numbers(&words[1..], 4)?;
+    let (color, scale) = (color(&values[0..3]), values[3]);
+    match *kind {
+        "checker" => Ok(Pattern::Checker { color, scale }),
+        "stripes" => Ok(Pattern::Stripes { color, scale }),
+        "noise" => Ok(Pattern::Noise { color, scale }),
+        other => Err(format!("unknown pattern {}", other)),
+    }
+}
+
+// <x> <y> <z> <radius> <material name>
+fn parse_sphere(words: &[&str], materials: &HashMap<String, Material>) -> Result<Sphere, String> {
+    if words.len() != 5 {
+        return Err("expected <x> <y> <z> <radius> <material>".to_string());
+    }
+    let values = parse_numbers(&words[..4], 4)?;
+    let material = materials.get(words[4]).ok_or_else(|| format!("unknown material {}", words[4]))?;
+    Ok(Sphere {
+        center: Vec3::new(values[0], values[1], values[2]),
+        radius: values[3],
+        material: *material,
+    })
+}
+
+// Primitive fields: the numbers their kind needs, then a material name
+fn parse_field(kind: &str, words: &[&str], materials: &HashMap<String, Material>) -> Result<Sdf, String> {
+    let count = match kind {
+        "sphere" => 4,
+        "box" => 6,
+        "torus" => 5,
+        "capsule" => 7,
+        other => return Err(format!("unknown field kind {}", other)),
+    };
+    if words.len() != count + 1 {
+        return Err(format!("expected {} numbers and a material", count));
+    }
+    let values = parse_numbers(&words[..count], count)?;
+    let material = *materials.get(words[count]).ok_or_else(|| format!("unknown material {}", words[count]))?;
+    let center = Vec3::new(values[0], values[1], values[2]);
+
+    Ok(match kind {
+        "sphere" => Sdf::Sphere { center, radius: values[3], material },
+        "box" => Sdf::Box { center, half_size: Vec3::new(values[3], values[4], values[5]), material },
+        "torus" => Sdf::Torus { center, major_radius: values[3], minor_radius: values[4], material },
+        _ => Sdf::Capsule { a: center, b: Vec3::new(values[3], values[4], values[5]), radius: values[6], material },
+    })
+}
+
+fn parse_numbers(words: &[&str], count: usize) -> Result<Vec<f32>, String> {
+    if words.len() != count {
+        return Err(format!("expected {} numbers, found {}", count, words.len()));
+    }
+    words
+        .iter()
+        .map(|word| word.parse::<f32>().map_err(|err| format!("{}: {}", word, err)))
+        .collect()
+}
+
+fn color(values: &[f32]) -> Color {
+    let channel = |value: f32| value.clamp(0.0, 255.0) as u8;
+    Color::new(channel(values[0]), channel(values[1]), channel(values[2]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scene_file(name: &str, contents: &str) -> String {
+        let file_path = std::env::temp_dir().join(format!("beartray_scene_{}_{}.txt", name, std::process::id()));
+        fs::write(&file_path, contents).unwrap();
+        file_path.to_string_lossy().into_owned()
+    }
+
+    fn load(name: &str, contents: &str) -> Result<Scene, String> {
+        let file_path = scene_file(name, contents);
+        let result = Scene::load(&file_path);
+        fs::remove_file(&file_path).unwrap();
+        result.map_err(|err| err.replace(&file_path, "scene"))
+    }
+
+    fn error(name: &str, contents: &str) -> String {
+        load(name, contents).err().unwrap()
+    }
+
+    #[test]
+    fn test_loads_spheres_and_lights() {
+        let scene = load(
+            "valid",
+            "# comment\n\nbackground 10 20 300\nambient 0.25\n\
+             material red 200 30 30 10 0.9 0.1 0.0 0.0 1.0\n\
+             \x20 sphere 0 -1 -5 1.5 red\n\
+             light -5 6 0 255 240 220 0.8\n",
+        )
+        .unwrap();
+
+        assert_eq!(scene.background, Color::new(10, 20, 255));
+        assert_eq!(scene.ambient, 0.25);
+        assert_eq!(scene.objects.len(), 1);
+        let Shape::Sphere(sphere) = &scene.objects[0] else {
+            panic!("expected a sphere");
+        };
+        assert_eq!(sphere.center, Vec3::new(0.0, -1.0, -5.0));
+        assert_eq!(sphere.radius, 1.5);
+        assert_eq!(sphere.material.diffuse, Color::new(200, 30, 30));
+        assert_eq!(sphere.material.albedo, [0.9, 0.1, 0.0, 0.0]);
+        assert_eq!(scene.lights.len(), 1);
+        assert_eq!(scene.lights[0].position, Vec3::new(-5.0, 6.0, 0.0));
+        assert_eq!(scene.lights[0].intensity, 0.8);
+    }
+
+    #[test]
+    fn test_empty_file_keeps_the_defaults() {
+        let scene = load("empty", "# nothing\n").unwrap();
+
+        assert!(scene.objects.is_empty() && scene.lights.is_empty());
+        assert_eq!(scene.background, Color::new(89, 0, 152));
+        assert_eq!(scene.ambient, 0.1);
+    }
+
+    #[test]
+    fn test_camera_line_sets_the_view() {
+        let scene = load("camera", "camera 0 0.5 0 0 0 -6 70\n").unwrap();
+
+        assert_eq!(scene.camera.eye, Vec3::new(0.0, 0.5, 0.0));
+        assert_eq!(scene.camera.center, Vec3::new(0.0, 0.0, -6.0));
+        assert!((scene.camera.fov - 70f32.to_radians()).abs() < 1e-6);
+        assert!(scene.camera.has_change, "the first frame has to be drawn");
+
+        // Without one it looks down -z with a 90 degree field of view
+        let scene = load("no_camera", "").unwrap();
+        assert_eq!(scene.camera.eye, Vec3::zeros());
+        assert_eq!(scene.camera.center, Vec3::new(0.0, 0.0, -1.0));
+        assert!((scene.camera.fov - PI / 2.0).abs() < 1e-6);
+
+        assert_eq!(error("camera", "camera 0 0 0 0 0 -1\n"), "scene:1: expected 7 numbers, found 6");
+    }
+
+    #[test]
+    fn test_parses_patterns() {
+        let textures = HashMap::from([("fur".to_string(), 3)]);
+        let parse = |line: &str| parse_pattern(&line.split_whitespace().collect::<Vec<_>>(), &textures);
+
+        assert!(matches!(parse(""), Ok(Pattern::Solid)));
+        assert!(matches!(parse("texture fur"), Ok(Pattern::Image(3))));
+        let Ok(Pattern::Checker { color, scale }) = parse("checker 230 220 300 8") else {
+            panic!("expected a checker");
+        };
+        assert_eq!((color, scale), (Color::new(230, 220, 255), 8.0));
+        assert!(matches!(parse("stripes 1 2 3 10"), Ok(Pattern::Stripes { scale, .. }) if scale == 10.0));
+        assert!(matches!(parse("noise 1 2 3 4"), Ok(Pattern::Noise { .. })));
+
+        let error = |line: &str| parse(line).err().unwrap();
+        assert_eq!(error("texture"), "expected texture <texture name>");
+        assert_eq!(error("texture fur extra"), "expected texture <texture name>");
+        assert_eq!(error("texture scales"), "unknown texture scales");
+        assert_eq!(error("checker 1 2 3"), "expected 4 numbers, found 3");
+        assert_eq!(error("noise 1 2 three 4"), "three: invalid float literal");
+        assert_eq!(error("dots 1 2 3 4"), "unknown pattern dots");
+    }
+
+    #[test]
+    fn test_textures_load_next_to_the_scene_file() {
+        let scene = Scene::load("scenes/bear.txt").unwrap();
+        assert!(!scene.textures.is_empty());
+
+        assert_eq!(error("texture_words", "texture fur\n"), "scene:1: expected texture <name> <image file>");
+        let missing = error("texture_file", "texture fur no_such_image.png\n");
+        assert!(missing.starts_with("scene:1: ") && missing.contains("no_such_image.png"), "{}", missing);
+        assert_eq!(
+            error("material_texture", "material red 200 30 30 10 0.9 0.1 0.0 0.0 1.0 texture fur\n"),
+            "scene:1: unknown texture fur"
+        );
+    }
+
+    #[test]
+    fn test_shipped_scenes_load() {
+        for file_path in ["scenes/spheres.txt", "scenes/bear.txt", "scenes/bear_sdf.txt"] {
+            let scene = Scene::load(file_path).unwrap();
+            assert!(!scene.objects.is_empty() && !scene.lights.is_empty(), "{}", file_path);
+        }
+    }
+
+    #[test]
+    fn test_rejects_broken_lines() {
+        let material = "material red 200 30 30 10 0.9 0.1 0.0 0.0 1.0\n";
+
+        assert_eq!(error("background", "background 1 2\n"), "scene:1: expected 3 numbers, found 2");
+        assert_eq!(error("ambient", "ambient dim\n"), "scene:1: dim: invalid float literal");
+        assert_eq!(error("material_name", "material\n"), "scene:1: material needs a name");
+        assert_eq!(error("material_count", "material red 1 2 3\n"), "scene:1: expected 9 numbers, found 3");
+        assert_eq!(
+            error("sphere_count", &format!("{}sphere 0 0 1 red\n", material)),
+            "scene:2: expected <x> <y> <z> <radius> <material>"
+        );
+        assert_eq!(error("sphere_material", "sphere 0 0 0 1 red\n"), "scene:1: unknown material red");
+        assert_eq!(error("light", "light 0 0 0 255 255 255\n"), "scene:1: expected 7 numbers, found 6");
+        assert_eq!(error("entry", "# ok\ncone 0 0 0\n"), "scene:2: unknown entry cone");
+
+        let missing = std::env::temp_dir().join("beartray_scene_missing.txt");
+        assert!(Scene::load(&missing.to_string_lossy()).is_err());
+    }
+}
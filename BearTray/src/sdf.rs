@@ -0,0 +1,262 @@
+use nalgebra_glm::Vec3;
+
+use crate::rayintersect::{Intersect, Material, RayIntersect, Span};
+use crate::sphere::Sphere;
+
+// Sphere tracing gives up after this many steps or this far from the origin
+const MAX_STEPS: usize = 256;
+const MAX_DISTANCE: f32 = 100.0;
+// Close enough to the surface to count as a hit
+const HIT_DISTANCE: f32 = 1e-5;
+// Step used to sample the gradient for normals
+const NORMAL_STEP: f32 = 1e-4;
+// Spans looked for along one ray, enough for a few blobs one behind the other
+const MAX_SPANS: usize = 8;
+
+// Solids described by a signed distance function: negative inside, positive
+// outside, and never more than the real distance to the surface, so a ray can
+// always step forward by it. Rendered by marching instead of solving for the
+// hit, which lets smooth unions melt shapes into each other.
+#[derive(Debug, Clone)]
+pub enum Sdf {
+    Sphere { center: Vec3, radius: f32, material: Material },
+    // Axis aligned, half_size is the distance from the center to each face
+    Box { center: Vec3, half_size: Vec3, material: Material },
+    // Ring lying flat around the y axis
+    Torus { center: Vec3, major_radius: f32, minor_radius: f32, material: Material },
+    // Segment from a to b with rounded ends
+    Capsule { a: Vec3, b: Vec3, radius: f32, material: Material },
+    // Union with a fillet about `smoothness` wide where the two meet, the
+    // materials blending across it
+    SmoothUnion(Box<Sdf>, Box<Sdf>, f32),
+}
+
+impl Sdf {
+    pub fn distance(&self, point: &Vec3) -> f32 {
+        match self {
+            Sdf::Sphere { center, radius, .. } => (point - center).magnitude() - radius,
+            Sdf::Box { center, half_size, .. } => {
+                let q = (point - center).abs() - half_size;
+                q.sup(&Vec3::zeros()).magnitude() + q.max().min(0.0)
+            }
+            Sdf::Torus { center, major_radius, minor_radius, .. } => {
+                let p = point - center;
+                let ring = (p.x * p.x + p.z * p.z).sqrt() - major_radius;
+                (ring * ring + p.y * p.y).sqrt() - minor_radius
+            }
+            Sdf::Capsule { a, b, radius, .. } => {
+                let (pa, ba) = (point - a, b - a);
+                let h = (pa.dot(&ba) / ba.dot(&ba).max(1e-12)).clamp(0.0, 1.0);
+                (pa - ba * h).magnitude() - radius
+            }
+            Sdf::SmoothUnion(a, b, smoothness) => smooth_min(a.distance(point), b.distance(point), *smoothness).0,
+        }
+    }
+
+    // Material at a point on the surface
+    pub fn material(&self, point: &Vec3) -> Material {
+        match self {
+            Sdf::Sphere { material, .. }
+            | Sdf::Box { material, .. }
+            | Sdf::Torus { material, .. }
+            | Sdf::Capsule { material, .. } => *material,
+            Sdf::SmoothUnion(a, b, smoothness) => {
+                let (_, weight) = smooth_min(a.distance(point), b.distance(point), *smoothness);
+                mix(&b.material(point), &a.material(point), weight)
+            }
+        }
+    }
+
+    // Gradient of the distance by central differences, pointing out of the solid
+    pub fn normal(&self, point: &Vec3) -> Vec3 {
+        let axis = |offset: Vec3| self.distance(&(point + offset)) - self.distance(&(point - offset));
+        Vec3::new(
+            axis(Vec3::new(NORMAL_STEP, 0.0, 0.0)),
+            axis(Vec3::new(0.0, NORMAL_STEP, 0.0)),
+            axis(Vec3::new(0.0, 0.0, NORMAL_STEP)),
+        )
+        .normalize()
+    }
+
+    // Steps along the ray until it crosses the surface. `outside` says which
+    // side the march starts on, from inside it looks for the way out.
+    fn march(&self, origin: &Vec3, direction: &Vec3, start: f32, outside: bool) -> Option<f32> {
+        let sign = if outside { 1.0 } else { -1.0 };
+        let mut t = start;
+
+        for _ in 0..MAX_STEPS {
+            let distance = sign * self.distance(&(origin + direction * t));
+            if distance < HIT_DISTANCE {
+                return Some(t);
+            }
+            t += distance;
+            if t > MAX_DISTANCE {
+                return None;
+            }
+        }
+        // Ran out of steps skimming along the surface, close enough to count
+        let distance = sign * self.distance(&(origin + direction * t));
+        (distance < HIT_DISTANCE * 100.0).then_some(t)
+    }
+
+    fn hit_at(&self, origin: &Vec3, direction: &Vec3, t: f32, front_face: bool) -> Intersect {
+        let point = origin + direction * t;
+        let normal = self.normal(&point);
+        let (u, v) = Sphere::get_uv(&normal);
+        Intersect::new(point, normal, t, self.material(&point), u, v, front_face)
+    }
+}
+
+impl RayIntersect for Sdf {
+    fn ray_intersect(&self, origin: &Vec3, direction: &Vec3) -> Intersect {
+        let outside = self.distance(origin) >= 0.0;
+        match self.march(origin, direction, 0.0, outside) {
+            Some(t) => self.hit_at(origin, direction, t, outside),
+            None => Intersect::empty(),
+        }
+    }
+
+    // Marches in and out again for each span. Only looks ahead of the origin:
+    // when it starts inside, the first span is entered right at the origin.
+    fn ray_spans(&self, origin: &Vec3, direction: &Vec3) -> Vec<Span> {
+        let mut spans = Vec::new();
+        let mut enter = if self.distance(origin) < 0.0 {
+            Some(self.hit_at(origin, direction, 0.0, true))
+        } else {
+            None
+        };
+        let mut t = 0.0;
+
+        while spans.len() < MAX_SPANS {
+            let span_enter = match enter.take() {
+                Some(hit) => hit,
+                None => match self.march(origin, direction, t, true) {
+                    Some(t_enter) => self.hit_at(origin, direction, t_enter, true),
+                    None => break,
+                },
+            };
+            // Step just past the surface before looking for the other side
+            let Some(t_exit) = self.march(origin, direction, span_enter.distance + HIT_DISTANCE * 10.0, false) else {
+                break;
+            };
+            spans.push(Span { enter: span_enter, exit: self.hit_at(origin, direction, t_exit, false) });
+            t = t_exit + HIT_DISTANCE * 10.0;
+        }
+
+        spans
+    }
+}
+
+// Polynomial smooth minimum of two distances, and how much of the first one
+// the result is made of (0 to 1), for blending what else they carry
+fn smooth_min(a: f32, b: f32, smoothness: f32) -> (f32, f32) {
+    if smoothness <= 0.0 {
+        return if a < b { (a, 1.0) } else { (b, 0.0) };
+    }
+    let weight = (0.5 + 0.5 * (b - a) / smoothness).clamp(0.0, 1.0);
+    let distance = b + (a - b) * weight - smoothness * weight * (1.0 - weight);
+    (distance, weight)
+}
+
+// Weight 0 is all of `from`, 1 all of `to`
+fn mix(from: &Material, to: &Material, weight: f32) -> Material {
+    let lerp = |a: f32, b: f32| a + (b - a) * weight;
+    Material::new(
+        from.diffuse.lerp(&to.diffuse, weight),
+        lerp(from.specular, to.specular),
+        [
+            lerp(from.albedo[0], to.albedo[0]),
+            lerp(from.albedo[1], to.albedo[1]),
+            lerp(from.albedo[2], to.albedo[2]),
+            lerp(from.albedo[3], to.albedo[3]),
+        ],
+        lerp(from.refraction_index, to.refraction_index),
+    )
+    // Patterns can't be blended, the side with more weight keeps its own
+    .with_pattern(if weight < 0.5 { from.pattern } else { to.pattern })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sphere(z: f32) -> Sdf {
+        Sdf::Sphere {
+            center: Vec3::new(0.0, 0.0, z),
+            radius: 1.0,
+            material: Material::black(),
+        }
+    }
+
+    fn forward() -> Vec3 {
+        Vec3::new(0.0, 0.0, -1.0)
+    }
+
+    #[test]
+    fn test_smooth_min_without_smoothness_is_min() {
+        assert_eq!(smooth_min(1.0, 2.0, 0.0), (1.0, 1.0));
+        assert_eq!(smooth_min(3.0, -2.0, 0.0), (-2.0, 0.0));
+        assert_eq!(smooth_min(1.0, 2.0, -1.0), (1.0, 1.0));
+    }
+
+    #[test]
+    fn test_smooth_min_only_rounds_near_the_seam() {
+        // Further apart than the smoothness, one side wins outright
+        assert_eq!(smooth_min(1.0, 2.5, 0.5), (1.0, 1.0));
+        assert_eq!(smooth_min(2.5, 1.0, 0.5), (1.0, 0.0));
+
+        // Where both are equal it dips a quarter of the smoothness below
+        let (distance, weight) = smooth_min(1.0, 1.0, 0.4);
+        assert!((distance - 0.9).abs() < 1e-6);
+        assert_eq!(weight, 0.5);
+
+        for (a, b) in [(0.3, 0.5), (-0.2, 0.1), (2.0, 1.9)] {
+            let (distance, weight) = smooth_min(a, b, 0.5);
+            let (swapped, swapped_weight) = smooth_min(b, a, 0.5);
+            assert!(distance <= a.min(b), "{} {}", a, b);
+            assert!((distance - swapped).abs() < 1e-6);
+            assert!((weight + swapped_weight - 1.0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_march_finds_the_surface_from_outside() {
+        let t = sphere(-5.0).march(&Vec3::zeros(), &forward(), 0.0, true).unwrap();
+        assert!((t - 4.0).abs() < 1e-4, "{}", t);
+
+        // Starting further along skips nothing in between
+        let t = sphere(-5.0).march(&Vec3::zeros(), &forward(), 2.0, true).unwrap();
+        assert!((t - 4.0).abs() < 1e-4, "{}", t);
+    }
+
+    #[test]
+    fn test_march_finds_the_way_out_from_inside() {
+        let t = sphere(-5.0).march(&Vec3::new(0.0, 0.0, -5.0), &forward(), 0.0, false).unwrap();
+
+        assert!((t - 1.0).abs() < 1e-4, "{}", t);
+    }
+
+    #[test]
+    fn test_march_misses() {
+        // Pointing away, and sideways past the sphere
+        assert!(sphere(-5.0).march(&Vec3::zeros(), &-forward(), 0.0, true).is_none());
+        assert!(sphere(-5.0).march(&Vec3::new(1.5, 0.0, 0.0), &forward(), 0.0, true).is_none());
+        // Past MAX_DISTANCE
+        assert!(sphere(-200.0).march(&Vec3::zeros(), &forward(), 0.0, true).is_none());
+    }
+
+    #[test]
+    fn test_smooth_union_fills_in_between() {
+        let blend = |smoothness: f32| Sdf::SmoothUnion(Box::new(sphere(-5.0)), Box::new(sphere(-7.5)), smoothness);
+        // Between the two spheres, 0.25 away from both
+        let origin = Vec3::new(0.0, 1.5, -6.25);
+        let down = Vec3::new(0.0, -1.0, 0.0);
+
+        assert!(blend(0.0).march(&origin, &down, 0.0, true).is_none());
+        let t = blend(1.0).march(&origin, &down, 0.0, true).unwrap();
+        assert!(t > 0.0 && t < 1.5, "{}", t);
+        // The march stops right on the blended surface
+        let point = origin + down * t;
+        assert!(blend(1.0).distance(&point).abs() < 1e-4);
+    }
+}
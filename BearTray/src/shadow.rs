@@ -0,0 +1,17 @@
+use crate::light::Light;
+use crate::rayintersect::{Intersect, RayIntersect};
+use crate::csg::Shape;
+
+// Hard shadows: whether anything sits between the hit point and the light
+pub fn in_shadow(intersect: &Intersect, light: &Light, objects: &[Shape]) -> bool {
+    let to_light = light.position - intersect.point;
+    let light_distance = to_light.magnitude();
+    let light_dir = to_light / light_distance;
+    let bias = 1e-3;
+    let shadow_ray_origin = intersect.point + intersect.facing_normal() * bias;
+
+    objects.iter().any(|object| {
+        let shadow_intersect = object.ray_intersect(&shadow_ray_origin, &light_dir);
+        shadow_intersect.is_intersecting && shadow_intersect.distance < light_distance
+    })
+}
@@ -1,18 +1,33 @@
+use std::f32::consts::PI;
+
 use nalgebra_glm::{Vec3, dot};
-use crate::rayintersect::{Intersect, Material, RayIntersect};
+use crate::rayintersect::{Intersect, Material, RayIntersect, Span};
 
+#[derive(Debug, Clone)]
 pub struct Sphere {
     pub center: Vec3,
     pub radius: f32,
     pub material: Material,
 }
 
-pub trait Rayintersect {
-    fn ray_intersect(&self, origin: &Vec3, direction: &Vec3) -> Intersect;
-}
+impl Sphere {
+    // Spherical mapping of an outward normal: u goes around the y axis,
+    // v from the south pole (0) to the north pole (1)
+    pub fn get_uv(normal: &Vec3) -> (f32, f32) {
+        let u = 0.5 + normal.z.atan2(normal.x) / (2.0 * PI);
+        let v = 0.5 + normal.y.clamp(-1.0, 1.0).asin() / PI;
+        (u, v)
+    }
 
-impl Rayintersect for Sphere {
-    fn ray_intersect(&self, origin: &Vec3, direction: &Vec3) -> Intersect {
+    fn hit_at(&self, origin: &Vec3, direction: &Vec3, t: f32, front_face: bool) -> Intersect {
+        let point = origin + direction * t;
+        let normal = (point - self.center) / self.radius;
+        let (u, v) = Sphere::get_uv(&normal);
+        Intersect::new(point, normal, t, self.material, u, v, front_face)
+    }
+
+    // Both roots of the ray/sphere equation, nearest first
+    fn roots(&self, origin: &Vec3, direction: &Vec3) -> Option<(f32, f32)> {
         let oc = origin - self.center;
         let a = dot(direction, direction);
         let b = 2.0 * dot(&oc, direction);
@@ -20,23 +35,37 @@ impl Rayintersect for Sphere {
         let discriminant = b * b - 4.0 * a * c;
 
         if discriminant > 0.0 {
-            // Calculate the two possible intersection points
             let sqrt_discriminant = discriminant.sqrt();
-            let t1 = (-b - sqrt_discriminant) / (2.0 * a);
-            let t2 = (-b + sqrt_discriminant) / (2.0 * a);
-
-            // Choose the closest positive intersection point
-            let t = if t1 > 0.0 { t1 } else { t2 };
-
-            if t > 0.0 {
-                let point = origin + direction * t;
-                let normal = (point - self.center).normalize();
-                let distance = t;
+            Some(((-b - sqrt_discriminant) / (2.0 * a), (-b + sqrt_discriminant) / (2.0 * a)))
+        } else {
+            None
+        }
+    }
+}
 
-                return Intersect::new(distance, self.material);
+impl RayIntersect for Sphere {
+    fn ray_intersect(&self, origin: &Vec3, direction: &Vec3) -> Intersect {
+        if let Some((t1, t2)) = self.roots(origin, direction) {
+            // Choose the closest positive intersection point. Only the far
+            // side is in front of rays starting inside.
+            if t1 > 0.0 {
+                return self.hit_at(origin, direction, t1, true);
+            }
+            if t2 > 0.0 {
+                return self.hit_at(origin, direction, t2, false);
             }
         }
 
         Intersect::empty()
     }
+
+    fn ray_spans(&self, origin: &Vec3, direction: &Vec3) -> Vec<Span> {
+        match self.roots(origin, direction) {
+            Some((t1, t2)) => vec![Span {
+                enter: self.hit_at(origin, direction, t1, true),
+                exit: self.hit_at(origin, direction, t2, false),
+            }],
+            None => Vec::new(),
+        }
+    }
 }
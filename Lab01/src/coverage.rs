@@ -0,0 +1,250 @@
+use nalgebra_glm::Vec2;
+use render_core::Framebuffer;
+
+// Scanlines sampled inside each pixel row. Across the row the coverage is
+// exact, so this only limits how smooth nearly horizontal edges get.
+const SUBSCANLINES: usize = 16;
+
+// How much of each pixel the polygons added so far cover, from 0.0 to 1.0.
+// Where polygons overlap the larger coverage wins instead of adding up, so
+// the pieces of a thick stroke paint as one shape without darker seams.
+// Like point and line, a vertex at (x, y) is the center of pixel (x, y).
+pub struct CoverageMask {
+    width: usize,
+    height: usize,
+    values: Vec<f32>,
+    // Rows that have any coverage, to only paint those
+    rows: Option<(usize, usize)>,
+}
+
+impl CoverageMask {
+    pub fn new(width: usize, height: usize) -> Self {
+        CoverageMask {
+            width,
+            height,
+            values: vec![0.0; width * height],
+            rows: None,
+        }
+    }
+
+    // Even-odd filled, where a polygon crosses over itself it leaves a hole
+    pub fn add_polygon(&mut self, points: &[Vec2]) {
+        self.add_polygons(&[points.to_vec()]);
+    }
+
+    // The union of the polygons, each even-odd filled. Unlike adding them one
+    // by one, pieces that each cover part of a pixel add up, so the pieces of
+    // a hairline meet without seams.
+    pub fn add_polygons(&mut self, polygons: &[Vec<Vec2>]) {
+        if self.width == 0 {
+            return;
+        }
+
+        // Pixel (x, y) spans x..x + 1 and y..y + 1 from here on
+        let polygons: Vec<Vec<Vec2>> = polygons
+            .iter()
+            .filter(|points| points.len() >= 3)
+            .map(|points| points.iter().map(|point| point.add_scalar(0.5)).collect())
+            .collect();
+        let min_y = polygons.iter().flatten().map(|point| point.y).fold(f32::INFINITY, f32::min);
+        let max_y = polygons.iter().flatten().map(|point| point.y).fold(f32::NEG_INFINITY, f32::max);
+        if !(min_y < self.height as f32 && max_y > 0.0) {
+            return;
+        }
+        let first_row = min_y.max(0.0) as usize;
+        let last_row = (max_y.ceil() as usize).min(self.height) - 1;
+
+        let mut row = vec![0.0f32; self.width];
+        let mut crossings = Vec::new();
+        let mut spans: Vec<(f32, f32)> = Vec::new();
+        for y in first_row..=last_row {
+            row.fill(0.0);
+
+            for sub in 0..SUBSCANLINES {
+                let scan_y = y as f32 + (sub as f32 + 0.5) / SUBSCANLINES as f32;
+
+                spans.clear();
+                for points in &polygons {
+                    crossings.clear();
+                    let mut j = points.len() - 1;
+                    for i in 0..points.len() {
+                        let (a, b) = (points[i], points[j]);
+                        if (a.y <= scan_y && b.y > scan_y) || (b.y <= scan_y && a.y > scan_y) {
+                            crossings.push(a.x + (scan_y - a.y) / (b.y - a.y) * (b.x - a.x));
+                        }
+                        j = i;
+                    }
+                    crossings.sort_by(f32::total_cmp);
+                    spans.extend(crossings.chunks_exact(2).map(|span| (span[0], span[1])));
+                }
+
+                // Overlapping spans are merged so nothing is counted twice
+                spans.sort_by(|a, b| a.0.total_cmp(&b.0));
+                let mut merged: Option<(f32, f32)> = None;
+                for &(from, to) in &spans {
+                    merged = match merged {
+                        Some((start, end)) if from <= end => Some((start, end.max(to))),
+                        Some((start, end)) => {
+                            self.add_span(&mut row, start, end, 1.0 / SUBSCANLINES as f32);
+                            Some((from, to))
+                        }
+                        None => Some((from, to)),
+                    };
+                }
+                if let Some((start, end)) = merged {
+                    self.add_span(&mut row, start, end, 1.0 / SUBSCANLINES as f32);
+                }
+            }
+
+            let start = y * self.width;
+            for (value, &covered) in self.values[start..start + self.width].iter_mut().zip(&row) {
+                *value = value.max(covered.min(1.0));
+            }
+            self.touch_row(y);
+        }
+    }
+
+    // Coverage of a single pixel. Like polygons, the larger coverage wins,
+    // unless add_up is set: then it is added to what is there, for pixels
+    // that several shapes each cover part of.
+    pub fn add_point(&mut self, x: usize, y: usize, coverage: f32, add_up: bool) {
+        if x >= self.width || y >= self.height || coverage <= 0.0 {
+            return;
+        }
+        let value = &mut self.values[y * self.width + x];
+        *value = if add_up { (*value + coverage).min(1.0) } else { value.max(coverage.min(1.0)) };
+        self.touch_row(y);
+    }
+
+    // Scales all the coverage so far, for shapes thinner than they are drawn
+    pub fn fade(&mut self, factor: f32) {
+        for value in &mut self.values {
+            *value *= factor.clamp(0.0, 1.0);
+        }
+    }
+
+    fn touch_row(&mut self, y: usize) {
+        self.rows = Some(match self.rows {
+            Some((first, last)) => (first.min(y), last.max(y)),
+            None => (y, y),
+        });
+    }
+
+    // Adds weight times the part of each pixel between from and to
+    fn add_span(&self, row: &mut [f32], from: f32, to: f32, weight: f32) {
+        let from = from.clamp(0.0, self.width as f32);
+        let to = to.clamp(0.0, self.width as f32);
+        if to <= from {
+            return;
+        }
+
+        let first = from as usize;
+        let last = to as usize;
+        if first == last {
+            row[first] += (to - from) * weight;
+            return;
+        }
+        row[first] += (first as f32 + 1.0 - from) * weight;
+        for covered in &mut row[first + 1..last] {
+            *covered += weight;
+        }
+        if last < self.width {
+            row[last] += (to - last as f32) * weight;
+        }
+    }
+
+    // Blends the foreground color into every covered pixel
+    pub fn paint(&self, framebuffer: &mut Framebuffer) {
+        let Some((first, last)) = self.rows else {
+            return;
+        };
+        for y in first..=last.min(framebuffer.height.saturating_sub(1)) {
+            for x in 0..self.width.min(framebuffer.width) {
+                framebuffer.blend_point(x, y, self.values[y * self.width + x]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    impl CoverageMask {
+        fn get(&self, x: usize, y: usize) -> f32 {
+            self.values[y * self.width + x]
+        }
+    }
+
+    #[test]
+    fn test_square_on_pixel_centers_half_covers_its_edges() {
+        let mut mask = CoverageMask::new(6, 6);
+
+        mask.add_polygon(&[Vec2::new(1.0, 1.0), Vec2::new(4.0, 1.0), Vec2::new(4.0, 4.0), Vec2::new(1.0, 4.0)]);
+
+        assert_eq!(mask.get(2, 2), 1.0);
+        assert_eq!(mask.get(3, 3), 1.0);
+        assert_eq!(mask.get(1, 2), 0.5);
+        assert_eq!(mask.get(2, 4), 0.5);
+        assert_eq!(mask.get(4, 4), 0.25);
+        assert_eq!(mask.get(0, 0), 0.0);
+        assert_eq!(mask.get(5, 2), 0.0);
+    }
+
+    #[test]
+    fn test_coverage_adds_up_to_the_area() {
+        let mut mask = CoverageMask::new(20, 20);
+        let triangle = [Vec2::new(2.3, 3.1), Vec2::new(15.7, 6.4), Vec2::new(7.2, 17.9)];
+
+        mask.add_polygon(&triangle);
+
+        let area = ((triangle[1] - triangle[0]).perp(&(triangle[2] - triangle[0])) / 2.0).abs();
+        let total: f32 = mask.values.iter().sum();
+        assert!((total - area).abs() < 0.5, "{} vs {}", total, area);
+    }
+
+    #[test]
+    fn test_overlaps_take_the_larger_coverage() {
+        let mut mask = CoverageMask::new(4, 4);
+        let square = [Vec2::new(0.5, 0.5), Vec2::new(2.5, 0.5), Vec2::new(2.5, 2.5), Vec2::new(0.5, 2.5)];
+
+        mask.add_polygon(&square);
+        mask.add_polygon(&square);
+
+        assert_eq!(mask.get(1, 1), 1.0);
+        assert_eq!(mask.get(3, 1), 0.0);
+        assert_eq!(mask.values.iter().sum::<f32>(), 4.0);
+    }
+
+    #[test]
+    fn test_polygons_added_together_fill_their_union() {
+        let mut mask = CoverageMask::new(4, 4);
+        // Two halves of pixel (1, 1), then one overlapping both
+        let left = vec![Vec2::new(0.5, 0.5), Vec2::new(1.0, 0.5), Vec2::new(1.0, 1.5), Vec2::new(0.5, 1.5)];
+        let right = vec![Vec2::new(1.0, 0.5), Vec2::new(1.5, 0.5), Vec2::new(1.5, 1.5), Vec2::new(1.0, 1.5)];
+        let middle = vec![Vec2::new(0.75, 0.5), Vec2::new(1.25, 0.5), Vec2::new(1.25, 1.5), Vec2::new(0.75, 1.5)];
+
+        mask.add_polygons(&[left, right, middle]);
+
+        assert_eq!(mask.get(1, 1), 1.0);
+        assert_eq!(mask.values.iter().sum::<f32>(), 1.0);
+    }
+
+    #[test]
+    fn test_points_add_up_only_when_asked() {
+        let mut mask = CoverageMask::new(4, 4);
+
+        mask.add_point(1, 1, 0.5, false);
+        mask.add_point(1, 1, 0.5, false);
+        mask.add_point(2, 1, 0.5, true);
+        mask.add_point(2, 1, 0.75, true);
+        mask.add_point(9, 1, 1.0, true);
+
+        assert_eq!(mask.get(1, 1), 0.5);
+        assert_eq!(mask.get(2, 1), 1.0);
+
+        mask.fade(0.5);
+        assert_eq!(mask.get(1, 1), 0.25);
+        assert_eq!(mask.get(2, 1), 0.5);
+    }
+}
@@ -1,113 +1,160 @@
-use crate::{bmp::write_bmp_file, line::Vecline};
-use nalgebra_glm as glm;
-use glm::Vec3 as Vec3glm;
-
-pub struct Framebuffer {
-    buffer: Vec<u32>,
-    pub width: usize,
-    pub height: usize,
-    background_color: u32,
-    foreground_color: u32,    
+use nalgebra_glm::{Vec2, Vec3 as Vec3glm};
+use render_core::Framebuffer;
+
+use crate::coverage::CoverageMask;
+use crate::line::Vecline;
+use crate::stroke::Stroke;
+
+// Anti-aliased polygon outlines and fills drawn with the current foreground
+// color. Vertices sit on pixel centers, like point and line.
+pub trait Polygon {
+    // Closed outline. Strokes up to a pixel wide are Wu lines, thinner ones
+    // fainter by their width, wider ones are filled with the stroke's joins
+    // at every corner. Two vertices make a line, drawn with the stroke's caps.
+    fn draw_polygon(&mut self, vertices: &[Vec3glm], stroke: &Stroke);
+    // Open line through the vertices, with caps at both ends
+    fn draw_polyline(&mut self, vertices: &[Vec3glm], stroke: &Stroke);
+    // Even-odd fill, edge pixels blended by how much of them is inside
+    fn fill_polygon(&mut self, vertices: &[Vec3glm]);
 }
 
-impl Framebuffer {
-    pub fn new(width: usize, height: usize) -> Self {
-        let background_color = 0x00FF00;
-        let foreground_color = 0xFF00FF;
-        let buffer = vec![background_color; width * height];
-
-        Self {
-            buffer,
-            width,
-            height,
-            background_color,
-            foreground_color,
-        }
+impl Polygon for Framebuffer {
+    fn draw_polygon(&mut self, vertices: &[Vec3glm], stroke: &Stroke) {
+        draw_path(self, vertices, stroke, vertices.len() > 2);
     }
 
-    pub fn clear(&mut self) {
-        self.buffer = vec![self.background_color; self.width * self.height];
+    fn draw_polyline(&mut self, vertices: &[Vec3glm], stroke: &Stroke) {
+        draw_path(self, vertices, stroke, false);
     }
 
-    pub fn point(&mut self, x: isize, y: isize) {
-        if x >= 0 && y >= 0 && x < self.width as isize && y < self.height as isize {
-            let pix_pos = (y * self.width as isize + x) as usize;
-            self.buffer[pix_pos] = self.foreground_color;
-        }
+    fn fill_polygon(&mut self, vertices: &[Vec3glm]) {
+        let points: Vec<Vec2> = vertices.iter().map(|vertex| vertex.xy()).collect();
+        let mut mask = CoverageMask::new(self.width, self.height);
+        mask.add_polygon(&points);
+        mask.paint(self);
     }
+}
 
-    pub fn set_background_color(&mut self, color: u32) {
-        self.background_color = color;
-        self.clear()
+fn draw_path(framebuffer: &mut Framebuffer, vertices: &[Vec3glm], stroke: &Stroke, closed: bool) {
+    if vertices.is_empty() {
+        return;
     }
 
-    pub fn set_foreground_color(&mut self, color: u32) {
-        self.foreground_color = color;
+    let mut mask = CoverageMask::new(framebuffer.width, framebuffer.height);
+    if stroke.width <= 1.0 {
+        let segments = if closed { vertices.len() } else { vertices.len() - 1 };
+        for i in 0..segments {
+            mask.line(&vertices[i], &vertices[(i + 1) % vertices.len()]);
+        }
+        mask.fade(stroke.width);
+        mask.paint(framebuffer);
+        return;
     }
 
-    pub fn render_buffer(&self, file_path: &str) -> std::io::Result<()> {
-        write_bmp_file(file_path, &self.buffer, self.width, self.height)?;
-        Ok(())
+    // All pieces are filled as one shape, so where they overlap isn't painted
+    // twice and where they meet inside a pixel they add up
+    let points: Vec<Vec2> = vertices.iter().map(|vertex| vertex.xy()).collect();
+    mask.add_polygons(&stroke.outline(&points, closed));
+    mask.paint(framebuffer);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stroke::{LineCap, LineJoin};
+
+    fn canvas() -> Framebuffer {
+        let mut framebuffer = Framebuffer::new(20, 20);
+        framebuffer.set_background_color(0x000000);
+        framebuffer.set_foreground_color(0xFFFFFF);
+        framebuffer
     }
 
-    pub fn draw_polygon(&mut self, vertices: &[Vec3glm]) {
-        if vertices.len() < 2 {
-            return;
-        }
+    fn square() -> Vec<Vec3glm> {
+        vec![
+            Vec3glm::new(5.0, 5.0, 0.0),
+            Vec3glm::new(14.0, 5.0, 0.0),
+            Vec3glm::new(14.0, 14.0, 0.0),
+            Vec3glm::new(5.0, 14.0, 0.0),
+        ]
+    }
 
-        for i in 0..vertices.len() {
-            let start = &vertices[i];
-            let end = if i == vertices.len() - 1 {
-                &vertices[0]
-            } else {
-                &vertices[i + 1]
-            };
-            self.line(start, end);
-        }
+    #[test]
+    fn test_fill_blends_only_the_edges() {
+        let mut framebuffer = canvas();
+        framebuffer.fill_polygon(&square());
+
+        assert_eq!(framebuffer.get_pixel(9, 9), Some(0xFFFFFF));
+        // Edges through pixel centers leave those pixels half inside
+        assert_eq!(framebuffer.get_pixel(5, 9), Some(0x808080));
+        assert_eq!(framebuffer.get_pixel(14, 9), Some(0x808080));
+        assert_eq!(framebuffer.get_pixel(14, 14), Some(0x404040));
+        assert_eq!(framebuffer.get_pixel(4, 9), Some(0x000000));
+
+        // Edges between pixels leave nothing to blend
+        let mut framebuffer = canvas();
+        let between: Vec<Vec3glm> = square().iter().map(|vertex| vertex.add_scalar(0.5)).collect();
+        framebuffer.fill_polygon(&between);
+
+        assert!(framebuffer.buffer.iter().all(|&pixel| pixel == 0x000000 || pixel == 0xFFFFFF));
+        assert_eq!(framebuffer.buffer.iter().filter(|&&pixel| pixel == 0xFFFFFF).count(), 81);
     }
 
-    pub fn fill_polygon(&mut self, vertices: &[Vec3glm]) {
-        let n = vertices.len();
-        if n < 3 {
-            return;
-        }
+    #[test]
+    fn test_thick_corners_follow_the_join() {
+        let corner = |join: LineJoin| {
+            let mut framebuffer = canvas();
+            framebuffer.draw_polygon(&square(), &Stroke::new(4.0).with_join(join));
+            framebuffer.get_pixel(4, 4).unwrap() & 0xFF
+        };
+
+        // The outer corner of the stroke is at (3, 3), past the segments
+        let (miter, round, bevel) = (corner(LineJoin::Miter), corner(LineJoin::Round), corner(LineJoin::Bevel));
+        assert_eq!(miter, 0xFF);
+        assert!(miter > round && round > bevel && bevel > 0, "{} {} {}", miter, round, bevel);
+
+        let mut framebuffer = canvas();
+        framebuffer.draw_polygon(&square(), &Stroke::new(4.0));
+        // Along the edges, two pixels either side of the line
+        assert_eq!(framebuffer.get_pixel(9, 3), Some(0x808080));
+        assert_eq!(framebuffer.get_pixel(9, 4), Some(0xFFFFFF));
+        assert_eq!(framebuffer.get_pixel(9, 7), Some(0x808080));
+        assert_eq!(framebuffer.get_pixel(9, 9), Some(0x000000));
+    }
 
-        let mut min_y = vertices[0].y as usize;
-        let mut max_y = vertices[0].y as usize;
+    #[test]
+    fn test_hairline_corners_are_solid() {
+        let mut framebuffer = canvas();
+        framebuffer.draw_polygon(&square(), &Stroke::new(1.0));
 
-        for vertex in vertices.iter() {
-            if vertex.y  < min_y as f32 {
-                min_y = vertex.y as usize;
-            }
-            if vertex.y as usize > max_y {
-                max_y = vertex.y as usize;
-            }
+        for (x, y) in [(5, 5), (14, 5), (14, 14), (5, 14), (9, 5), (14, 9)] {
+            assert_eq!(framebuffer.get_pixel(x, y), Some(0xFFFFFF), "({}, {})", x, y);
         }
+        assert_eq!(framebuffer.get_pixel(9, 4), Some(0x000000));
+        assert_eq!(framebuffer.get_pixel(9, 9), Some(0x000000));
+    }
 
-        for y in min_y..=max_y {
-            let mut nodes = Vec::new();
-            let mut j = n - 1;
-            for i in 0..n {
-                let vi = &vertices[i];
-                let vj = &vertices[j];
-
-                if (vi.y <= y as f32 && vj.y > y as f32) || (vj.y <= y as f32 && vi.y > y as f32) {
-                    let x = (vi.x + (y as f32 - vi.y) / (vj.y - vi.y) * (vj.x - vi.x)) as usize;
-                    nodes.push(x);
-                }
-                j = i;
-            }
-
-            nodes.sort();
-
-            for k in (0..nodes.len()).step_by(2) {
-                if k + 1 >= nodes.len() {
-                    break;
-                }
-                for x in nodes[k]..=nodes[k + 1] {
-                    self.point(x as isize, y as isize);
-                }
-            }
-        }
+    #[test]
+    fn test_thin_strokes_fade_with_their_width() {
+        let mut framebuffer = canvas();
+        framebuffer.draw_polygon(&square(), &Stroke::new(0.5));
+
+        assert_eq!(framebuffer.get_pixel(9, 5), Some(0x808080));
+        assert_eq!(framebuffer.get_pixel(5, 5), Some(0x808080));
+        assert_eq!(framebuffer.get_pixel(9, 4), Some(0x000000));
+    }
+
+    #[test]
+    fn test_caps_extend_open_lines() {
+        let line = [Vec3glm::new(5.0, 10.0, 0.0), Vec3glm::new(14.0, 10.0, 0.0)];
+        let reach = |cap: LineCap| {
+            let mut framebuffer = canvas();
+            framebuffer.draw_polyline(&line, &Stroke::new(4.0).with_cap(cap));
+            (0..20).filter(|&x| framebuffer.get_pixel(x, 10) != Some(0x000000)).count()
+        };
+
+        assert_eq!(reach(LineCap::Butt), 10);
+        assert_eq!(reach(LineCap::Square), 14);
+        assert_eq!(reach(LineCap::Round), 14);
     }
 }
@@ -1,52 +1,115 @@
-use nalgebra_glm::Vec3 as Vec3glm;
-use crate::framebuffer::Framebuffer;
+use nalgebra_glm::{Vec2, Vec3 as Vec3glm};
+use render_core::Framebuffer;
+
+use crate::coverage::CoverageMask;
 
 pub trait Vecline {
+    // Xiaolin Wu's line, each pixel along the way is blended by how much of
+    // it the one pixel wide line covers
     fn line(&mut self, start: &Vec3glm, end: &Vec3glm);
 }
 
 impl Vecline for Framebuffer {
     fn line(&mut self, start: &Vec3glm, end: &Vec3glm) {
-        let x1 = start.x as usize;
-        let y1 = start.y as usize;
-        let x2 = end.x as usize;
-        let y2 = end.y as usize;
-
-        let dx = if x2 > x1 { x2 - x1 } else { x1 - x2 };
-        let dy = if y2 > y1 { y2 - y1 } else { y1 - y2 };
-        let sx = if x1 < x2 { 1 } else { usize::MAX }; // usize::MAX as a substitute for -1
-        let sy = if y1 < y2 { 1 } else { usize::MAX }; // usize::MAX as a substitute for -1
-        let mut err = if dx > dy { (dx as isize) / 2 } else { -(dy as isize) / 2 };
-
-        let mut x = x1;
-        let mut y = y1;
-
-        loop {
-            self.point(x as isize, y as isize);
-
-            if x == x2 && y == y2 {
-                break;
-            }
-
-            let e2 = err;
-
-            if e2 > -(dx as isize) {
-                err -= dy as isize;
-                if sx == usize::MAX {
-                    x = x.wrapping_sub(1);
-                } else {
-                    x += sx;
-                }
-            }
-
-            if e2 < dy as isize {
-                err += dx as isize;
-                if sy == usize::MAX {
-                    y = y.wrapping_sub(1);
-                } else {
-                    y += sy;
-                }
-            }
+        wu_line(start.xy(), end.xy(), |x, y, coverage, _| self.blend_point(x, y, coverage));
+    }
+}
+
+// Lines of a path go in one mask, so the two that meet at a vertex add up
+// there instead of blending over each other
+impl Vecline for CoverageMask {
+    fn line(&mut self, start: &Vec3glm, end: &Vec3glm) {
+        wu_line(start.xy(), end.xy(), |x, y, coverage, at_end| self.add_point(x, y, coverage, at_end));
+    }
+}
+
+// Calls plot with every pixel the line touches, how much of it is covered
+// and whether it is one of the end pixels, which only get the part of the
+// line that reaches into them
+fn wu_line(start: Vec2, end: Vec2, mut plot: impl FnMut(usize, usize, f32, bool)) {
+    let (mut x0, mut y0, mut x1, mut y1) = (start.x, start.y, end.x, end.y);
+
+    // Walk along the longer axis, left to right
+    let steep = (y1 - y0).abs() > (x1 - x0).abs();
+    if steep {
+        std::mem::swap(&mut x0, &mut y0);
+        std::mem::swap(&mut x1, &mut y1);
+    }
+    if x0 > x1 {
+        std::mem::swap(&mut x0, &mut x1);
+        std::mem::swap(&mut y0, &mut y1);
+    }
+
+    let dx = x1 - x0;
+    let gradient = if dx == 0.0 { 1.0 } else { (y1 - y0) / dx };
+
+    let mut plot = |x: f32, y: f32, coverage: f32, at_end: bool| {
+        let (x, y) = if steep { (y, x) } else { (x, y) };
+        if x >= 0.0 && y >= 0.0 {
+            plot(x as usize, y as usize, coverage, at_end);
+        }
+    };
+
+    let x_start = x0.round();
+    let y_start = y0 + gradient * (x_start - x0);
+    let start_gap = 1.0 - (x0 + 0.5).fract();
+    plot(x_start, y_start.floor(), (1.0 - y_start.fract()) * start_gap, true);
+    plot(x_start, y_start.floor() + 1.0, y_start.fract() * start_gap, true);
+
+    let x_end = x1.round();
+    let y_end = y1 + gradient * (x_end - x1);
+    let end_gap = (x1 + 0.5).fract();
+    plot(x_end, y_end.floor(), (1.0 - y_end.fract()) * end_gap, true);
+    plot(x_end, y_end.floor() + 1.0, y_end.fract() * end_gap, true);
+
+    // In between, the two pixels straddling the line share it
+    let mut y = y_start + gradient;
+    let mut x = x_start + 1.0;
+    while x < x_end {
+        plot(x, y.floor(), 1.0 - y.fract(), false);
+        plot(x, y.floor() + 1.0, y.fract(), false);
+        y += gradient;
+        x += 1.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coverage(framebuffer: &Framebuffer, x: usize, y: usize) -> f32 {
+        (framebuffer.get_pixel(x, y).unwrap() & 0xFF) as f32 / 255.0
+    }
+
+    #[test]
+    fn test_line_on_pixel_centers_is_solid() {
+        let mut framebuffer = Framebuffer::new(8, 3);
+        framebuffer.set_background_color(0x000000);
+        framebuffer.set_foreground_color(0xFFFFFF);
+
+        framebuffer.line(&Vec3glm::new(1.0, 1.0, 0.0), &Vec3glm::new(6.0, 1.0, 0.0));
+
+        for x in 2..=5 {
+            assert_eq!(framebuffer.get_pixel(x, 1), Some(0xFFFFFF), "x {}", x);
+        }
+        // The line starts and ends halfway through the end pixels
+        assert_eq!(framebuffer.get_pixel(1, 1), Some(0x808080));
+        assert_eq!(framebuffer.get_pixel(6, 1), Some(0x808080));
+        assert_eq!(framebuffer.buffer.iter().filter(|&&pixel| pixel != 0).count(), 6);
+    }
+
+    #[test]
+    fn test_line_splits_between_rows() {
+        let mut framebuffer = Framebuffer::new(6, 8);
+        framebuffer.set_background_color(0x000000);
+        framebuffer.set_foreground_color(0xFFFFFF);
+
+        // Steep, so the columns share it, a quarter of the way from 2 to 3
+        framebuffer.line(&Vec3glm::new(2.25, 1.0, 0.0), &Vec3glm::new(2.25, 6.0, 0.0));
+
+        for y in 2..=5 {
+            assert!((coverage(&framebuffer, 2, y) - 0.75).abs() < 0.01, "y {}", y);
+            assert!((coverage(&framebuffer, 3, y) - 0.25).abs() < 0.01, "y {}", y);
         }
     }
 }
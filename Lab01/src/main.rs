@@ -1,9 +1,13 @@
+mod coverage;
 mod framebuffer;
 mod line;
-mod bmp;
+mod stroke;
 
 use nalgebra_glm::Vec3;
-use crate::framebuffer::Framebuffer;
+use render_core::Framebuffer;
+
+use crate::framebuffer::Polygon;
+use crate::stroke::{LineCap, LineJoin, Stroke};
 
 fn main() {
     let mut fb = Framebuffer::new(800, 600);
@@ -12,6 +16,8 @@ fn main() {
     fb.set_background_color(0xccc2ff);
     fb.set_foreground_color(0x000000);
 
+    let outline = Stroke::new(2.0).with_join(LineJoin::Round);
+
     let vertices = vec![
         Vec3::new(165.0, 380.0, 0.0),
         Vec3::new(185.0, 360.0, 0.0),
@@ -28,7 +34,7 @@ fn main() {
     fb.set_foreground_color(0xFFFF00);
     fb.fill_polygon(&vertices);
     fb.set_foreground_color(0xFFFFFF);
-    fb.draw_polygon(&vertices);
+    fb.draw_polygon(&vertices, &outline);
     
 
     let verticespol2 = vec![
@@ -41,7 +47,7 @@ fn main() {
     fb.set_foreground_color(0x0000FF);
     fb.fill_polygon(&verticespol2);
     fb.set_foreground_color(0xFFFFFF);
-    fb.draw_polygon(&verticespol2);
+    fb.draw_polygon(&verticespol2, &outline);
     
     let verticespol3 = vec![
         Vec3::new(377.0, 249.0, 0.0),
@@ -52,7 +58,7 @@ fn main() {
     fb.set_foreground_color(0xFF0000);
     fb.fill_polygon(&verticespol3);
     fb.set_foreground_color(0xFFFFFF);
-    fb.draw_polygon(&verticespol3);
+    fb.draw_polygon(&verticespol3, &outline);
 
 
     let verticespol4 = vec![
@@ -79,7 +85,7 @@ fn main() {
     fb.set_foreground_color(0x00FF00);
     fb.fill_polygon(&verticespol4);
     fb.set_foreground_color(0xFFFFFF);
-    fb.draw_polygon(&verticespol4);
+    fb.draw_polygon(&verticespol4, &outline);
 
 
     let poli5 = vec![
@@ -92,7 +98,41 @@ fn main() {
     fb.set_foreground_color(0xccc2ff);
     fb.fill_polygon(&poli5);
     fb.set_foreground_color(0xFFFFFF);
-    fb.draw_polygon(&poli5);
+    fb.draw_polygon(&poli5, &outline);
+
+    // Open lines under the shapes, to show the caps
+    let ground = vec![
+        Vec3::new(60.0, 540.0, 0.0),
+        Vec3::new(250.0, 480.0, 0.0),
+        Vec3::new(440.0, 540.0, 0.0),
+        Vec3::new(630.0, 480.0, 0.0),
+    ];
+    fb.set_foreground_color(0x4b3f8c);
+    fb.draw_polyline(&ground, &Stroke::new(8.0).with_join(LineJoin::Bevel).with_cap(LineCap::Round));
+
+    let horizon = vec![Vec3::new(60.0, 570.0, 0.0), Vec3::new(740.0, 570.0, 0.0)];
+    fb.draw_polyline(&horizon, &Stroke::new(4.0).with_cap(LineCap::Square));
 
     fb.render_buffer("filled_polygon.bmp").unwrap();
+
+    // With a reference BMP as argument, check the drawing still matches it
+    if let Some(reference_path) = std::env::args().nth(1) {
+        let reference = match Framebuffer::load_bmp(&reference_path) {
+            Ok(reference) => reference,
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        };
+        if (reference.width, reference.height) != (fb.width, fb.height) {
+            eprintln!("{} is {}x{}, the drawing is {}x{}", reference_path, reference.width, reference.height, fb.width, fb.height);
+            std::process::exit(1);
+        }
+
+        let different = fb.buffer.iter().zip(&reference.buffer).filter(|(a, b)| a != b).count();
+        println!("{} of {} pixels differ from {}", different, fb.buffer.len(), reference_path);
+        if different > 0 {
+            std::process::exit(1);
+        }
+    }
 }
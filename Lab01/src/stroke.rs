@@ -0,0 +1,187 @@
+use nalgebra_glm::Vec2;
+
+// Longest a miter join may get, as a multiple of half the stroke width,
+// before it turns into a bevel. Same default as SVG.
+const MITER_LIMIT: f32 = 4.0;
+
+// How the outer corner of two segments is filled in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineJoin {
+    // Edges extended until they meet, up to MITER_LIMIT
+    Miter,
+    Round,
+    // The corner cut off straight
+    Bevel,
+}
+
+// How the ends of an open line look
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineCap {
+    // Stops right at the end point
+    Butt,
+    Round,
+    // Goes on half the width past the end point
+    Square,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stroke {
+    pub width: f32,
+    pub join: LineJoin,
+    pub cap: LineCap,
+}
+
+impl Stroke {
+    pub fn new(width: f32) -> Self {
+        Stroke {
+            width,
+            join: LineJoin::Miter,
+            cap: LineCap::Butt,
+        }
+    }
+
+    pub fn with_join(mut self, join: LineJoin) -> Self {
+        self.join = join;
+        self
+    }
+
+    pub fn with_cap(mut self, cap: LineCap) -> Self {
+        self.cap = cap;
+        self
+    }
+
+    // Polygons that together cover the stroke along points: one quad per
+    // segment, plus the joins between them and the caps of an open line.
+    // They overlap, so they are meant to be filled into one CoverageMask.
+    pub fn outline(&self, points: &[Vec2], closed: bool) -> Vec<Vec<Vec2>> {
+        let half = self.width / 2.0;
+        // Repeated points would give segments without a direction
+        let mut points = points.to_vec();
+        points.dedup();
+        if closed && points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+
+        let mut pieces = Vec::new();
+        if points.is_empty() {
+            return pieces;
+        }
+        if points.len() == 1 {
+            // A dot, drawn the way its caps would draw it
+            match self.cap {
+                LineCap::Butt => {}
+                LineCap::Round => pieces.push(circle(points[0], half)),
+                LineCap::Square => pieces.push(square(points[0], half)),
+            }
+            return pieces;
+        }
+
+        let segments: Vec<(Vec2, Vec2)> = if closed {
+            (0..points.len()).map(|i| (points[i], points[(i + 1) % points.len()])).collect()
+        } else {
+            points.windows(2).map(|pair| (pair[0], pair[1])).collect()
+        };
+
+        for &(start, end) in &segments {
+            let normal = normal(start, end) * half;
+            pieces.push(vec![start + normal, end + normal, end - normal, start - normal]);
+        }
+
+        // A corner between each segment and the next
+        let corners = if closed { segments.len() } else { segments.len() - 1 };
+        for i in 0..corners {
+            let (start, corner) = segments[i];
+            let end = segments[(i + 1) % segments.len()].1;
+            if let Some(join) = self.join_piece(start, corner, end) {
+                pieces.push(join);
+            }
+        }
+
+        if !closed {
+            let (first, second) = segments[0];
+            let (second_last, last) = segments[segments.len() - 1];
+            pieces.extend(self.cap_piece(first, second));
+            pieces.extend(self.cap_piece(last, second_last));
+        }
+
+        pieces
+    }
+
+    // What fills the gap on the outside of the corner from start to end
+    fn join_piece(&self, start: Vec2, corner: Vec2, end: Vec2) -> Option<Vec<Vec2>> {
+        let half = self.width / 2.0;
+        let incoming = (corner - start).normalize();
+        let outgoing = (end - corner).normalize();
+        let turn = incoming.perp(&outgoing);
+        if turn.abs() < 1e-6 && incoming.dot(&outgoing) > 0.0 {
+            // Straight on, the segments already meet
+            return None;
+        }
+
+        // The outside is to the right of a left turn and the other way round
+        let side = if turn > 0.0 { -1.0 } else { 1.0 };
+        let before = corner + normal(start, corner) * half * side;
+        let after = corner + normal(corner, end) * half * side;
+
+        match self.join {
+            LineJoin::Round => Some(circle(corner, half)),
+            LineJoin::Bevel => Some(vec![corner, before, after]),
+            LineJoin::Miter => {
+                let bisector = (before - corner) + (after - corner);
+                // Half the width over how far the tip is from the corner
+                let cos_half_angle = bisector.norm() / (2.0 * half);
+                if cos_half_angle < 1.0 / MITER_LIMIT {
+                    return Some(vec![corner, before, after]);
+                }
+                let tip = corner + bisector.normalize() * (half / cos_half_angle);
+                Some(vec![corner, before, tip, after])
+            }
+        }
+    }
+
+    // The cap at end, of the segment coming from inner
+    fn cap_piece(&self, end: Vec2, inner: Vec2) -> Option<Vec<Vec2>> {
+        let half = self.width / 2.0;
+        match self.cap {
+            LineCap::Butt => None,
+            LineCap::Round => Some(circle(end, half)),
+            LineCap::Square => {
+                let outward = (end - inner).normalize() * half;
+                let normal = normal(inner, end) * half;
+                Some(vec![end + normal, end + normal + outward, end - normal + outward, end - normal])
+            }
+        }
+    }
+}
+
+impl Default for Stroke {
+    fn default() -> Self {
+        Stroke::new(1.0)
+    }
+}
+
+// Unit vector to the left of the direction from start to end
+fn normal(start: Vec2, end: Vec2) -> Vec2 {
+    let direction = (end - start).normalize();
+    Vec2::new(-direction.y, direction.x)
+}
+
+fn circle(center: Vec2, radius: f32) -> Vec<Vec2> {
+    // Enough sides that the flat parts stay under a tenth of a pixel
+    let sides = ((std::f32::consts::PI / (1.0 - 0.1 / radius.max(0.1)).acos()).ceil() as usize).clamp(8, 128);
+    (0..sides)
+        .map(|i| {
+            let angle = i as f32 / sides as f32 * std::f32::consts::TAU;
+            center + Vec2::new(angle.cos(), angle.sin()) * radius
+        })
+        .collect()
+}
+
+fn square(center: Vec2, half: f32) -> Vec<Vec2> {
+    vec![
+        center + Vec2::new(-half, -half),
+        center + Vec2::new(half, -half),
+        center + Vec2::new(half, half),
+        center + Vec2::new(-half, half),
+    ]
+}
@@ -28,14 +28,10 @@ impl GameOfLife {
 
     pub fn update(&mut self) {
         let mut new_grid = self.grid.clone();
-        for y in 0..self.height {
-            for x in 0..self.width {
+        for (y, row) in new_grid.iter_mut().enumerate() {
+            for (x, cell) in row.iter_mut().enumerate() {
                 let live_neighbors = self.live_neighbor_count(x, y);
-                new_grid[y][x] = match (self.grid[y][x], live_neighbors) {
-                    (true, 2) | (true, 3) => true,
-                    (false, 3) => true,
-                    _ => false,
-                };
+                *cell = matches!((self.grid[y][x], live_neighbors), (true, 2) | (true, 3) | (false, 3));
             }
         }
         self.grid = new_grid;
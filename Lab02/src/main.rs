@@ -1,8 +1,5 @@
-use minifb::{Key, Window, WindowOptions};
 use std::time::Duration;
-mod framebuffer;
-use framebuffer::Framebuffer;
-mod bmp;
+use render_core::{screenshot, window, Framebuffer, Key};
 
 mod game_life;
 use game_life::GameOfLife;
@@ -12,7 +9,7 @@ fn render(framebuffer: &mut Framebuffer, game: &GameOfLife) {
     for y in 0..game.height {
         for x in 0..game.width {
             if game.grid[y][x] {
-                framebuffer.point(x as usize, y as usize);
+                framebuffer.point(x, y);
             }
         }
     }
@@ -27,7 +24,7 @@ fn main() {
 
     let frame_delay = Duration::from_millis(36);
 
-    let mut framebuffer = framebuffer::Framebuffer::new(framebuffer_width, framebuffer_height);
+    let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
     let mut game = GameOfLife::new(framebuffer_width, framebuffer_height);
 
     // Manually define initial states for various patterns using set_pattern method
@@ -40,7 +37,7 @@ fn main() {
     game.set_pattern("glider", 13, 1);
     game.set_pattern("glider", 180, 20);
     game.set_pattern("block", 10, 10);
-    game.set_pattern("blinker", 20, c20);
+    game.set_pattern("blinker", 20, 20);
     game.set_pattern("glider", 50, 6);
     game.set_pattern("pulsar", 50, 50);
     game.set_pattern("penta-decathlon", 110, 60);
@@ -87,10 +84,13 @@ fn main() {
     game.set_pattern("glider", 185, 1);
     game.set_pattern("glider", 188, 1);
   
-    let mut window = Window::new(
-        "Rust Graphics - Conway's Game of Life",
-        window_width, window_height, WindowOptions::default()
-    ).unwrap();
+    let mut window = match window::open("Rust Graphics - Conway's Game of Life", window_width, window_height) {
+        Ok(window) => window,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
 
     let mut running = false;
 
@@ -104,7 +104,7 @@ fn main() {
             framebuffer.render_buffer("output.bmp").unwrap();
         }
 
-        if window.is_key_pressed(Key::R, minifb::KeyRepeat::No) {
+        if window.is_key_pressed(Key::R) {
             game.clear();
             // Redefine initial states if needed
             game.set_pattern("glider", 1, 1);
@@ -161,7 +161,7 @@ fn main() {
             
         }
 
-        if window.is_key_pressed(Key::P, minifb::KeyRepeat::No) {
+        if window.is_key_pressed(Key::P) {
             running = !running;
         }
 
@@ -178,9 +178,8 @@ fn main() {
         render(&mut framebuffer, &game);
 
         // Update the window with the framebuffer contents
-        window
-            .update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height)
-            .unwrap();
+        screenshot::handle_hotkey(window.as_ref(), &framebuffer);
+        window.present(&framebuffer);
 
         std::thread::sleep(frame_delay);
     }
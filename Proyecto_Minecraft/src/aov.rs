@@ -0,0 +1,307 @@
+use image::{ImageResult, RgbImage};
+use nalgebra_glm::Vec3;
+use render_core::{Color, Framebuffer};
+
+use crate::castingray::Shading;
+use crate::cube::Cube;
+use crate::gbuffer::GBuffer;
+use crate::hdr::{HdrBuffer, HdrColor, ToneMapper};
+
+// Passes that can be shown in the window instead of the final image
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Aov {
+    Beauty,
+    Depth,
+    Normal,
+    Uv,
+    Albedo,
+    ObjectId,
+    ShadowMask,
+    Direct,
+    Reflection,
+    Refraction,
+}
+
+impl Aov {
+    pub const ALL: [Aov; 10] = [
+        Aov::Beauty,
+        Aov::Depth,
+        Aov::Normal,
+        Aov::Uv,
+        Aov::Albedo,
+        Aov::ObjectId,
+        Aov::ShadowMask,
+        Aov::Direct,
+        Aov::Reflection,
+        Aov::Refraction,
+    ];
+
+    pub fn next(self) -> Aov {
+        let index = Aov::ALL.iter().position(|aov| *aov == self).unwrap_or(0);
+        Aov::ALL[(index + 1) % Aov::ALL.len()]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Aov::Beauty => "beauty",
+            Aov::Depth => "depth",
+            Aov::Normal => "normal",
+            Aov::Uv => "uv",
+            Aov::Albedo => "albedo",
+            Aov::ObjectId => "object_id",
+            Aov::ShadowMask => "shadow",
+            Aov::Direct => "direct",
+            Aov::Reflection => "reflection",
+            Aov::Refraction => "refraction",
+        }
+    }
+}
+
+// Arbitrary output variables of the primary rays. Depth, normal and albedo
+// live in the same GBuffer the denoiser reads.
+pub struct AovBuffers {
+    pub gbuffer: GBuffer,
+    pub uv: Vec<(f32, f32)>,
+    pub object_id: Vec<Option<usize>>,
+    // Fraction of the lights blocked, 0.0 fully lit
+    pub shadow: Vec<f32>,
+    pub direct: Vec<HdrColor>,
+    pub reflection: Vec<HdrColor>,
+    pub refraction: Vec<HdrColor>,
+}
+
+impl AovBuffers {
+    pub fn new(width: usize, height: usize) -> Self {
+        AovBuffers {
+            gbuffer: GBuffer::new(width, height),
+            uv: vec![(0.0, 0.0); width * height],
+            object_id: vec![None; width * height],
+            shadow: vec![0.0; width * height],
+            direct: vec![HdrColor::black(); width * height],
+            reflection: vec![HdrColor::black(); width * height],
+            refraction: vec![HdrColor::black(); width * height],
+        }
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, shading: &Shading, objects: &[Cube]) {
+        if x >= self.gbuffer.width || y >= self.gbuffer.height {
+            return;
+        }
+        let index = y * self.gbuffer.width + x;
+
+        self.shadow[index] = shading.shadow;
+        self.direct[index] = shading.direct;
+        self.reflection[index] = shading.reflection;
+        self.refraction[index] = shading.refraction;
+
+        match &shading.hit {
+            Some((intersect, object_index)) => {
+                let material = &objects[*object_index].material;
+                self.gbuffer.depth[index] = intersect.distance;
+                self.gbuffer.normal[index] = shading.normal;
+                self.gbuffer.albedo[index] = HdrColor::from_srgb(material.get_diffuse(intersect.u, intersect.v));
+                self.uv[index] = (intersect.u, intersect.v);
+                self.object_id[index] = Some(*object_index);
+            }
+            None => {
+                self.gbuffer.depth[index] = f32::INFINITY;
+                self.gbuffer.normal[index] = Vec3::zeros();
+                self.gbuffer.albedo[index] = HdrColor::black();
+                self.uv[index] = (0.0, 0.0);
+                self.object_id[index] = None;
+            }
+        }
+    }
+
+    // Displayable color of a pass. Lighting passes go through the tone mapper
+    // like the final image, data passes are mapped straight to [0, 255].
+    // Beauty lives in the HdrBuffer, not here, and comes out black.
+    fn color_at(&self, aov: Aov, index: usize, max_depth: f32, tone_mapper: &ToneMapper) -> Color {
+        let unit = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+        match aov {
+            Aov::Beauty => Color::black(),
+            Aov::Depth => {
+                // Near is white, far and sky are black
+                let depth = self.gbuffer.depth[index];
+                if depth.is_finite() {
+                    let value = unit(1.0 - depth / max_depth);
+                    Color::new(value, value, value)
+                } else {
+                    Color::black()
+                }
+            }
+            Aov::Normal => {
+                let normal = self.gbuffer.normal[index];
+                if normal == Vec3::zeros() {
+                    Color::black()
+                } else {
+                    Color::new(unit(normal.x * 0.5 + 0.5), unit(normal.y * 0.5 + 0.5), unit(normal.z * 0.5 + 0.5))
+                }
+            }
+            Aov::Uv => {
+                let (u, v) = self.uv[index];
+                Color::new(unit(u), unit(v), 0)
+            }
+            Aov::Albedo => self.gbuffer.albedo[index].to_srgb(),
+            Aov::ObjectId => match self.object_id[index] {
+                Some(id) => id_color(id),
+                None => Color::black(),
+            },
+            Aov::ShadowMask => {
+                let value = unit(1.0 - self.shadow[index]);
+                Color::new(value, value, value)
+            }
+            Aov::Direct => tone_mapper.map(self.direct[index]),
+            Aov::Reflection => tone_mapper.map(self.reflection[index]),
+            Aov::Refraction => tone_mapper.map(self.refraction[index]),
+        }
+    }
+
+    fn max_depth(&self) -> f32 {
+        self.gbuffer
+            .depth
+            .iter()
+            .copied()
+            .filter(|depth| depth.is_finite())
+            .fold(0.0, f32::max)
+            .max(1e-3)
+    }
+
+    pub fn resolve(&self, aov: Aov, framebuffer: &mut Framebuffer, tone_mapper: &ToneMapper) {
+        let max_depth = self.max_depth();
+        for y in 0..self.gbuffer.height.min(framebuffer.height) {
+            for x in 0..self.gbuffer.width.min(framebuffer.width) {
+                let color = self.color_at(aov, y * self.gbuffer.width + x, max_depth, tone_mapper);
+                framebuffer.set_foreground_color(color.to_hex());
+                framebuffer.point(x, y);
+            }
+        }
+    }
+
+    pub fn save_png(&self, aov: Aov, file_path: &str, tone_mapper: &ToneMapper) -> ImageResult<()> {
+        let max_depth = self.max_depth();
+        let width = self.gbuffer.width;
+        let image = RgbImage::from_fn(width as u32, self.gbuffer.height as u32, |x, y| {
+            let color = self.color_at(aov, y as usize * width + x as usize, max_depth, tone_mapper);
+            image::Rgb([color.r, color.g, color.b])
+        });
+        image.save(file_path)
+    }
+
+    // Writes <prefix>_<pass>.png for every pass, beauty taken from hdr_buffer
+    pub fn save_all(&self, hdr_buffer: &HdrBuffer, prefix: &str, tone_mapper: &ToneMapper) -> ImageResult<()> {
+        for aov in Aov::ALL {
+            let file_path = format!("{}_{}.png", prefix, aov.name());
+            if aov == Aov::Beauty {
+                hdr_buffer.save_png(&file_path, tone_mapper)?;
+            } else {
+                self.save_png(aov, &file_path, tone_mapper)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Stable, well separated color per object index
+fn id_color(id: usize) -> Color {
+    let mut hash = (id as u32).wrapping_add(1).wrapping_mul(0x9E37_79B9);
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x85EB_CA6B);
+    hash ^= hash >> 13;
+    Color::new((hash >> 16) as u8, (hash >> 8) as u8, hash as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hdr::ToneOperator;
+    use crate::material::Material;
+    use crate::rayintersect::Intersect;
+
+    fn objects() -> Vec<Cube> {
+        vec![Cube {
+            center: Vec3::zeros(),
+            size: 1.0,
+            material: Material::new(Color::new(255, 0, 0), 0.0, [1.0, 0.0, 0.0, 0.0], 0.0),
+        }]
+    }
+
+    fn shading(hit: Option<(Intersect, usize)>) -> Shading {
+        Shading {
+            color: HdrColor::black(),
+            direct: HdrColor::new(0.5, 0.5, 0.5),
+            reflection: HdrColor::black(),
+            refraction: HdrColor::black(),
+            shadow: 0.25,
+            normal: if hit.is_some() { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::zeros() },
+            hit,
+        }
+    }
+
+    fn hit_at(distance: f32) -> Shading {
+        let intersect = Intersect::new(Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0), distance, Material::black(), 0.25, 0.75, true);
+        shading(Some((intersect, 0)))
+    }
+
+    #[test]
+    fn test_next_cycles_through_every_pass() {
+        let mut aov = Aov::Beauty;
+        let mut seen = Vec::new();
+        for _ in 0..Aov::ALL.len() {
+            seen.push(aov);
+            aov = aov.next();
+        }
+
+        assert_eq!(seen, Aov::ALL.to_vec());
+        assert_eq!(aov, Aov::Beauty);
+    }
+
+    #[test]
+    fn test_sky_clears_the_pixel() {
+        let objects = objects();
+        let tone_mapper = ToneMapper::new(ToneOperator::Clamp, 1.0);
+        let mut buffers = AovBuffers::new(2, 1);
+
+        buffers.set(0, 0, &hit_at(2.0), &objects);
+        assert_eq!(buffers.object_id[0], Some(0));
+        assert_eq!(buffers.uv[0], (0.25, 0.75));
+        assert_eq!(buffers.color_at(Aov::Albedo, 0, 2.0, &tone_mapper), Color::new(255, 0, 0));
+        assert_eq!(buffers.color_at(Aov::Normal, 0, 2.0, &tone_mapper), Color::new(128, 255, 128));
+
+        // The sky overwrites a hit from the previous frame
+        buffers.set(0, 0, &shading(None), &objects);
+        assert!(buffers.gbuffer.depth[0].is_infinite());
+        assert_eq!(buffers.object_id[0], None);
+        assert_eq!(buffers.uv[0], (0.0, 0.0));
+        for aov in [Aov::Depth, Aov::Normal, Aov::Uv, Aov::Albedo, Aov::ObjectId] {
+            assert_eq!(buffers.color_at(aov, 0, 2.0, &tone_mapper), Color::black(), "{}", aov.name());
+        }
+        // Lighting passes still show what was shaded
+        assert_eq!(buffers.color_at(Aov::ShadowMask, 0, 2.0, &tone_mapper), Color::new(191, 191, 191));
+
+        // Out of bounds writes are ignored
+        buffers.set(2, 0, &hit_at(1.0), &objects);
+        assert_eq!(buffers.object_id, vec![None, None]);
+    }
+
+    #[test]
+    fn test_depth_is_scaled_to_the_farthest_hit() {
+        let objects = objects();
+        let tone_mapper = ToneMapper::new(ToneOperator::Clamp, 1.0);
+        let mut buffers = AovBuffers::new(3, 1);
+
+        buffers.set(0, 0, &hit_at(2.0), &objects);
+        buffers.set(1, 0, &hit_at(4.0), &objects);
+        buffers.set(2, 0, &shading(None), &objects);
+
+        // The sky doesn't count toward the farthest depth
+        let max_depth = buffers.max_depth();
+        assert_eq!(max_depth, 4.0);
+        assert_eq!(buffers.color_at(Aov::Depth, 0, max_depth, &tone_mapper), Color::new(128, 128, 128));
+        assert_eq!(buffers.color_at(Aov::Depth, 1, max_depth, &tone_mapper), Color::black());
+
+        // All sky still gives a usable scale
+        assert!(AovBuffers::new(1, 1).max_depth() > 0.0);
+    }
+}
@@ -1,89 +1,189 @@
-use crate::colors::Color;
 use crate::cube::Cube;
+use crate::hdr::HdrColor;
 use crate::light::Light;
+use crate::medium::MediumStack;
 use crate::r_stations::{offset_origin, reflection, refract};
 use crate::rayintersect::{Intersect, RayIntersect};
 use crate::shadow::cast_shadow;
+use crate::skybox::Skybox;
+use crate::stats::{record_depth, record_intersection_tests, record_ray, RayKind};
 use nalgebra_glm::Vec3;
 
+// What a ray saw, split into the parts that make up its color. Primary rays
+// keep this around to fill the AOV buffers, bounces only use `color`.
+pub struct Shading {
+    pub color: HdrColor,
+    // Diffuse and specular from the lights, weighted like in `color`
+    pub direct: HdrColor,
+    pub reflection: HdrColor,
+    pub refraction: HdrColor,
+    // Fraction of the lights that are blocked at the hit point
+    pub shadow: f32,
+    // Normal after normal/bump mapping, zero for the sky
+    pub normal: Vec3,
+    // Hit and index of the cube that was hit, None for the sky
+    pub hit: Option<(Intersect, usize)>,
+}
 
-pub fn cast_ray(origin: &Vec3, direction: &Vec3, objects: &[Cube], lights: &[Light], depth: u32, skybox_color:Color) -> Color {
-    let mut intersect = Intersect::empty();
-    let mut zbuffer = f32::INFINITY;
-    let mut intersected_object: Option<&Cube> = None;
+impl Shading {
+    fn sky(color: HdrColor) -> Self {
+        Shading {
+            color,
+            direct: HdrColor::black(),
+            reflection: HdrColor::black(),
+            refraction: HdrColor::black(),
+            shadow: 0.0,
+            normal: Vec3::zeros(),
+            hit: None,
+        }
+    }
+}
 
-    if depth > 3 {
-        return skybox_color;
+// Returns linear HDR radiance; tone mapping happens when the frame is resolved.
+// `media` holds the transparent volumes the ray origin is inside of.
+pub fn cast_ray(origin: &Vec3, direction: &Vec3, objects: &[Cube], lights: &[Light], depth: u32, skybox: &Skybox, media: &MediumStack) -> HdrColor {
+    shade(origin, direction, objects, lights, depth, skybox, media).color
+}
+
+// cast_ray with the breakdown of the color
+pub fn shade(origin: &Vec3, direction: &Vec3, objects: &[Cube], lights: &[Light], depth: u32, skybox: &Skybox, media: &MediumStack) -> Shading {
+    if depth == 0 {
+        record_ray(RayKind::Primary);
     }
+    // Callers count the ray before calling, so its depth is recorded even when
+    // it is past the limit and ends right here, or the average comes out low
+    record_depth(depth);
 
-    // Loop through objects to find intersections
-    for object in objects {
-        let i = object.ray_intersect(origin, direction);
-        if i.is_intersecting && i.distance < zbuffer {
-            zbuffer = i.distance;
-            intersect = i;
-            intersected_object = Some(object);
-        }
+    if depth > 3 {
+        return Shading::sky(skybox.background());
     }
 
     // If no intersection, return the skybox color
-    if !intersect.is_intersecting {
-        return skybox_color;
-    }
+    let Some((intersect, object_index)) = closest_hit(origin, direction, objects) else {
+        return Shading::sky(skybox.apply_fog(skybox.background(), origin, direction, f32::INFINITY, lights, objects));
+    };
+    let intersected_object = &objects[object_index];
 
     // Get the UV coordinates from the intersected cube
-    let (u, v) = intersected_object.unwrap().get_uv(&intersect.point, &intersect.normal);
+    let (u, v) = intersected_object.get_uv(&intersect.point, &intersect.normal);
     // Get the base diffuse color based on the UV coordinates
-    let diffuse_color = intersected_object.unwrap().material.get_diffuse(u, v);
+    let diffuse_color = HdrColor::from_srgb(intersected_object.material.get_diffuse(u, v));
+    // Lighting and reflections use the mapped normal on the side the ray came
+    // from, offsets and refraction keep the geometric one so rays still leave
+    // from the right side
+    let (tangent, bitangent) = intersected_object.get_tangent_frame(&intersect.normal);
+    let normal = intersected_object.material.shading_normal(u, v, &intersect.facing_normal(), &tangent, &bitangent);
 
-    let mut final_color = Color::black();
+    let mut final_color = HdrColor::black();
+    let mut blocked_lights = 0;
 
     // Calculate contributions from each light source
     for light in lights {
-        let light_dir = (light.position - intersect.point).normalize();
+        let (light_dir, _) = light.direction_from(&intersect.point);
         let view_dir = (origin - intersect.point).normalize();
-        let reflect_dir = reflection(&-light_dir, &intersect.normal).normalize();
+        let reflect_dir = reflection(&-light_dir, &normal).normalize();
         let shadow_intensity = cast_shadow(&intersect, light, objects);
-        let light_intensity = light.intensity * (1.0 - shadow_intensity);
+        if shadow_intensity > 0.0 {
+            blocked_lights += 1;
+        }
+        let light_intensity = light.intensity_at(&intersect.point) * (1.0 - shadow_intensity);
+        let light_color = HdrColor::from_srgb(light.color);
 
-        let diffuse_intensity = intersect.normal.dot(&light_dir).max(0.0);
+        let diffuse_intensity = normal.dot(&light_dir).max(0.0);
 
         // Compute adjusted diffuse color based on light color
-        let adjusted_diffuse_color = Color {
-            r: (diffuse_color.r as f32 * light.color.r as f32 * diffuse_intensity * light_intensity / 255.0).min(255.0) as u8,
-            g: (diffuse_color.g as f32 * light.color.g as f32 * diffuse_intensity * light_intensity / 255.0).min(255.0) as u8,
-            b: (diffuse_color.b as f32 * light.color.b as f32 * diffuse_intensity * light_intensity / 255.0).min(255.0) as u8,
-        };
+        let adjusted_diffuse_color = diffuse_color.modulate(light_color) * (diffuse_intensity * light_intensity);
 
         let specular_intensity = view_dir.dot(&reflect_dir).max(0.0).powf(intersect.material.specular);
-        let specular = Color {
-            r: (light.color.r as f32 * intersect.material.albedo[1] * specular_intensity * light_intensity).min(255.0) as u8,
-            g: (light.color.g as f32 * intersect.material.albedo[1] * specular_intensity * light_intensity).min(255.0) as u8,
-            b: (light.color.b as f32 * intersect.material.albedo[1] * specular_intensity * light_intensity).min(255.0) as u8,
-        };
+        let specular = light_color * (intersect.material.albedo[1] * specular_intensity * light_intensity);
 
         // Combine diffuse and specular contributions
-        final_color = final_color.blend(&adjusted_diffuse_color).blend(&specular);
+        final_color = final_color + adjusted_diffuse_color + specular;
     }
 
-    let mut reflect_color = Color::black();
+    let mut reflect_color = HdrColor::black();
     let reflectivity = intersect.material.albedo[2];
     if reflectivity > 0.0 {
-        let reflect_dir = reflection(&direction, &intersect.normal).normalize();
+        let reflect_dir = reflection(direction, &normal).normalize();
         let reflect_origin = offset_origin(&intersect, &reflect_dir);
-        reflect_color = cast_ray(&reflect_origin, &reflect_dir, objects, lights, depth + 1,skybox_color);
+        record_ray(RayKind::Reflection);
+        reflect_color = cast_ray(&reflect_origin, &reflect_dir, objects, lights, depth + 1, skybox, media);
     }
 
-    let mut refract_color = Color::black();
+    let mut refract_color = HdrColor::black();
     let transparency = intersect.material.albedo[3];
     if transparency > 0.0 {
-        let refract_dir = refract(&direction, &intersect.normal, intersect.material.refraction_index);
+        // Normals point out of the cube, so a ray against the normal is entering
+        let medium = intersect.material.medium();
+        let entering = direction.dot(&intersect.normal) < 0.0;
+        let other_side = if entering {
+            media.entering(object_index, medium)
+        } else {
+            media.leaving(object_index, medium)
+        };
+
+        let refract_dir = refract(
+            direction,
+            &intersect.normal,
+            media.current().refraction_index,
+            other_side.current().refraction_index,
+        );
         let refract_origin = offset_origin(&intersect, &refract_dir);
-        refract_color = cast_ray(&refract_origin, &refract_dir, objects, lights, depth + 1, skybox_color);
+
+        // On total internal reflection the ray stays in the medium it was in
+        let crossed = refract_dir.dot(&intersect.normal).signum() == direction.dot(&intersect.normal).signum();
+        let next_media = if crossed { &other_side } else { media };
+        record_ray(RayKind::Refraction);
+        refract_color = cast_ray(&refract_origin, &refract_dir, objects, lights, depth + 1, skybox, next_media);
+    }
+
+    // Partly transparent texels cover only `alpha` of the surface, the rest
+    // shows whatever is straight behind it
+    let alpha = intersect.material.get_alpha(u, v);
+    let mut behind_color = HdrColor::black();
+    if alpha < 1.0 {
+        let behind_origin = offset_origin(&intersect, direction);
+        record_ray(RayKind::Transmission);
+        behind_color = cast_ray(&behind_origin, direction, objects, lights, depth + 1, skybox, media);
+    }
+
+    let direct = final_color * ((1.0 - reflectivity - transparency).max(0.0) * alpha);
+    let reflection = reflect_color * (reflectivity * alpha);
+    let refraction = refract_color * (transparency * alpha) + behind_color * (1.0 - alpha);
+    let surface_color = direct + reflection + refraction;
+
+    let color = if media.is_empty() {
+        // Travelling through air, the atmosphere sits between origin and hit
+        skybox.apply_fog(surface_color, origin, direction, intersect.distance, lights, objects)
+    } else {
+        // Absorption along the segment from the origin to the hit
+        surface_color.modulate(media.current().transmittance(intersect.distance))
+    };
+
+    Shading {
+        color,
+        direct,
+        reflection,
+        refraction,
+        normal,
+        shadow: if lights.is_empty() { 0.0 } else { blocked_lights as f32 / lights.len() as f32 },
+        hit: Some((intersect, object_index)),
+    }
+}
+
+// Nearest cube along the ray and its index in `objects`, if any
+pub fn closest_hit(origin: &Vec3, direction: &Vec3, objects: &[Cube]) -> Option<(Intersect, usize)> {
+    let mut closest: Option<(Intersect, usize)> = None;
+    let mut zbuffer = f32::INFINITY;
+
+    record_intersection_tests(objects.len());
+    for (index, object) in objects.iter().enumerate() {
+        let i = object.ray_intersect_cutout(origin, direction);
+        if i.is_intersecting && i.distance < zbuffer {
+            zbuffer = i.distance;
+            closest = Some((i, index));
+        }
     }
 
-    return final_color
-        .adjust_brightness(1.0 - reflectivity - transparency)
-        .blend(&reflect_color.adjust_brightness(reflectivity))
-        .blend(&refract_color.adjust_brightness(transparency));
+    closest
 }
@@ -1,12 +1,37 @@
 use nalgebra_glm::Vec3;
 use crate::rayintersect::{Intersect, RayIntersect};
-use crate::material::Material;
+use crate::material::{Material, ALPHA_CUTOFF};
+use crate::r_stations::offset_origin;
 
+#[derive(Clone)]
 pub struct Cube {
     pub center: Vec3,
     pub size: f32,  // Size represents the length of each side of the cube
     pub material: Material,
 }
+impl Cube {
+    // ray_intersect that skips faces where the texture is a cutout. The ray
+    // can then hit the opposite face from inside, with front_face false so
+    // its inner side gets lit, or miss the cube entirely.
+    pub fn ray_intersect_cutout(&self, origin: &Vec3, direction: &Vec3) -> Intersect {
+        let mut intersect = self.ray_intersect(origin, direction);
+        let mut travelled = 0.0;
+
+        // Entry face, then exit face
+        for _ in 0..2 {
+            if !intersect.is_intersecting || self.material.get_alpha(intersect.u, intersect.v) >= ALPHA_CUTOFF {
+                intersect.distance += travelled;
+                return intersect;
+            }
+            travelled += intersect.distance;
+            let next_origin = offset_origin(&intersect, direction);
+            intersect = self.ray_intersect(&next_origin, direction);
+        }
+
+        Intersect::empty()
+    }
+}
+
 impl RayIntersect for Cube {
 
     fn get_uv(&self, point: &Vec3, normal: &Vec3) -> (f32, f32) {
@@ -57,7 +82,17 @@ impl RayIntersect for Cube {
         }
         
     }
-    
+
+    // Follows the same face tests as get_uv
+    fn get_tangent_frame(&self, normal: &Vec3) -> (Vec3, Vec3) {
+        if normal.z.abs() > 0.9 {
+            (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0))
+        } else if normal.y.abs() > 0.0 {
+            (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0))
+        } else {
+            (Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 1.0, 0.0))
+        }
+    }
 
     fn ray_intersect(&self, origin: &Vec3, direction: &Vec3) -> Intersect {
         let half_size = self.size / 2.0;
@@ -74,7 +109,8 @@ impl RayIntersect for Cube {
         let t_far = t2.x.min(t2.y).min(t2.z);
 
         if t_near < t_far && t_far > 0.0 {
-            let distance = t_near;
+            // A ray starting inside the cube (refraction) hits the exit face
+            let distance = if t_near > 0.0 { t_near } else { t_far };
             let point = origin + direction * distance;
             let normal = if point.x > max_bound.x - 0.0001 {
                 Vec3::new(1.0, 0.0, 0.0) // Right face
@@ -92,9 +128,103 @@ impl RayIntersect for Cube {
 
             let (u, v) = self.get_uv(&point, &normal);
 
-            return Intersect::new(point, normal, distance, self.material.clone(), u, v);
+            return Intersect::new(point, normal, distance, self.material.clone(), u, v, t_near > 0.0);
         }
 
         Intersect::empty()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{DynamicImage, RgbaImage};
+    use render_core::Texture;
+    use std::sync::Arc;
+
+    fn cube(material: Material) -> Cube {
+        Cube {
+            center: Vec3::zeros(),
+            size: 0.5,
+            material,
+        }
+    }
+
+    // Holes in the top half of the texture, where the front and back faces
+    // sample, the top and bottom faces sample the opaque bottom half
+    fn holed() -> Material {
+        let image = RgbaImage::from_fn(64, 64, |_, y| image::Rgba([90, 160, 60, if y < 32 { 0 } else { 255 }]));
+        let texture = Arc::new(Texture::from_image(DynamicImage::ImageRgba8(image)));
+        Material::new_with_text(0.0, [1.0, 0.0, 0.0, 0.0], 0.0, texture)
+    }
+
+    #[test]
+    fn test_through_a_hole_the_inside_of_the_far_face_faces_the_ray() {
+        let origin = Vec3::new(0.0, -0.4, 1.0);
+        let direction = Vec3::new(0.0, 0.6, -1.0).normalize();
+
+        let solid = cube(Material::black()).ray_intersect_cutout(&origin, &direction);
+        assert!(solid.front_face);
+        assert_eq!(solid.normal, Vec3::new(0.0, 0.0, 1.0));
+
+        // Goes in through the front face and hits the top face from below
+        let intersect = cube(holed()).ray_intersect_cutout(&origin, &direction);
+        assert!(intersect.is_intersecting);
+        assert!(!intersect.front_face);
+        assert_eq!(intersect.normal, Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(intersect.facing_normal(), Vec3::new(0.0, -1.0, 0.0));
+        assert!(intersect.facing_normal().dot(&direction) < 0.0);
+        assert!(((origin + direction * intersect.distance) - intersect.point).magnitude() < 1e-3);
+    }
+
+    #[test]
+    fn test_from_inside_the_normal_still_points_out() {
+        // Refraction rays start inside and need the outward normal to know
+        // they are leaving, lighting uses the facing one
+        let intersect = cube(Material::black()).ray_intersect_cutout(&Vec3::zeros(), &Vec3::new(1.0, 0.0, 0.0));
+
+        assert!(!intersect.front_face);
+        assert_eq!(intersect.normal, Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(intersect.facing_normal(), Vec3::new(-1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_glass_is_translucent_on_the_faces_and_cut_out_around_them() {
+        let texture = Arc::new(Texture::load("src/assets/glass.png").unwrap());
+        let glass = Material::new_with_text(0.0, [1.0, 0.1, 0.2, 0.6], 1.5, texture);
+
+        // Corner of the cube net, outside every face
+        assert!(glass.get_alpha(0.0, 0.0) < ALPHA_CUTOFF);
+        let front = cube(glass).ray_intersect_cutout(&Vec3::new(0.0, 0.0, 1.0), &Vec3::new(0.0, 0.0, -1.0));
+        let alpha = front.material.get_alpha(front.u, front.v);
+        assert!(front.front_face && (ALPHA_CUTOFF..1.0).contains(&alpha), "{}", alpha);
+    }
+
+    #[test]
+    fn test_tangent_frame_follows_the_uvs() {
+        let cube = Cube {
+            center: Vec3::new(1.0, 2.0, 3.0),
+            size: 0.5,
+            material: Material::black(),
+        };
+        let step = 0.05;
+
+        for axis in 0..3 {
+            for sign in [1.0, -1.0] {
+                let mut normal = Vec3::zeros();
+                normal[axis] = sign;
+                let point = cube.center + normal * 0.25;
+                let (tangent, bitangent) = cube.get_tangent_frame(&normal);
+                let (u, v) = cube.get_uv(&point, &normal);
+
+                let (u_along, v_along) = cube.get_uv(&(point + tangent * step), &normal);
+                assert!(u_along > u && (v_along - v).abs() < 1e-6, "tangent of {:?}", normal);
+
+                let (u_up, v_up) = cube.get_uv(&(point + bitangent * step), &normal);
+                assert!(v_up > v && (u_up - u).abs() < 1e-6, "bitangent of {:?}", normal);
+
+                assert!(tangent.dot(&normal).abs() < 1e-6 && bitangent.dot(&normal).abs() < 1e-6);
+            }
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use crate::gbuffer::GBuffer;
+use crate::hdr::{HdrBuffer, HdrColor};
+
+// Edge-avoiding à-trous wavelet filter (Dammertz et al. 2010). Each pass is
+// a 5x5 B3 spline kernel with holes, the step doubles every iteration so a
+// few passes cover a wide footprint. The normal, depth and albedo buffers
+// stop the blur at geometry and texture edges.
+#[derive(Debug, Clone, Copy)]
+pub struct DenoiseSettings {
+    pub iterations: u32,
+    // Color distance tolerance, halved every iteration
+    pub sigma_color: f32,
+    // Exponent on the normal dot product, higher keeps creases sharper
+    pub sigma_normal: f32,
+    // Depth difference tolerance relative to the pixel's own depth
+    pub sigma_depth: f32,
+    pub sigma_albedo: f32,
+}
+
+impl DenoiseSettings {
+    pub fn new() -> Self {
+        DenoiseSettings {
+            iterations: 5,
+            sigma_color: 0.6,
+            sigma_normal: 64.0,
+            sigma_depth: 0.05,
+            sigma_albedo: 0.1,
+        }
+    }
+}
+
+impl Default for DenoiseSettings {
+    fn default() -> Self {
+        DenoiseSettings::new()
+    }
+}
+
+const KERNEL: [f32; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+pub fn denoise(hdr_buffer: &mut HdrBuffer, gbuffer: &GBuffer, settings: &DenoiseSettings) {
+    assert_eq!(
+        (hdr_buffer.width, hdr_buffer.height),
+        (gbuffer.width, gbuffer.height),
+        "the G-buffer must match the image size"
+    );
+    let width = gbuffer.width;
+    let height = gbuffer.height;
+
+    // Filter lighting only: dividing out the albedo keeps texture detail
+    // from being blurred together with the noise
+    let mut irradiance: Vec<HdrColor> = (0..width * height)
+        .map(|index| demodulate(hdr_buffer.buffer[index], gbuffer.albedo[index]))
+        .collect();
+    let mut filtered = irradiance.clone();
+
+    for iteration in 0..settings.iterations {
+        let step = 1isize << iteration;
+        let sigma_color = settings.sigma_color / (1u32 << iteration) as f32;
+
+        for y in 0..height {
+            for x in 0..width {
+                let index = y * width + x;
+                filtered[index] = filter_pixel(&irradiance, gbuffer, x, y, step, sigma_color, settings);
+            }
+        }
+        std::mem::swap(&mut irradiance, &mut filtered);
+    }
+
+    for ((pixel, color), albedo) in hdr_buffer.buffer.iter_mut().zip(&irradiance).zip(&gbuffer.albedo) {
+        *pixel = remodulate(*color, *albedo);
+    }
+}
+
+fn filter_pixel(
+    irradiance: &[HdrColor],
+    gbuffer: &GBuffer,
+    x: usize,
+    y: usize,
+    step: isize,
+    sigma_color: f32,
+    settings: &DenoiseSettings,
+) -> HdrColor {
+    let (width, height) = (gbuffer.width, gbuffer.height);
+    let center = y * width + x;
+    let center_color = irradiance[center];
+
+    // Sky pixels have nothing to guide the filter and no noise either
+    if gbuffer.depth[center].is_infinite() {
+        return center_color;
+    }
+
+    let mut sum = HdrColor::black();
+    let mut total_weight = 0.0;
+
+    for (ky, kernel_y) in KERNEL.iter().enumerate() {
+        for (kx, kernel_x) in KERNEL.iter().enumerate() {
+            let sx = x as isize + (kx as isize - 2) * step;
+            let sy = y as isize + (ky as isize - 2) * step;
+            if sx < 0 || sy < 0 || sx >= width as isize || sy >= height as isize {
+                continue;
+            }
+            let sample = sy as usize * width + sx as usize;
+            if gbuffer.depth[sample].is_infinite() {
+                continue;
+            }
+
+            let color = irradiance[sample];
+            let color_weight = (-color_distance(center_color, color) / (sigma_color * sigma_color).max(1e-6)).exp();
+
+            let normal_weight = gbuffer.normal[center].dot(&gbuffer.normal[sample]).max(0.0).powf(settings.sigma_normal);
+
+            let depth_difference = (gbuffer.depth[center] - gbuffer.depth[sample]).abs();
+            let depth_scale = settings.sigma_depth * gbuffer.depth[center] * step as f32;
+            let depth_weight = (-depth_difference / depth_scale.max(1e-6)).exp();
+
+            let albedo_distance = color_distance(gbuffer.albedo[center], gbuffer.albedo[sample]);
+            let albedo_weight = (-albedo_distance / (settings.sigma_albedo * settings.sigma_albedo)).exp();
+
+            let weight = kernel_x * kernel_y * color_weight * normal_weight * depth_weight * albedo_weight;
+            sum = sum + color * weight;
+            total_weight += weight;
+        }
+    }
+
+    if total_weight > 0.0 {
+        sum * (1.0 / total_weight)
+    } else {
+        center_color
+    }
+}
+
+fn color_distance(a: HdrColor, b: HdrColor) -> f32 {
+    let (dr, dg, db) = (a.r - b.r, a.g - b.g, a.b - b.b);
+    dr * dr + dg * dg + db * db
+}
+
+// Channels with (nearly) black albedo are left as they are
+fn demodulate(color: HdrColor, albedo: HdrColor) -> HdrColor {
+    let divide = |c: f32, a: f32| if a > 1e-3 { c / a } else { c };
+    HdrColor::new(divide(color.r, albedo.r), divide(color.g, albedo.g), divide(color.b, albedo.b))
+}
+
+fn remodulate(color: HdrColor, albedo: HdrColor) -> HdrColor {
+    let multiply = |c: f32, a: f32| if a > 1e-3 { c * a } else { c };
+    HdrColor::new(multiply(color.r, albedo.r), multiply(color.g, albedo.g), multiply(color.b, albedo.b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::Vec3;
+
+    const SIZE: usize = 16;
+
+    fn gray(value: f32) -> HdrColor {
+        HdrColor::new(value, value, value)
+    }
+
+    // One flat white wall facing the camera at depth 1
+    fn wall() -> GBuffer {
+        let mut gbuffer = GBuffer::new(SIZE, SIZE);
+        gbuffer.depth.fill(1.0);
+        gbuffer.normal.fill(Vec3::new(0.0, 0.0, 1.0));
+        gbuffer.albedo.fill(gray(1.0));
+        gbuffer
+    }
+
+    fn image(color: impl Fn(usize, usize) -> HdrColor) -> HdrBuffer {
+        let mut hdr_buffer = HdrBuffer::new(SIZE, SIZE);
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                hdr_buffer.set(x, y, color(x, y));
+            }
+        }
+        hdr_buffer
+    }
+
+    fn variance(hdr_buffer: &HdrBuffer) -> f32 {
+        let count = hdr_buffer.buffer.len() as f32;
+        let mean = hdr_buffer.buffer.iter().map(|color| color.r).sum::<f32>() / count;
+        hdr_buffer.buffer.iter().map(|color| (color.r - mean).powi(2)).sum::<f32>() / count
+    }
+
+    #[test]
+    fn test_flat_noise_is_smoothed() {
+        // Deterministic speckle around 0.5
+        let mut hdr_buffer = image(|x, y| gray(0.5 + 0.1 * (((x * 7 + y * 13) % 5) as f32 - 2.0) / 2.0));
+        let before = variance(&hdr_buffer);
+
+        denoise(&mut hdr_buffer, &wall(), &DenoiseSettings::new());
+
+        assert!(variance(&hdr_buffer) < before * 0.1, "{} -> {}", before, variance(&hdr_buffer));
+    }
+
+    #[test]
+    fn test_edges_in_the_gbuffer_are_not_blurred_across() {
+        // Left and right halves lit differently, close enough in color that
+        // only the G-buffer keeps them apart
+        let halves = || image(|x, _| gray(if x < SIZE / 2 { 0.4 } else { 0.6 }));
+        let edge_pixel = |gbuffer: &GBuffer| {
+            let mut hdr_buffer = halves();
+            denoise(&mut hdr_buffer, gbuffer, &DenoiseSettings::new());
+            hdr_buffer.buffer[SIZE / 2 * SIZE + SIZE / 2 - 1].r
+        };
+
+        // Without an edge in the G-buffer the halves bleed into each other
+        assert!(edge_pixel(&wall()) > 0.42, "{}", edge_pixel(&wall()));
+
+        let right_half = |index: usize| index % SIZE >= SIZE / 2;
+        let mut depth_edge = wall();
+        let mut normal_edge = wall();
+        let mut albedo_edge = wall();
+        for index in (0..SIZE * SIZE).filter(|&index| right_half(index)) {
+            depth_edge.depth[index] = 3.0;
+            normal_edge.normal[index] = Vec3::new(1.0, 0.0, 0.0);
+            // Same lighting on the right, just on a darker surface
+            albedo_edge.albedo[index] = gray(0.5);
+        }
+
+        for (name, gbuffer) in [("depth", depth_edge), ("normal", normal_edge), ("albedo", albedo_edge)] {
+            let mut hdr_buffer = halves();
+            if name == "albedo" {
+                for index in (0..SIZE * SIZE).filter(|&index| right_half(index)) {
+                    hdr_buffer.buffer[index] = gray(0.3);
+                }
+            }
+            denoise(&mut hdr_buffer, &gbuffer, &DenoiseSettings::new());
+
+            let left = hdr_buffer.buffer[SIZE / 2 * SIZE + SIZE / 2 - 1].r;
+            assert!((left - 0.4).abs() < 0.005, "{} edge: {}", name, left);
+        }
+    }
+
+    #[test]
+    fn test_sky_passes_through() {
+        let mut gbuffer = wall();
+        for y in 0..SIZE / 2 {
+            for x in 0..SIZE {
+                gbuffer.depth[y * SIZE + x] = f32::INFINITY;
+                gbuffer.normal[y * SIZE + x] = Vec3::zeros();
+                gbuffer.albedo[y * SIZE + x] = HdrColor::black();
+            }
+        }
+        let original = image(|x, y| gray(0.2 + 0.05 * ((x + y) % 3) as f32));
+        let mut hdr_buffer = image(|x, y| gray(0.2 + 0.05 * ((x + y) % 3) as f32));
+
+        denoise(&mut hdr_buffer, &gbuffer, &DenoiseSettings::new());
+
+        assert_eq!(hdr_buffer.buffer[..SIZE * SIZE / 2], original.buffer[..SIZE * SIZE / 2]);
+        // The ground below still gets filtered, without pulling in the sky
+        assert_ne!(hdr_buffer.buffer[SIZE * SIZE / 2..], original.buffer[SIZE * SIZE / 2..]);
+    }
+}
@@ -0,0 +1,83 @@
+use nalgebra_glm::Vec3;
+use render_core::Camera;
+
+use crate::castingray::closest_hit;
+use crate::cube::Cube;
+use crate::hdr::HdrColor;
+use crate::offline::{sample_primary_ray, OfflineSettings};
+
+// Per-pixel surface data of the primary hits, used to guide the denoiser
+pub struct GBuffer {
+    pub width: usize,
+    pub height: usize,
+    // Distance from the camera, f32::INFINITY where the ray hits the sky
+    pub depth: Vec<f32>,
+    pub normal: Vec<Vec3>,
+    // Linear texture/diffuse color without any lighting
+    pub albedo: Vec<HdrColor>,
+}
+
+impl GBuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        GBuffer {
+            width,
+            height,
+            depth: vec![f32::INFINITY; width * height],
+            normal: vec![Vec3::zeros(); width * height],
+            albedo: vec![HdrColor::black(); width * height],
+        }
+    }
+}
+
+// Fills the buffer from the same primary rays render_offline uses with these
+// settings, averaged over the samples. Only the first hit is looked at, so
+// this is cheap next to the render itself.
+pub fn capture_gbuffer<C, A>(gbuffer: &mut GBuffer, objects: &[Cube], settings: &OfflineSettings, camera_at: C, animate: A)
+where
+    C: Fn(f32) -> Camera,
+    A: Fn(&mut [Cube], f32),
+{
+    let width = gbuffer.width as f32;
+    let height = gbuffer.height as f32;
+    let size = gbuffer.width * gbuffer.height;
+
+    let mut depth_sum = vec![0.0; size];
+    let mut hits = vec![0u32; size];
+    let mut normal_sum = vec![Vec3::zeros(); size];
+    let mut albedo_sum = vec![HdrColor::black(); size];
+    let mut scene = objects.to_vec();
+
+    for sample in 0..settings.samples {
+        let time = settings.sample_time(sample);
+        let camera = camera_at(time);
+        scene.clone_from_slice(objects);
+        animate(&mut scene, time);
+
+        for y in 0..gbuffer.height {
+            for x in 0..gbuffer.width {
+                let (origin, direction) = sample_primary_ray(&camera, x, y, sample, width, height);
+                if let Some((intersect, object_index)) = closest_hit(&origin, &direction, &scene) {
+                    let object = &scene[object_index];
+                    let index = y * gbuffer.width + x;
+                    depth_sum[index] += intersect.distance;
+                    normal_sum[index] += intersect.normal;
+                    albedo_sum[index] = albedo_sum[index] + HdrColor::from_srgb(object.material.get_diffuse(intersect.u, intersect.v));
+                    hits[index] += 1;
+                }
+            }
+        }
+    }
+
+    for index in 0..size {
+        if hits[index] == 0 {
+            gbuffer.depth[index] = f32::INFINITY;
+            gbuffer.normal[index] = Vec3::zeros();
+            gbuffer.albedo[index] = HdrColor::black();
+            continue;
+        }
+        let weight = 1.0 / hits[index] as f32;
+        gbuffer.depth[index] = depth_sum[index] * weight;
+        gbuffer.normal[index] = normal_sum[index].try_normalize(1e-6).unwrap_or_else(Vec3::zeros);
+        gbuffer.albedo[index] = albedo_sum[index] * weight;
+    }
+}
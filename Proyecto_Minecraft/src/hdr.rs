@@ -0,0 +1,253 @@
+use std::ops::{Add, Mul};
+
+use image::{ImageResult, RgbImage};
+use render_core::{Color, Framebuffer};
+
+// Linear, unclamped radiance. Lighting is accumulated in this type so that
+// several lights and bounces can add up past 1.0 without losing energy.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HdrColor {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+}
+
+impl HdrColor {
+    pub const fn new(r: f32, g: f32, b: f32) -> Self {
+        HdrColor { r, g, b }
+    }
+
+    pub const fn black() -> Self {
+        HdrColor { r: 0.0, g: 0.0, b: 0.0 }
+    }
+
+    // Decode an 8-bit sRGB color (textures, skybox, light colors) to linear
+    pub fn from_srgb(color: Color) -> Self {
+        HdrColor {
+            r: srgb_to_linear(color.r),
+            g: srgb_to_linear(color.g),
+            b: srgb_to_linear(color.b),
+        }
+    }
+
+    // Encode to 8-bit sRGB, clamping anything outside [0, 1]
+    pub fn to_srgb(self) -> Color {
+        Color::new(
+            linear_to_srgb(self.r),
+            linear_to_srgb(self.g),
+            linear_to_srgb(self.b),
+        )
+    }
+
+    // Component-wise product, used to tint light by a surface color
+    pub fn modulate(self, other: HdrColor) -> HdrColor {
+        HdrColor::new(self.r * other.r, self.g * other.g, self.b * other.b)
+    }
+}
+
+impl Add for HdrColor {
+    type Output = HdrColor;
+
+    fn add(self, other: HdrColor) -> HdrColor {
+        HdrColor::new(self.r + other.r, self.g + other.g, self.b + other.b)
+    }
+}
+
+impl Mul<f32> for HdrColor {
+    type Output = HdrColor;
+
+    fn mul(self, factor: f32) -> HdrColor {
+        HdrColor::new(self.r * factor, self.g * factor, self.b * factor)
+    }
+}
+
+fn srgb_to_linear(value: u8) -> f32 {
+    let c = value as f32 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> u8 {
+    let c = value.clamp(0.0, 1.0);
+    let encoded = if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    };
+    (encoded * 255.0).round() as u8
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToneOperator {
+    Clamp,
+    Reinhard,
+    Aces,
+    Exposure,
+}
+
+impl ToneOperator {
+    pub fn next(self) -> ToneOperator {
+        match self {
+            ToneOperator::Clamp => ToneOperator::Reinhard,
+            ToneOperator::Reinhard => ToneOperator::Aces,
+            ToneOperator::Aces => ToneOperator::Exposure,
+            ToneOperator::Exposure => ToneOperator::Clamp,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ToneOperator::Clamp => "Clamp",
+            ToneOperator::Reinhard => "Reinhard",
+            ToneOperator::Aces => "ACES",
+            ToneOperator::Exposure => "Exposure",
+        }
+    }
+
+    fn apply(self, c: f32) -> f32 {
+        match self {
+            ToneOperator::Clamp => c,
+            ToneOperator::Reinhard => c / (1.0 + c),
+            // Narkowicz's fit of the ACES filmic curve
+            ToneOperator::Aces => (c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14),
+            ToneOperator::Exposure => 1.0 - (-c).exp(),
+        }
+    }
+}
+
+// Maps HDR radiance to displayable sRGB: exposure scale, tone curve, gamma
+#[derive(Debug, Clone, Copy)]
+pub struct ToneMapper {
+    pub operator: ToneOperator,
+    pub exposure: f32,
+}
+
+impl ToneMapper {
+    pub fn new(operator: ToneOperator, exposure: f32) -> Self {
+        ToneMapper { operator, exposure }
+    }
+
+    pub fn map(&self, color: HdrColor) -> Color {
+        let exposed = color * self.exposure;
+        HdrColor::new(
+            self.operator.apply(exposed.r.max(0.0)),
+            self.operator.apply(exposed.g.max(0.0)),
+            self.operator.apply(exposed.b.max(0.0)),
+        )
+        .to_srgb()
+    }
+}
+
+// Floating point render target. The ray tracer writes linear radiance here
+// and `resolve` tone maps it into the u32 framebuffer shown by minifb, so
+// changing exposure or operator doesn't require casting the rays again.
+pub struct HdrBuffer {
+    pub buffer: Vec<HdrColor>,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl HdrBuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        HdrBuffer {
+            buffer: vec![HdrColor::black(); width * height],
+            width,
+            height,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.buffer.fill(HdrColor::black());
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, color: HdrColor) {
+        if x < self.width && y < self.height {
+            self.buffer[y * self.width + x] = color;
+        }
+    }
+
+    pub fn resolve(&self, framebuffer: &mut Framebuffer, tone_mapper: &ToneMapper) {
+        for y in 0..self.height.min(framebuffer.height) {
+            for x in 0..self.width.min(framebuffer.width) {
+                let color = tone_mapper.map(self.buffer[y * self.width + x]);
+                framebuffer.set_foreground_color(color.to_hex());
+                framebuffer.point(x, y);
+            }
+        }
+    }
+
+    pub fn save_png(&self, file_path: &str, tone_mapper: &ToneMapper) -> ImageResult<()> {
+        let image = RgbImage::from_fn(self.width as u32, self.height as u32, |x, y| {
+            let color = tone_mapper.map(self.buffer[y as usize * self.width + x as usize]);
+            image::Rgb([color.r, color.g, color.b])
+        });
+        image.save(file_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPERATORS: [ToneOperator; 4] = [ToneOperator::Clamp, ToneOperator::Reinhard, ToneOperator::Aces, ToneOperator::Exposure];
+
+    #[test]
+    fn test_srgb_round_trip() {
+        for value in 0..=255u8 {
+            let color = Color::new(value, value / 2, 255 - value);
+            assert_eq!(HdrColor::from_srgb(color).to_srgb(), color);
+        }
+        assert_eq!(HdrColor::from_srgb(Color::white()), HdrColor::new(1.0, 1.0, 1.0));
+        // Mid grey in sRGB is about a fifth of the light
+        assert!((HdrColor::from_srgb(Color::new(128, 128, 128)).r - 0.2158).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_to_srgb_clamps_out_of_range() {
+        assert_eq!(HdrColor::new(-1.0, 7.5, f32::INFINITY).to_srgb(), Color::new(0, 255, 255));
+    }
+
+    #[test]
+    fn test_operators_at_zero_one_and_large() {
+        for operator in OPERATORS {
+            assert_eq!(operator.apply(0.0).abs(), 0.0, "{}", operator.name());
+            // Very bright light ends up white with every curve
+            let bright = ToneMapper::new(operator, 1.0).map(HdrColor::new(1000.0, 1000.0, 1000.0));
+            assert_eq!(bright, Color::white(), "{}", operator.name());
+        }
+        // Reinhard and exposure only get there in the limit, the ACES fit
+        // overshoots slightly and relies on the clamp when encoding
+        assert!(ToneOperator::Reinhard.apply(1000.0) < 1.0);
+        assert!(ToneOperator::Exposure.apply(20.0) <= 1.0);
+        assert!((ToneOperator::Aces.apply(1000.0) - 2.51 / 2.43).abs() < 0.001);
+
+        assert_eq!(ToneOperator::Clamp.apply(1.0), 1.0);
+        assert_eq!(ToneOperator::Reinhard.apply(1.0), 0.5);
+        assert!((ToneOperator::Aces.apply(1.0) - 0.8038).abs() < 0.001);
+        assert!((ToneOperator::Exposure.apply(1.0) - 0.6321).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_tone_mapper_applies_exposure_before_the_curve() {
+        let white = HdrColor::new(1.0, 1.0, 1.0);
+
+        assert_eq!(ToneMapper::new(ToneOperator::Clamp, 1.0).map(white), Color::white());
+        assert_eq!(ToneMapper::new(ToneOperator::Clamp, 0.0).map(white), Color::black());
+        assert_eq!(ToneMapper::new(ToneOperator::Clamp, 1.0).map(HdrColor::new(50.0, -2.0, 0.0)), Color::new(255, 0, 0));
+        // Reinhard of 1.0 is 0.5 linear, 188 once encoded
+        assert_eq!(ToneMapper::new(ToneOperator::Reinhard, 1.0).map(white), Color::new(188, 188, 188));
+        assert_eq!(ToneMapper::new(ToneOperator::Reinhard, 0.5).map(white * 2.0), Color::new(188, 188, 188));
+    }
+
+    #[test]
+    fn test_next_visits_every_operator() {
+        let mut operator = ToneOperator::Clamp;
+        for expected in OPERATORS.iter().cycle().skip(1).take(4) {
+            operator = operator.next();
+            assert_eq!(operator, *expected);
+        }
+    }
+}
@@ -1,10 +1,35 @@
 use nalgebra_glm::Vec3;
-use crate::colors::Color;
+use render_core::Color;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Falloff {
+    // Same intensity at any distance
+    None,
+    // Physically based, 1 / distance²
+    InverseSquare,
+    // Smoothly fades to nothing at the given distance
+    Range(f32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightKind {
+    // Shines in every direction from its position
+    Point,
+    // Sun-like, parallel rays travelling along the direction from infinitely far away
+    Directional(Vec3),
+    // Point light limited to a cone around the direction. Full intensity
+    // inside inner_angle, fading to zero at outer_angle (radians, from the axis).
+    Spot { direction: Vec3, inner_angle: f32, outer_angle: f32 },
+}
+
+#[derive(Debug, Clone)]
 pub struct Light {
+    // Unused by directional lights
     pub position: Vec3,
     pub color: Color,
     pub intensity: f32,
+    pub kind: LightKind,
+    pub falloff: Falloff,
 }
 
 impl Light {
@@ -13,6 +38,155 @@ impl Light {
             position,
             color,
             intensity,
+            kind: LightKind::Point,
+            falloff: Falloff::None,
+        }
+    }
+
+    pub fn directional(direction: Vec3, color: Color, intensity: f32) -> Self {
+        Light {
+            position: Vec3::zeros(),
+            color,
+            intensity,
+            kind: LightKind::Directional(direction.normalize()),
+            falloff: Falloff::None,
+        }
+    }
+
+    pub fn spot(position: Vec3, direction: Vec3, inner_angle: f32, outer_angle: f32, color: Color, intensity: f32) -> Self {
+        Light {
+            position,
+            color,
+            intensity,
+            kind: LightKind::Spot {
+                direction: direction.normalize(),
+                inner_angle,
+                outer_angle: outer_angle.max(inner_angle),
+            },
+            falloff: Falloff::None,
         }
     }
-}
\ No newline at end of file
+
+    pub fn with_falloff(mut self, falloff: Falloff) -> Self {
+        self.falloff = falloff;
+        self
+    }
+
+    // Unit direction from the point toward the light and the distance to it,
+    // f32::INFINITY for directional lights
+    pub fn direction_from(&self, point: &Vec3) -> (Vec3, f32) {
+        match self.kind {
+            LightKind::Directional(direction) => (-direction, f32::INFINITY),
+            LightKind::Point | LightKind::Spot { .. } => {
+                let to_light = self.position - point;
+                let distance = to_light.magnitude();
+                (to_light / distance.max(1e-6), distance)
+            }
+        }
+    }
+
+    // Intensity arriving at the point before shadows: falloff with distance
+    // and, for spot lights, the cone
+    pub fn intensity_at(&self, point: &Vec3) -> f32 {
+        let (light_dir, distance) = self.direction_from(point);
+
+        let attenuation = match self.falloff {
+            _ if distance.is_infinite() => 1.0,
+            Falloff::None => 1.0,
+            Falloff::InverseSquare => 1.0 / (distance * distance).max(1e-2),
+            Falloff::Range(range) => {
+                let ratio = distance / range.max(1e-6);
+                (1.0 - ratio.powi(4)).clamp(0.0, 1.0).powi(2)
+            }
+        };
+
+        let cone = match self.kind {
+            LightKind::Spot { direction, inner_angle, outer_angle } => {
+                let cos_angle = (-light_dir).dot(&direction);
+                let (cos_inner, cos_outer) = (inner_angle.cos(), outer_angle.cos());
+                if cos_inner - cos_outer < 1e-6 {
+                    if cos_angle >= cos_outer { 1.0 } else { 0.0 }
+                } else {
+                    let t = ((cos_angle - cos_outer) / (cos_inner - cos_outer)).clamp(0.0, 1.0);
+                    t * t * (3.0 - 2.0 * t)
+                }
+            }
+            _ => 1.0,
+        };
+
+        self.intensity * attenuation * cone
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn white() -> Color {
+        Color::new(255, 255, 255)
+    }
+
+    // Point at `angle` radians off the spot's axis, one unit away
+    fn off_axis(angle: f32) -> Vec3 {
+        Vec3::new(angle.sin(), -angle.cos(), 0.0)
+    }
+
+    #[test]
+    fn test_spot_cone_edges() {
+        let (inner, outer) = (15f32.to_radians(), 25f32.to_radians());
+        let spot = Light::spot(Vec3::zeros(), Vec3::new(0.0, -1.0, 0.0), inner, outer, white(), 2.0);
+
+        assert_eq!(spot.intensity_at(&off_axis(0.0)), 2.0);
+        assert!((spot.intensity_at(&off_axis(inner)) - 2.0).abs() < 1e-4);
+        assert!(spot.intensity_at(&off_axis(outer)).abs() < 1e-4);
+        assert_eq!(spot.intensity_at(&off_axis(40f32.to_radians())), 0.0);
+
+        // Smoothstep in between, steadily dimmer toward the outer edge
+        let halfway = spot.intensity_at(&off_axis((inner + outer) / 2.0));
+        assert!(halfway > 0.5 && halfway < 1.5, "{}", halfway);
+        let samples: Vec<f32> = (0..=10).map(|i| spot.intensity_at(&off_axis(inner + (outer - inner) * i as f32 / 10.0))).collect();
+        assert!(samples.windows(2).all(|pair| pair[1] <= pair[0]), "{:?}", samples);
+    }
+
+    #[test]
+    fn test_spot_with_equal_angles_has_a_hard_edge() {
+        let angle = 20f32.to_radians();
+        let spot = Light::spot(Vec3::zeros(), Vec3::new(0.0, -1.0, 0.0), angle, angle, white(), 1.0);
+
+        assert_eq!(spot.intensity_at(&off_axis(angle - 0.01)), 1.0);
+        assert_eq!(spot.intensity_at(&off_axis(angle + 0.01)), 0.0);
+    }
+
+    #[test]
+    fn test_inverse_square_falloff() {
+        let light = Light::new(Vec3::zeros(), white(), 8.0).with_falloff(Falloff::InverseSquare);
+
+        assert!((light.intensity_at(&Vec3::new(2.0, 0.0, 0.0)) - 2.0).abs() < 1e-5);
+        assert!((light.intensity_at(&Vec3::new(0.0, 0.0, 4.0)) - 0.5).abs() < 1e-5);
+        // Capped close to the light instead of blowing up
+        assert!((light.intensity_at(&Vec3::zeros()) - 800.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_range_falloff_reaches_zero_at_the_range() {
+        let light = Light::new(Vec3::zeros(), white(), 1.0).with_falloff(Falloff::Range(3.0));
+        let at = |distance: f32| light.intensity_at(&Vec3::new(distance, 0.0, 0.0));
+
+        assert!((at(0.01) - 1.0).abs() < 1e-5);
+        assert!(at(1.5) > at(2.5) && at(2.5) > 0.0);
+        assert_eq!(at(3.0), 0.0);
+        assert_eq!(at(5.0), 0.0);
+    }
+
+    #[test]
+    fn test_directional_light_ignores_distance() {
+        for falloff in [Falloff::None, Falloff::InverseSquare, Falloff::Range(1.0)] {
+            let sun = Light::directional(Vec3::new(0.0, -1.0, 0.0), white(), 1.5).with_falloff(falloff);
+
+            for point in [Vec3::zeros(), Vec3::new(100.0, -50.0, 3.0)] {
+                assert_eq!(sun.intensity_at(&point), 1.5, "{:?} at {:?}", falloff, point);
+            }
+            assert_eq!(sun.direction_from(&Vec3::zeros()), (Vec3::new(0.0, 1.0, 0.0), f32::INFINITY));
+        }
+    }
+}
@@ -1,63 +1,102 @@
-use castingray::cast_ray;
-use colors::Color;
-use minifb::{Key, Window, WindowOptions};
+use castingray::shade;
 use nalgebra_glm::Vec3;
-use once_cell::sync::Lazy;
+use render_core::{screenshot, window, AssetManager, Camera, Color, Framebuffer, Key};
 use std::{
     f32::consts::PI,
-    sync::Arc,
     time::{Duration, Instant},
 };
 
-mod camera;
+mod aov;
 mod castingray;
-mod colors;
 mod cube;
-mod framebuffer;
+mod denoise;
+mod gbuffer;
+mod hdr;
 mod light;
 mod material;
+mod medium;
 mod myobjects;
+mod offline;
+mod overlay;
 mod r_stations;
 mod rayintersect;
+mod sampler;
+mod scene;
+mod sequence;
 mod shadow;
-mod texture;
+mod skybox;
+mod stats;
 
-use camera::Camera;
+use aov::{Aov, AovBuffers};
 use cube::Cube;
-use framebuffer::Framebuffer;
-use light::Light;
+use denoise::{denoise, DenoiseSettings};
+use gbuffer::{capture_gbuffer, GBuffer};
+use hdr::{HdrBuffer, ToneMapper, ToneOperator};
+use light::{Falloff, Light};
 use material::Material;
-use myobjects::loadobjects;
-use texture::Texture;
+use medium::MediumStack;
+use myobjects::{animate_water, loadobjects, Palette};
+use offline::{render_offline, OfflineSettings};
+use overlay::draw_panel;
+use scene::{load_scene_file, FileWatcher, DEFAULT_SCENE_FILE};
+use sequence::{parse_args, render_sequence};
+use skybox::Skybox;
+use stats::RenderStats;
+
+
+// White sunlight coming from the upper left
+fn sun(intensity: f32) -> Light {
+    Light::directional(Vec3::new(5.0, -5.0, -5.0), Color::new(255, 255, 255), intensity)
+}
+
+// The diorama plus whatever the scene file adds. Lights from the file come
+// back separately so they can go after the built-in ones.
+fn build_scene(assets: &mut AssetManager, scene_file: &str) -> (Vec<Cube>, Vec<Light>) {
+    // Initialize water material
+    // Water lets light through and tints it blue-green with depth
+    let mut water = Material::new_with_text(0.0, [1.0, 0.0, 0.1, 0.4], 1.33, assets.texture("water.png"))
+        .with_absorption(Color::new(60, 150, 190), 0.5);
+
+    // Set initial albedo property
+    water.albedo[2] = 0.5; // Initial value
 
-static WATER: Lazy<Arc<Texture>> = Lazy::new(|| Arc::new(Texture::new("assets/water.png")));
+    let palette = Palette::new(water, assets);
+    let mut objects = loadobjects(&palette);
 
-pub fn render(framebuffer: &mut Framebuffer, objects: &[Cube], camera: &Camera, lights: &[Light], skybox_color: Color) {
-    let width = framebuffer.width as f32;
-    let height = framebuffer.height as f32;
+    match load_scene_file(scene_file, &palette) {
+        Ok(Some(scene)) => {
+            objects.extend(scene.cubes);
+            (objects, scene.lights)
+        }
+        Ok(None) => (objects, Vec::new()),
+        Err(err) => {
+            eprintln!("Scene file ignored: {}", err);
+            (objects, Vec::new())
+        }
+    }
+}
+
+pub fn render(hdr_buffer: &mut HdrBuffer, aovs: &mut AovBuffers, objects: &[Cube], camera: &Camera, lights: &[Light], skybox: &Skybox) {
+    let width = hdr_buffer.width as f32;
+    let height = hdr_buffer.height as f32;
     let aspect_ratio = width / height;
-    let fov = PI / 3.0; // Adjust if needed
-    let perspective_scale = (fov / 2.0).tan();
 
-    for y in 0..framebuffer.height {
-        for x in 0..framebuffer.width {
+    for y in 0..hdr_buffer.height {
+        for x in 0..hdr_buffer.width {
             // Map the pixel coordinate to screen space [-1, 1]
             let screen_x = (2.0 * x as f32) / width - 1.0;
             let screen_y = -(2.0 * y as f32) / height + 1.0;
 
-            let screen_x = screen_x * aspect_ratio * perspective_scale;
-            let screen_y = screen_y * perspective_scale;
-
-            // Calculate the direction of the ray for this pixel
-            let ray_direction = &Vec3::new(screen_x, screen_y, -1.0).normalize();
-            let rotated_direction = camera.basis_change(&ray_direction);
+            // Interactive frames go through the center of the lens (pinhole),
+            // depth of field is left to the offline render
+            let (origin, direction) = camera.primary_ray(screen_x, screen_y, aspect_ratio, 0.0, 0.0);
 
             // Cast the ray and get the pixel color
-            let pixel_color = cast_ray(&camera.eye, &rotated_direction, objects, lights, 0, skybox_color);
+            let shading = shade(&origin, &direction, objects, lights, 0, skybox, &MediumStack::new());
 
-            // Draw the pixel on screen with the returned color
-            framebuffer.set_foreground_color(pixel_color.to_hex());
-            framebuffer.point(x, y);
+            // Store the linear radiance, it gets tone mapped on resolve
+            hdr_buffer.set(x, y, shading.color);
+            aovs.set(x, y, &shading, objects);
         }
     }
 }
@@ -71,26 +110,36 @@ fn main() {
     let fps = 0;
 
     let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
-    let mut window = Window::new(
-        &format!("KOALONSON ONSON - FPS: {}", fps),
-        window_width,
-        window_height,
-        WindowOptions::default(),
-    )
-    .unwrap();
-
-    // Initialize water material
-    let mut water = Material::new_with_text(0.0, [1.0, 0.0, 0.1, 0.0], 0.0, WATER.clone());
-
-    // Set initial albedo property
-    water.albedo[2] = 0.5; // Initial value
+    let mut hdr_buffer = HdrBuffer::new(framebuffer_width, framebuffer_height);
+    let mut aovs = AovBuffers::new(framebuffer_width, framebuffer_height);
+    // V cycles the pass shown in the window, E writes all of them to disk
+    let mut current_aov = Aov::Beauty;
+    // ACES rolls bright light off instead of clipping it, T cycles through
+    // the other curves and plain clamping
+    let mut tone_mapper = ToneMapper::new(ToneOperator::Aces, 1.0);
+    let mut tone_changed = false;
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let sequence_job = match parse_args(&args, tone_mapper) {
+        Ok(job) => job,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
 
-    let mut objects = loadobjects(water);
+    let mut assets = AssetManager::from_env();
+    let scene_file = std::env::var("SCENE_FILE").unwrap_or_else(|_| DEFAULT_SCENE_FILE.to_string());
+    let mut scene_watcher = FileWatcher::new(&scene_file);
+    let (mut objects, scene_lights) = build_scene(&mut assets, &scene_file);
 
     let mut lights = vec![
-        Light::new(Vec3::new(-5.0, 5.0, 5.0), Color::new(255, 255, 255), 1.0),
-        Light::new(Vec3::new(2.0, 0.8, -1.4), Color::new(144, 16, 235), 0.18),
+        sun(1.0),
+        // Purple glow around the respawn anchor, fades out before the far end of the island
+        Light::new(Vec3::new(2.0, 0.8, -1.4), Color::new(144, 16, 235), 0.6).with_falloff(Falloff::Range(2.5)),
     ];
+    let builtin_lights = lights.len();
+    lights.extend(scene_lights);
 
     let mut camera = Camera::new(
         Vec3::new(0.1, 0.1, 5.0),
@@ -98,16 +147,45 @@ fn main() {
         Vec3::new(0.0, 1.0, 0.0),
         true,
     );
+    // Lens size for the offline render's depth of field, 0 keeps it all sharp
+    camera.aperture = match std::env::var("APERTURE") {
+        Ok(value) => value.parse().unwrap_or_else(|_| {
+            eprintln!("APERTURE must be a number, got {}", value);
+            std::process::exit(1);
+        }),
+        Err(_) => 0.08,
+    };
+
+    // Headless turntable or camera path render, no window needed
+    if let Some(job) = sequence_job {
+        if let Err(err) = render_sequence(&objects, &lights, &Skybox::day(), &camera, &job, animate_water) {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let mut window = match window::open(&format!("KOALONSON ONSON - FPS: {}", fps), window_width, window_height) {
+        Ok(window) => window,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
 
     let mut last_time = Instant::now();
     let mut frame_count = 0;
     let rotation_speed = PI / 50.0;
     let zoom_speed = 0.1;
 
-    // Timer to keep track of albedo changes
-    let mut albedo_timer = Instant::now();
-    let mut albedo_state = 0; // To toggle between albedo states
-    let mut current_skybox_color = Color::skybox_color_j(); // Set initial skybox color
+    let mut current_skybox = Skybox::day(); // Set initial skybox and fog
+
+    // Ray counts and timings of the last rendered frame, F3 shows them and
+    // F4 appends one JSON line per frame to render_stats.jsonl
+    let mut stats = RenderStats::default();
+    let mut show_stats = false;
+    let mut dump_stats = false;
+    let mut new_frame = false;
 
     while window.is_open() && !window.is_key_down(Key::Escape) {
         if window.is_key_down(Key::Left) {
@@ -134,48 +212,150 @@ fn main() {
         }
 
         if window.is_key_down(Key::J) {
-            current_skybox_color = Color::skybox_color_j();
-            lights[0] = Light::new(Vec3::new(-5.0, 5.0, 5.0), Color::new(255, 255, 255), 1.0);
+            current_skybox = Skybox::day();
+            camera.has_change = true; // Fog depends on the preset
+            lights[0] = sun(1.0);
         }
         if window.is_key_down(Key::K) {
-            current_skybox_color = Color::skybox_color_k();
-            lights[0] = Light::new(Vec3::new(-5.0, 5.0, 5.0), Color::new(255, 255, 255), 0.6);
+            current_skybox = Skybox::sunset();
+            camera.has_change = true; // Fog depends on the preset
+            lights[0] = sun(0.6);
         }
         if window.is_key_down(Key::L) {
-            current_skybox_color = Color::skybox_color_l();
-            lights[0] = Light::new(Vec3::new(-5.0, 5.0, 5.0), Color::new(255, 255, 255), 0.3);
-        }
-
-        
-
-        // Check if 10 seconds or 5 seconds have passed to change albedo
-        if albedo_timer.elapsed().as_secs() >= 5 {
-            albedo_timer = Instant::now(); // Reset timer
-
-            // Change albedo based on the current state
-            match albedo_state {
-                0 => {
-                    objects[104].material.albedo[2] = 0.3; // Set to 0.5 at index 104
-                    objects[105].material.albedo[2] = 0.3; // Set to 0.5 at index 105
-                    albedo_state = 1; // Move to the next state
-                }
-                1 => {
-                    objects[104].material.albedo[2] = 0.5; // Set to 0.5 at index 104
-                    objects[105].material.albedo[2] = 0.5;
-                    albedo_state = 0; // Reset to the initial state
-                }
-                _ => {}
+            current_skybox = Skybox::night();
+            camera.has_change = true; // Fog depends on the preset
+            lights[0] = sun(0.3);
+        }
+
+        // Tone mapping only needs a resolve of the HDR buffer, not a new render
+        if window.is_key_pressed(Key::T) {
+            tone_mapper.operator = tone_mapper.operator.next();
+            tone_changed = true;
+        }
+        if window.is_key_repeated(Key::PageUp) {
+            tone_mapper.exposure *= 1.25;
+            tone_changed = true;
+        }
+        if window.is_key_repeated(Key::PageDown) {
+            tone_mapper.exposure /= 1.25;
+            tone_changed = true;
+        }
+        if window.is_key_pressed(Key::F3) {
+            show_stats = !show_stats;
+            tone_changed = true;
+        }
+        if window.is_key_pressed(Key::F4) {
+            dump_stats = !dump_stats;
+        }
+        if window.is_key_pressed(Key::V) {
+            current_aov = current_aov.next();
+            tone_changed = true;
+        }
+        if window.is_key_pressed(Key::P) {
+            let result = if current_aov == Aov::Beauty {
+                hdr_buffer.save_png("render.png", &tone_mapper)
+            } else {
+                aovs.save_png(current_aov, &format!("render_{}.png", current_aov.name()), &tone_mapper)
+            };
+            if let Err(err) = result {
+                eprintln!("Could not save render: {}", err);
+            }
+        }
+        if window.is_key_pressed(Key::E) {
+            if let Err(err) = aovs.save_all(&hdr_buffer, "aov", &tone_mapper) {
+                eprintln!("Could not save AOVs: {}", err);
             }
         }
 
+        // Offline still with depth of field and motion blur. The shutter stays
+        // open for a second: the camera sweeps one orbit step (as if Left was
+        // held) and the water keeps animating over it.
+        if window.is_key_pressed(Key::O) {
+            let shutter_start = window.time();
+            let mut camera_end = camera.clone();
+            camera_end.orbit(rotation_speed, 0.0);
+            let offline_settings = OfflineSettings::new(16, 0.0, 1.0);
+            let camera_at = |time| camera.interpolate(&camera_end, time);
+            let mut offline_buffer = HdrBuffer::new(framebuffer_width, framebuffer_height);
+            render_offline(
+                &mut offline_buffer,
+                &objects,
+                &lights,
+                &current_skybox,
+                &offline_settings,
+                camera_at,
+                |scene, time| animate_water(scene, shutter_start + time),
+            );
+            if let Err(err) = offline_buffer.save_png("offline.png", &tone_mapper) {
+                eprintln!("Could not save offline.png: {}", err);
+            }
+
+            // Denoised copy next to the raw one, for comparison
+            let mut gbuffer = GBuffer::new(framebuffer_width, framebuffer_height);
+            capture_gbuffer(&mut gbuffer, &objects, &offline_settings, camera_at, |scene, time| {
+                animate_water(scene, shutter_start + time)
+            });
+            denoise(&mut offline_buffer, &gbuffer, &DenoiseSettings::new());
+            if let Err(err) = offline_buffer.save_png("offline_denoised.png", &tone_mapper) {
+                eprintln!("Could not save offline_denoised.png: {}", err);
+            }
+        }
+
+        // Textures and the scene file edited on disk show up without
+        // restarting, the camera and the water animation carry on
+        let textures_changed = !assets.reload_changed().is_empty();
+        let scene_changed = scene_watcher.changed();
+        if textures_changed || scene_changed {
+            let (new_objects, scene_lights) = build_scene(&mut assets, &scene_file);
+            objects = new_objects;
+            lights.truncate(builtin_lights);
+            lights.extend(scene_lights);
+            camera.has_change = true;
+        }
+        // Set every frame from the time, so a rebuilt scene picks up where the
+        // water was instead of starting over
+        animate_water(&mut objects, window.time());
+
         if camera.check_change() {
-            framebuffer.clear();
-            render(&mut framebuffer, &objects, &mut camera, &lights,  current_skybox_color);
+            // Drop whatever was counted outside of this frame (offline renders)
+            RenderStats::take();
+            let render_start = Instant::now();
+            hdr_buffer.clear();
+            render(&mut hdr_buffer, &mut aovs, &objects, &camera, &lights, &current_skybox);
+
+            let present_time = stats.present_time;
+            stats = RenderStats::take();
+            stats.render_time = render_start.elapsed();
+            stats.present_time = present_time;
+            new_frame = true;
+            tone_changed = true;
+        }
+
+        if tone_changed {
+            let resolve_start = Instant::now();
+            if current_aov == Aov::Beauty {
+                hdr_buffer.resolve(&mut framebuffer, &tone_mapper);
+            } else {
+                aovs.resolve(current_aov, &mut framebuffer, &tone_mapper);
+            }
+            stats.resolve_time = resolve_start.elapsed();
+            if show_stats {
+                draw_panel(&mut framebuffer, &stats.overlay_lines(), 4, 4, 2);
+            }
+            tone_changed = false;
+        }
+
+        if new_frame && dump_stats {
+            if let Err(err) = stats.append_to("render_stats.jsonl") {
+                eprintln!("Could not write render_stats.jsonl: {}", err);
+            }
         }
+        new_frame = false;
 
-        window
-            .update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height)
-            .unwrap();
+        screenshot::handle_hotkey(window.as_ref(), &framebuffer);
+        let present_start = Instant::now();
+        window.present(&framebuffer);
+        stats.present_time = present_start.elapsed();
         std::thread::sleep(frame_delay);
 
         // Calculate FPS every second
@@ -191,7 +371,13 @@ fn main() {
             last_time = now;
 
             // Update window title with FPS
-            let title = format!("KOALONSON ONSON - FPS: {}", fps);
+            let title = format!(
+                "KOALONSON ONSON - FPS: {} - {} - {} x{:.2}",
+                fps,
+                current_aov.name(),
+                tone_mapper.operator.name(),
+                tone_mapper.exposure
+            );
             window.set_title(&title);
         }
     }
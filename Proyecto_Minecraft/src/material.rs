@@ -1,7 +1,10 @@
 
 use std::sync::Arc;
 
-use crate::{texture::Texture, Color};
+use nalgebra_glm::Vec3;
+use render_core::{Color, Texture};
+
+use crate::{hdr::HdrColor, medium::Medium};
 
 
 #[derive(Debug, Clone)]
@@ -10,9 +13,18 @@ pub struct Material {
     pub specular: f32,
     pub albedo: [f32;4],
     pub refraction_index: f32, 
-    pub texture: Option<Arc<Texture>>
+    pub texture: Option<Arc<Texture>>,
+    pub absorption: HdrColor,
+    // Tangent space normal map, x along the face's u axis and y along v
+    pub normal_map: Option<Arc<Texture>>,
+    // Height map for bump mapping, brighter texels stand out
+    pub height_map: Option<Arc<Texture>>,
+    pub bump_strength: f32,
 }
 
+// Texels with less alpha than this are holes, rays pass straight through
+pub const ALPHA_CUTOFF: f32 = 0.1;
+
 impl Material{
 
     pub fn new(
@@ -26,7 +38,11 @@ impl Material{
             specular,
             albedo,
             refraction_index,
-            texture : None 
+            texture : None,
+            absorption: HdrColor::black(),
+            normal_map: None,
+            height_map: None,
+            bump_strength: 0.0,
         }
     }
 
@@ -41,7 +57,71 @@ impl Material{
             specular,
             albedo,
             refraction_index,
-            texture: Some(texture)
+            texture: Some(texture),
+            absorption: HdrColor::black(),
+            normal_map: None,
+            height_map: None,
+            bump_strength: 0.0,
+        }
+    }
+
+    // Light that crosses `distance` units of this material comes out tinted
+    // to `color`; thicker blocks get darker and more saturated (Beer–Lambert)
+    pub fn with_absorption(mut self, color: Color, distance: f32) -> Self {
+        let tint = HdrColor::from_srgb(color);
+        let coefficient = |c: f32| -c.max(1e-4).ln() / distance;
+        self.absorption = HdrColor::new(coefficient(tint.r), coefficient(tint.g), coefficient(tint.b));
+        self
+    }
+
+    pub fn with_normal_map(mut self, normal_map: Arc<Texture>) -> Self {
+        self.normal_map = Some(normal_map);
+        self
+    }
+
+    // Bumps the shading normal by the luminance of height_map. The blocks in
+    // myobjects pass their own diffuse texture, so its darker texels (mortar,
+    // cracks) read as dents without a separate height map.
+    pub fn with_height_map(mut self, height_map: Arc<Texture>, strength: f32) -> Self {
+        self.height_map = Some(height_map);
+        self.bump_strength = strength;
+        self
+    }
+
+    // Normal used for lighting at (u, v). tangent and bitangent are the world
+    // directions in which u and v grow on the face that was hit.
+    // The normal map is applied first and the height map bumps the result.
+    pub fn shading_normal(&self, u: f32, v: f32, normal: &Vec3, tangent: &Vec3, bitangent: &Vec3) -> Vec3 {
+        let mut shading_normal = *normal;
+
+        if let Some(normal_map) = &self.normal_map {
+            let local = normal_map.get_normal(u, v);
+            shading_normal = (tangent * local.x + bitangent * local.y + normal * local.z).normalize();
+        }
+
+        if let Some(height_map) = &self.height_map {
+            // Central differences one texel apart
+            let du = 1.0 / height_map.width as f32;
+            let dv = 1.0 / height_map.height as f32;
+            let dh_du = (height_map.get_height(u + du, v) - height_map.get_height(u - du, v)) * 0.5;
+            let dh_dv = (height_map.get_height(u, v + dv) - height_map.get_height(u, v - dv)) * 0.5;
+            shading_normal = (shading_normal - (tangent * dh_du + bitangent * dh_dv) * self.bump_strength).normalize();
+        }
+
+        shading_normal
+    }
+
+    pub fn get_alpha(&self, u: f32, v: f32) -> f32 {
+        match &self.texture {
+            Some(texture) => texture.get_alpha(u, v),
+            None => 1.0,
+        }
+    }
+
+    pub fn medium(&self) -> Medium {
+        Medium {
+            refraction_index: self.refraction_index,
+            absorption: self.absorption,
         }
     }
 
@@ -81,9 +161,100 @@ impl Material{
             specular: 0.0,
             albedo: [0.0,0.0,0.0,0.0],
             refraction_index:0.0,
-            texture: None
-
+            texture: None,
+            absorption: HdrColor::black(),
+            normal_map: None,
+            height_map: None,
+            bump_strength: 0.0,
         }
     }
 
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{DynamicImage, RgbaImage};
+
+    // Gets brighter, so higher, to the right
+    fn ramp() -> Arc<Texture> {
+        let image = RgbaImage::from_fn(16, 16, |x, _| image::Rgba([x as u8 * 16, x as u8 * 16, x as u8 * 16, 255]));
+        Arc::new(Texture::from_image(DynamicImage::ImageRgba8(image)))
+    }
+
+    #[test]
+    fn test_without_a_height_map_the_normal_is_kept() {
+        let material = Material::new(Color::black(), 0.0, [1.0, 0.0, 0.0, 0.0], 0.0);
+        let normal = Vec3::new(0.0, 0.0, -1.0);
+
+        let shading = material.shading_normal(0.5, 0.5, &normal, &Vec3::new(1.0, 0.0, 0.0), &Vec3::new(0.0, 1.0, 0.0));
+
+        assert_eq!(shading, normal);
+    }
+
+    // Every texel the same color
+    fn flat(color: [u8; 3]) -> Arc<Texture> {
+        let image = RgbaImage::from_pixel(4, 4, image::Rgba([color[0], color[1], color[2], 255]));
+        Arc::new(Texture::from_image(DynamicImage::ImageRgba8(image)))
+    }
+
+    #[test]
+    fn test_flat_normal_map_keeps_the_normal() {
+        let material = Material::new(Color::black(), 0.0, [1.0, 0.0, 0.0, 0.0], 0.0).with_normal_map(flat([128, 128, 255]));
+        let normal = Vec3::new(0.0, 0.0, -1.0);
+
+        let shading = material.shading_normal(0.5, 0.5, &normal, &Vec3::new(1.0, 0.0, 0.0), &Vec3::new(0.0, 1.0, 0.0));
+
+        // 128 is as close to the middle as a byte gets
+        assert!((shading - normal).magnitude() < 0.01, "{:?}", shading);
+    }
+
+    #[test]
+    fn test_normal_map_tilts_toward_the_tangent() {
+        let material = Material::new(Color::black(), 0.0, [1.0, 0.0, 0.0, 0.0], 0.0).with_normal_map(flat([218, 128, 218]));
+        let tangent = Vec3::new(0.0, 1.0, 0.0);
+        let bitangent = Vec3::new(0.0, 0.0, 1.0);
+        let normal = Vec3::new(1.0, 0.0, 0.0);
+
+        let shading = material.shading_normal(0.5, 0.5, &normal, &tangent, &bitangent);
+
+        // About 45 degrees from the normal, all of it along the tangent
+        assert!((shading.dot(&tangent) - shading.dot(&normal)).abs() < 0.01, "{:?}", shading);
+        assert!(shading.dot(&tangent) > 0.7);
+        assert!(shading.dot(&bitangent).abs() < 0.01);
+        assert!((shading.magnitude() - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_bumps_apply_on_top_of_the_normal_map() {
+        let tangent = Vec3::new(1.0, 0.0, 0.0);
+        let bitangent = Vec3::new(0.0, 1.0, 0.0);
+        let normal = Vec3::new(0.0, 0.0, 1.0);
+        let mapped = Material::new(Color::black(), 0.0, [1.0, 0.0, 0.0, 0.0], 0.0).with_normal_map(flat([128, 218, 218]));
+        let bumped = mapped.clone().with_height_map(ramp(), 1.5);
+
+        let before = mapped.shading_normal(0.5, 0.5, &normal, &tangent, &bitangent);
+        let after = bumped.shading_normal(0.5, 0.5, &normal, &tangent, &bitangent);
+
+        // The map leans it along v, the ramp then pushes it back along u
+        assert!(before.y > 0.5 && before.x.abs() < 0.01, "{:?}", before);
+        assert!(after.x < 0.0 && after.y > 0.0, "{:?}", after);
+    }
+
+    #[test]
+    fn test_bumps_tilt_away_from_higher_ground() {
+        let material = Material::new(Color::black(), 0.0, [1.0, 0.0, 0.0, 0.0], 0.0).with_height_map(ramp(), 1.5);
+        let tangent = Vec3::new(1.0, 0.0, 0.0);
+        let bitangent = Vec3::new(0.0, 1.0, 0.0);
+
+        // Same u and v axes on both sides of the cube, the normal only flips
+        for normal in [Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0)] {
+            let shading = material.shading_normal(0.5, 0.5, &normal, &tangent, &bitangent);
+
+            assert!(shading.x < 0.0, "{:?} for {:?}", shading, normal);
+            assert!(shading.y.abs() < 1e-6);
+            assert!(shading.dot(&normal) > 0.9, "still facing out of {:?}", normal);
+            assert!((shading.magnitude() - 1.0).abs() < 1e-5);
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use crate::hdr::HdrColor;
+
+// The volume a ray is currently travelling through
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Medium {
+    pub refraction_index: f32,
+    // Beer–Lambert absorption coefficient per unit distance, per channel
+    pub absorption: HdrColor,
+}
+
+impl Medium {
+    pub const AIR: Medium = Medium {
+        refraction_index: 1.0,
+        absorption: HdrColor::black(),
+    };
+
+    // Fraction of light left after travelling `distance` through the medium
+    pub fn transmittance(&self, distance: f32) -> HdrColor {
+        HdrColor::new(
+            (-self.absorption.r * distance).exp(),
+            (-self.absorption.g * distance).exp(),
+            (-self.absorption.b * distance).exp(),
+        )
+    }
+}
+
+// Media the ray is nested in, innermost last. Entering a transparent object
+// pushes its medium and leaving pops it, so water inside glass refracts with
+// the water/glass IOR ratio instead of assuming air on the other side.
+// Entries are tagged with the index of the object they belong to.
+#[derive(Debug, Clone, Default)]
+pub struct MediumStack {
+    media: Vec<(usize, Medium)>,
+}
+
+impl MediumStack {
+    pub fn new() -> Self {
+        MediumStack { media: Vec::new() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.media.is_empty()
+    }
+
+    pub fn current(&self) -> Medium {
+        self.media.last().map(|&(_, medium)| medium).unwrap_or(Medium::AIR)
+    }
+
+    pub fn entering(&self, object: usize, medium: Medium) -> MediumStack {
+        let mut media = self.media.clone();
+        media.push((object, medium));
+        MediumStack { media }
+    }
+
+    // Removes the object's entry. Two blocks of the same material that touch
+    // share a face, and the ray can be counted as entering one and leaving the
+    // other, so without an entry of its own the innermost one of the same
+    // material goes instead.
+    pub fn leaving(&self, object: usize, medium: Medium) -> MediumStack {
+        let mut media = self.media.clone();
+        let index = media
+            .iter()
+            .rposition(|&(entered, _)| entered == object)
+            .or_else(|| media.iter().rposition(|&(_, entered)| entered == medium));
+        if let Some(index) = index {
+            media.remove(index);
+        }
+        MediumStack { media }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn water() -> Medium {
+        Medium {
+            refraction_index: 1.33,
+            absorption: HdrColor::new(0.1, 0.05, 0.02),
+        }
+    }
+
+    fn glass() -> Medium {
+        Medium {
+            refraction_index: 1.5,
+            absorption: HdrColor::black(),
+        }
+    }
+
+    #[test]
+    fn test_empty_stack_is_air() {
+        let media = MediumStack::new();
+
+        assert!(media.is_empty());
+        assert_eq!(media.current(), Medium::AIR);
+        assert!(media.leaving(3, water()).is_empty());
+    }
+
+    #[test]
+    fn test_leaving_the_outer_object_keeps_the_inner_one() {
+        let media = MediumStack::new().entering(1, glass()).entering(2, water());
+
+        let left = media.leaving(1, glass());
+
+        assert_eq!(left.current(), water());
+        assert_eq!(left.leaving(2, water()).current(), Medium::AIR);
+        assert_eq!(media.current(), water(), "leaving returns a new stack");
+    }
+
+    #[test]
+    fn test_leaving_matches_the_object_not_an_equal_medium() {
+        // Two glass blocks with identical media, nested
+        let media = MediumStack::new().entering(1, glass()).entering(5, water()).entering(2, glass());
+
+        let left = media.leaving(1, glass());
+
+        assert_eq!(left.current(), glass());
+        assert_eq!(left.leaving(2, glass()).current(), water());
+    }
+
+    #[test]
+    fn test_leaving_a_touching_block_of_the_same_material() {
+        let media = MediumStack::new().entering(1, glass()).entering(7, water());
+
+        // Went in through block 7 and came out of block 8 next to it
+        let left = media.leaving(8, water());
+
+        assert_eq!(left.current(), glass());
+        assert_eq!(left.leaving(9, water()).current(), glass());
+    }
+}
@@ -1,40 +1,81 @@
-use crate::colors::Color;
+use render_core::{AssetManager, Color};
+
 use crate::material::Material;
-use crate::texture::Texture;
-use crate::Arc;
 use crate::Cube;
-use crate::Lazy;
 use crate::Vec3;
 
-static COBBLESTONE: Lazy<Arc<Texture>> = Lazy::new(|| Arc::new(Texture::new("assets/acacia.png")));
-static SNOW: Lazy<Arc<Texture>> = Lazy::new(|| Arc::new(Texture::new("assets/snowb.png")));
-static SNOWBLOCK: Lazy<Arc<Texture>> = Lazy::new(|| Arc::new(Texture::new("assets/coponieve.png")));
-static GLASS: Lazy<Arc<Texture>> = Lazy::new(|| Arc::new(Texture::new("assets/glass.png")));
-static ACACIADOWN: Lazy<Arc<Texture>> =
-    Lazy::new(|| Arc::new(Texture::new("assets/acaciadown.png")));
-static LEAVES: Lazy<Arc<Texture>> = Lazy::new(|| Arc::new(Texture::new("assets/leaves.png")));
-static RESPAWN: Lazy<Arc<Texture>> = Lazy::new(|| Arc::new(Texture::new("assets/respawn.png")));
-
 //transparency es (3)
 //reflection (2)
 //albedo (1)
 //refraction 4
 
-pub fn loadobjects(water: Material) -> Vec<Cube> {
-    let marmle = Material::new(Color::new(118, 169, 253), 0.0, [0.5, 0.01, 0.8, 0.0], 0.0);
-
-    let ivorys = Material::new_with_text(0.0, [1.0, 0.0, 0.0, 0.0], 0.0, COBBLESTONE.clone());
-
-    let snowgras = Material::new_with_text(0.0, [0.9, 0.0, 0.0, 0.0], 0.0, SNOW.clone());
-
-    let snowblocks = Material::new_with_text(0.0, [0.9, 0.0, 0.0, 0.0], 0.0, SNOWBLOCK.clone());
+// Block materials, by the names scene files use for them
+pub struct Palette {
+    pub marble: Material,
+    pub cobblestone: Material,
+    pub snow: Material,
+    pub snowblock: Material,
+    pub glass: Material,
+    pub acacia: Material,
+    pub leaves: Material,
+    pub respawn: Material,
+    pub water: Material,
+}
 
-    let glassobj = Material::new_with_text(0.0, [1.0, 0.1, 0.2, 0.0], 0.0, GLASS.clone());
+impl Palette {
+    pub fn new(water: Material, assets: &mut AssetManager) -> Self {
+        let cobblestone = assets.texture("acacia.png");
+        let snow = assets.texture("snowb.png");
+        let snowblock = assets.texture("coponieve.png");
+        let glass = assets.texture("glass.png");
+        let acaciadown = assets.texture("acaciadown.png");
+        let leaves = assets.texture("leaves.png");
+        let respawn = assets.texture("respawn.png");
+
+        Palette {
+            marble: Material::new(Color::new(118, 169, 253), 0.0, [0.5, 0.01, 0.8, 0.0], 0.0),
+            cobblestone: Material::new_with_text(0.0, [1.0, 0.0, 0.0, 0.0], 0.0, cobblestone.clone())
+                .with_height_map(cobblestone, 1.5),
+            snow: Material::new_with_text(0.0, [0.9, 0.0, 0.0, 0.0], 0.0, snow.clone())
+                .with_height_map(snow, 0.8),
+            snowblock: Material::new_with_text(0.0, [0.9, 0.0, 0.0, 0.0], 0.0, snowblock.clone())
+                .with_height_map(snowblock, 0.8),
+            glass: Material::new_with_text(0.0, [1.0, 0.1, 0.2, 0.6], 1.5, glass)
+                .with_absorption(Color::new(215, 240, 225), 0.5),
+            acacia: Material::new_with_text(0.0, [1.0, 0.0, 0.0, 0.0], 0.0, acaciadown.clone())
+                .with_height_map(acaciadown, 1.5),
+            leaves: Material::new_with_text(0.0, [1.0, 0.0, 0.0, 0.0], 0.0, leaves),
+            respawn: Material::new_with_text(0.0, [1.0, 0.0, 0.0, 0.0], 0.0, respawn),
+            water,
+        }
+    }
 
-    let acacia_downs = Material::new_with_text(0.0, [1.0, 0.0, 0.0, 0.0], 0.0, ACACIADOWN.clone());
-    let leaves = Material::new_with_text(0.0, [1.0, 0.0, 0.0, 0.0], 0.0, LEAVES.clone());
+    pub fn get(&self, name: &str) -> Option<&Material> {
+        match name {
+            "marble" => Some(&self.marble),
+            "cobblestone" => Some(&self.cobblestone),
+            "snow" => Some(&self.snow),
+            "snowblock" => Some(&self.snowblock),
+            "glass" => Some(&self.glass),
+            "acacia" => Some(&self.acacia),
+            "leaves" => Some(&self.leaves),
+            "respawn" => Some(&self.respawn),
+            "water" => Some(&self.water),
+            _ => None,
+        }
+    }
+}
 
-    let resp = Material::new_with_text(0.0, [1.0, 0.0, 0.0, 0.0], 0.0, RESPAWN.clone());
+pub fn loadobjects(palette: &Palette) -> Vec<Cube> {
+    let marmle = palette.marble.clone();
+    let ivorys = palette.cobblestone.clone();
+    let snowgras = palette.snow.clone();
+    let snowblocks = palette.snowblock.clone();
+    let glassobj = palette.glass.clone();
+    let acacia_downs = palette.acacia.clone();
+    let leaves = palette.leaves.clone();
+    let resp = palette.respawn.clone();
+    let water = palette.water.clone();
 
     let mut objects = Vec::new();
     let cloned_ivorys = ivorys.clone();
@@ -65,7 +106,7 @@ pub fn loadobjects(water: Material) -> Vec<Cube> {
     }
 
     objects.push(Cube {
-        center: Vec3::new(2.5 as f32, 0.5 as f32, -1 as f32),
+        center: Vec3::new(2.5, 0.5, -1.0),
         size: 0.5,
         material: snowblocks.clone(),
     });
@@ -91,13 +132,13 @@ pub fn loadobjects(water: Material) -> Vec<Cube> {
     });
 
     objects.push(Cube {
-        center: Vec3::new(2.5 as f32, 1.5 as f32, -1.0 as f32),
+        center: Vec3::new(2.5, 1.5, -1.0),
         size: 0.5,
         material: snowblocks.clone(),
     });
 
     objects.push(Cube {
-        center: Vec3::new(2.5 as f32, 0.5 as f32, -1.5 as f32),
+        center: Vec3::new(2.5, 0.5, -1.5),
         size: 0.5,
         material: snowblocks.clone(),
     });
@@ -123,31 +164,31 @@ pub fn loadobjects(water: Material) -> Vec<Cube> {
     });
 
     objects.push(Cube {
-        center: Vec3::new(2.5 as f32, 0.5 as f32, -0.5 as f32),
+        center: Vec3::new(2.5, 0.5, -0.5),
         size: 0.5,
         material: snowblocks.clone(),
     });
 
     objects.push(Cube {
-        center: Vec3::new(2.0 as f32, 0.5 as f32, -0.0 as f32),
+        center: Vec3::new(2.0, 0.5, -0.0),
         size: 0.5,
         material: snowblocks.clone(),
     });
 
     objects.push(Cube {
-        center: Vec3::new(2.0 as f32, 0.5 as f32, -2.0 as f32),
+        center: Vec3::new(2.0, 0.5, -2.0),
         size: 0.5,
         material: snowblocks.clone(),
     });
 
     objects.push(Cube {
-        center: Vec3::new(1.5 as f32, 0.5 as f32, -2.0 as f32),
+        center: Vec3::new(1.5, 0.5, -2.0),
         size: 0.5,
         material: snowblocks.clone(),
     });
 
     objects.push(Cube {
-        center: Vec3::new(1.5 as f32, 0.5 as f32, -0.0 as f32),
+        center: Vec3::new(1.5, 0.5, -0.0),
         size: 0.5,
         material: snowblocks.clone(),
     });
@@ -296,7 +337,7 @@ pub fn loadobjects(water: Material) -> Vec<Cube> {
     }
 
     objects.push(Cube {
-        center: Vec3::new(0.5 as f32, 0.5 as f32, 0.5 as f32),
+        center: Vec3::new(0.5, 0.5, 0.5),
         size: 0.5,
         material: cloned_ivorys,
     });
@@ -361,7 +402,7 @@ pub fn loadobjects(water: Material) -> Vec<Cube> {
     }
 
     objects.push(Cube {
-        center: Vec3::new(-1.5 as f32, 0.5 as f32, 0.0 as f32),
+        center: Vec3::new(-1.5, 0.5, 0.0),
         size: 0.5,
         material: leaves,
     });
@@ -398,3 +439,18 @@ pub fn loadobjects(water: Material) -> Vec<Cube> {
 
     objects
 }
+
+// The two water blocks loadobjects puts in the pool
+const WATER_CUBES: [usize; 2] = [104, 105];
+
+// Pool water at `time` seconds: every 5 seconds it switches between less
+// and more reflective. It only depends on the time, so the offline render
+// can ask for any instant inside its shutter.
+pub fn animate_water(objects: &mut [Cube], time: f32) {
+    let reflectivity = if (time / 5.0) as u32 % 2 == 1 { 0.3 } else { 0.5 };
+    for index in WATER_CUBES {
+        if let Some(cube) = objects.get_mut(index) {
+            cube.material.albedo[2] = reflectivity;
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use render_core::Camera;
+use crate::castingray::cast_ray;
+use crate::cube::Cube;
+use crate::hdr::{HdrBuffer, HdrColor};
+use crate::light::Light;
+use crate::medium::MediumStack;
+use crate::sampler::Sampler;
+use crate::skybox::Skybox;
+use nalgebra_glm::Vec3;
+
+pub struct OfflineSettings {
+    // Samples per pixel. Each sample is one pass over the image at its own
+    // time inside the shutter interval, with a jittered pixel and lens position.
+    pub samples: u32,
+    pub shutter_open: f32,
+    pub shutter_close: f32,
+}
+
+impl OfflineSettings {
+    pub fn new(samples: u32, shutter_open: f32, shutter_close: f32) -> Self {
+        OfflineSettings {
+            samples: samples.max(1),
+            shutter_open,
+            shutter_close,
+        }
+    }
+
+    // Stratified time for a pass, so the passes cover the whole shutter interval
+    pub fn sample_time(&self, sample: u32) -> f32 {
+        let t = (sample as f32 + 0.5) / self.samples as f32;
+        self.shutter_open + (self.shutter_close - self.shutter_open) * t
+    }
+}
+
+// Multi-sample render for stills: anti-aliasing, thin-lens depth of field and
+// motion blur. camera_at gives the camera pose at a shutter time and animate
+// moves the objects to that time, so both camera and object motion blur.
+pub fn render_offline<C, A>(
+    hdr_buffer: &mut HdrBuffer,
+    objects: &[Cube],
+    lights: &[Light],
+    skybox: &Skybox,
+    settings: &OfflineSettings,
+    camera_at: C,
+    animate: A,
+) where
+    C: Fn(f32) -> Camera,
+    A: Fn(&mut [Cube], f32),
+{
+    let width = hdr_buffer.width as f32;
+    let height = hdr_buffer.height as f32;
+    let weight = 1.0 / settings.samples as f32;
+
+    let mut accumulation = vec![HdrColor::black(); hdr_buffer.width * hdr_buffer.height];
+    let mut scene = objects.to_vec();
+
+    for sample in 0..settings.samples {
+        let time = settings.sample_time(sample);
+        let camera = camera_at(time);
+        scene.clone_from_slice(objects);
+        animate(&mut scene, time);
+
+        for y in 0..hdr_buffer.height {
+            for x in 0..hdr_buffer.width {
+                let (origin, direction) = sample_primary_ray(&camera, x, y, sample, width, height);
+                let pixel_color = cast_ray(&origin, &direction, &scene, lights, 0, skybox, &MediumStack::new());
+
+                let index = y * hdr_buffer.width + x;
+                accumulation[index] = accumulation[index] + pixel_color * weight;
+            }
+        }
+    }
+
+    hdr_buffer.buffer = accumulation;
+}
+
+// Jittered pixel position and lens point for one sample. Seeded from the
+// pixel and sample index, so every pass that calls it sees the same rays.
+pub fn sample_primary_ray(camera: &Camera, x: usize, y: usize, sample: u32, width: f32, height: f32) -> (Vec3, Vec3) {
+    let mut sampler = Sampler::for_pixel(x, y, sample);
+    let screen_x = (2.0 * (x as f32 + sampler.next_f32())) / width - 1.0;
+    let screen_y = -(2.0 * (y as f32 + sampler.next_f32())) / height + 1.0;
+    let (lens_x, lens_y) = sampler.unit_disk();
+
+    camera.primary_ray(screen_x, screen_y, width / height, lens_x, lens_y)
+}
@@ -0,0 +1,91 @@
+use render_core::Framebuffer;
+
+// Tiny 3x5 bitmap font for the debug overlay. Each row is 3 bits, the
+// highest bit is the leftmost column. Only upper case is covered.
+fn glyph(c: char) -> [u8; 5] {
+    match c {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '%' => [0b101, 0b001, 0b010, 0b100, 0b101],
+        _ => [0b000; 5],
+    }
+}
+
+// Draws `text` with its top left corner at (x, y), each font pixel becomes a
+// scale x scale block. Characters outside the font render as blanks.
+pub fn draw_text(framebuffer: &mut Framebuffer, text: &str, x: usize, y: usize, scale: usize, color: u32) {
+    framebuffer.set_foreground_color(color);
+
+    for (i, c) in text.to_uppercase().chars().enumerate() {
+        let origin_x = x + i * 4 * scale;
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for col in 0..3 {
+                if bits & (0b100 >> col) == 0 {
+                    continue;
+                }
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        framebuffer.point(origin_x + col * scale + dx, y + row * scale + dy);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Stack of text lines over a darkened box so it stays readable on the sky
+pub fn draw_panel(framebuffer: &mut Framebuffer, lines: &[String], x: usize, y: usize, scale: usize) {
+    let line_height = 6 * scale;
+    let width = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0) * 4 * scale + 2 * scale;
+    let height = lines.len() * line_height + scale;
+
+    for py in y..(y + height).min(framebuffer.height) {
+        for px in x..(x + width).min(framebuffer.width) {
+            let index = py * framebuffer.width + px;
+            // Halve every channel in one go
+            framebuffer.buffer[index] = (framebuffer.buffer[index] >> 1) & 0x7F7F7F;
+        }
+    }
+
+    for (i, line) in lines.iter().enumerate() {
+        draw_text(framebuffer, line, x + scale, y + scale + i * line_height, scale, 0xFFFFFF);
+    }
+}
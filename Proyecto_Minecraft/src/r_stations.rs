@@ -15,29 +15,25 @@ pub fn reflection(incident: &Vec3, normal: &Vec3) -> Vec3 {
     incident - 2.0 * incident.dot(normal) * normal
 }
 
-pub fn refract(incident: &Vec3, normal: &Vec3, eta_t: f32) -> Vec3 {
-    let cosi = -incident.dot(normal).max(-1.0).min(1.0);
-    
-    let (n_cosi, eta, n_normal);
+// eta_i is the refraction index the ray travels in, eta_t the one on the other
+// side of the surface. The normal can face either way.
+pub fn refract(incident: &Vec3, normal: &Vec3, eta_i: f32, eta_t: f32) -> Vec3 {
+    let mut cosi = -incident.dot(normal).clamp(-1.0, 1.0);
+    let mut n_normal = *normal;
 
     if cosi < 0.0 {
-        // Ray is entering the object
-        n_cosi = -cosi;
-        eta = 1.0 / eta_t;
+        // Normal points into the side the ray is going to, flip it
+        cosi = -cosi;
         n_normal = -normal;
-    } else {
-        // Ray is leaving the object
-        n_cosi = cosi;
-        eta = eta_t;  // Assuming it's going back into air with index 1.0
-        n_normal = *normal;
     }
-    
-    let k = 1.0 - eta * eta * (1.0 - n_cosi * n_cosi);
+
+    let eta = eta_i / eta_t;
+    let k = 1.0 - eta * eta * (1.0 - cosi * cosi);
     
     if k < 0.0 {
         // Total internal reflection
         reflection(incident, &n_normal)
     } else {
-        eta * incident + (eta * n_cosi - k.sqrt()) * n_normal
+        eta * incident + (eta * cosi - k.sqrt()) * n_normal
     }
-}
\ No newline at end of file
+}
@@ -12,10 +12,12 @@ pub struct Intersect {
     pub material: Material,
     pub u: f32,
     pub v: f32,
+    // Whether the ray hit the outside of the face, false from inside the cube
+    pub front_face: bool,
 }
 
 impl Intersect {
-    pub fn new( point: Vec3, normal: Vec3 ,distance: f32, material: Material, u:f32, v:f32) -> Self {
+    pub fn new( point: Vec3, normal: Vec3 ,distance: f32, material: Material, u:f32, v:f32, front_face: bool) -> Self {
         Intersect {
             normal,
             point,
@@ -24,6 +26,17 @@ impl Intersect {
             material,
             u,
             v,
+            front_face,
+        }
+    }
+
+    // Normal on the side the ray came from, for lighting. `normal` always
+    // points out of the cube, refraction uses it to tell entering from leaving.
+    pub fn facing_normal(&self) -> Vec3 {
+        if self.front_face {
+            self.normal
+        } else {
+            -self.normal
         }
     }
 
@@ -35,7 +48,8 @@ impl Intersect {
             is_intersecting: false,
             material: Material::black(),
             u: 0.0,
-            v:0.0
+            v:0.0,
+            front_face: false,
 
         }
     }
@@ -43,5 +57,7 @@ impl Intersect {
 
 pub trait RayIntersect {
   fn get_uv(&self, point: &Vec3, normal: &Vec3) -> (f32, f32);
+  // World directions in which u and v grow on the face with this normal
+  fn get_tangent_frame(&self, normal: &Vec3) -> (Vec3, Vec3);
   fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect;
 }
\ No newline at end of file
@@ -0,0 +1,43 @@
+use std::f32::consts::PI;
+
+// Small xorshift generator. Offline renders seed it per pixel so the same
+// scene always produces the same image, without pulling in a rand crate.
+pub struct Sampler {
+    state: u32,
+}
+
+impl Sampler {
+    pub fn new(seed: u32) -> Self {
+        // xorshift never leaves the zero state, so mix the seed first
+        let mut state = seed.wrapping_mul(0x9E37_79B9) ^ 0x85EB_CA6B;
+        if state == 0 {
+            state = 1;
+        }
+        Sampler { state }
+    }
+
+    pub fn for_pixel(x: usize, y: usize, sample: u32) -> Self {
+        Sampler::new((x as u32).wrapping_mul(73_856_093) ^ (y as u32).wrapping_mul(19_349_663) ^ sample.wrapping_mul(83_492_791))
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+
+    // Uniform in [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    // Uniform point on the unit disk, used for lens samples
+    pub fn unit_disk(&mut self) -> (f32, f32) {
+        let radius = self.next_f32().sqrt();
+        let angle = 2.0 * PI * self.next_f32();
+        (radius * angle.cos(), radius * angle.sin())
+    }
+}
@@ -0,0 +1,236 @@
+use std::fs;
+use std::ops::RangeInclusive;
+use std::time::SystemTime;
+
+use nalgebra_glm::Vec3;
+use render_core::Color;
+
+use crate::cube::Cube;
+use crate::light::{Falloff, Light};
+use crate::myobjects::Palette;
+
+// Scene file read unless SCENE_FILE says otherwise
+pub const DEFAULT_SCENE_FILE: &str = "scene.txt";
+
+// Blocks and lights added on top of the built-in diorama
+pub struct SceneData {
+    pub cubes: Vec<Cube>,
+    pub lights: Vec<Light>,
+}
+
+// One entry per line, blank lines and lines starting with # are skipped:
+//   cube <material> <x> <y> <z> <size>
+//   light <x> <y> <z> <r> <g> <b> <intensity> [range]
+//   sun <dx> <dy> <dz> <r> <g> <b> <intensity>
+//   spot <x> <y> <z> <dx> <dy> <dz> <inner deg> <outer deg> <r> <g> <b> <intensity>
+// Material names are the ones Palette::get knows. Ok(None) when there is no
+// file, which just means the diorama has nothing extra.
+pub fn load_scene_file(file_path: &str, palette: &Palette) -> Result<Option<SceneData>, String> {
+    let contents = match fs::read_to_string(file_path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(format!("{}: {}", file_path, err)),
+    };
+    let mut scene = SceneData { cubes: Vec::new(), lights: Vec::new() };
+
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let error = |message: String| format!("{}:{}: {}", file_path, number + 1, message);
+        let mut words = line.split_whitespace();
+        let kind = words.next().unwrap_or_default();
+
+        match kind {
+            "cube" => {
+                let name = words.next().ok_or_else(|| error("cube needs a material".to_string()))?;
+                let material = palette.get(name).ok_or_else(|| error(format!("unknown material {}", name)))?;
+                let values = parse_numbers(words, 4..=4).map_err(error)?;
+                scene.cubes.push(Cube {
+                    center: Vec3::new(values[0], values[1], values[2]),
+                    size: values[3],
+                    material: material.clone(),
+                });
+            }
+            "light" => {
+                // Optional 8th number: distance the light fades out at
+                let values = parse_numbers(words, 7..=8).map_err(error)?;
+                let mut light = Light::new(Vec3::new(values[0], values[1], values[2]), color(&values[3..6]), values[6]);
+                if let Some(range) = values.get(7) {
+                    light = light.with_falloff(Falloff::Range(*range));
+                }
+                scene.lights.push(light);
+            }
+            "sun" => {
+                let values = parse_numbers(words, 7..=7).map_err(error)?;
+                scene.lights.push(Light::directional(
+                    Vec3::new(values[0], values[1], values[2]),
+                    color(&values[3..6]),
+                    values[6],
+                ));
+            }
+            "spot" => {
+                let values = parse_numbers(words, 12..=12).map_err(error)?;
+                scene.lights.push(
+                    Light::spot(
+                        Vec3::new(values[0], values[1], values[2]),
+                        Vec3::new(values[3], values[4], values[5]),
+                        values[6].to_radians(),
+                        values[7].to_radians(),
+                        color(&values[8..11]),
+                        values[11],
+                    )
+                    .with_falloff(Falloff::InverseSquare),
+                );
+            }
+            other => return Err(error(format!("unknown entry {}", other))),
+        }
+    }
+
+    Ok(Some(scene))
+}
+
+fn parse_numbers<'a>(words: impl Iterator<Item = &'a str>, count: RangeInclusive<usize>) -> Result<Vec<f32>, String> {
+    let values: Vec<f32> = words
+        .map(|word| word.parse::<f32>().map_err(|err| format!("{}: {}", word, err)))
+        .collect::<Result<_, _>>()?;
+    if !count.contains(&values.len()) {
+        return Err(format!("expected {} numbers, found {}", count.end(), values.len()));
+    }
+    Ok(values)
+}
+
+fn color(values: &[f32]) -> Color {
+    let channel = |value: f32| value.clamp(0.0, 255.0) as u8;
+    Color::new(channel(values[0]), channel(values[1]), channel(values[2]))
+}
+
+// Tells when a file was created, modified or removed since the last check
+pub struct FileWatcher {
+    path: String,
+    modified: Option<SystemTime>,
+}
+
+impl FileWatcher {
+    pub fn new(path: &str) -> Self {
+        FileWatcher {
+            path: path.to_string(),
+            modified: modified_time(path),
+        }
+    }
+
+    pub fn changed(&mut self) -> bool {
+        let modified = modified_time(&self.path);
+        let changed = modified != self.modified;
+        self.modified = modified;
+        changed
+    }
+}
+
+fn modified_time(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::light::LightKind;
+    use crate::material::Material;
+    use render_core::AssetManager;
+
+    fn palette() -> Palette {
+        Palette::new(Material::black(), &mut AssetManager::new("src/assets"))
+    }
+
+    fn scene_file(name: &str, contents: &str) -> String {
+        let file_path = std::env::temp_dir().join(format!("proyecto_scene_{}_{}.txt", name, std::process::id()));
+        fs::write(&file_path, contents).unwrap();
+        file_path.to_string_lossy().into_owned()
+    }
+
+    fn load(name: &str, contents: &str) -> Result<Option<SceneData>, String> {
+        let file_path = scene_file(name, contents);
+        let result = load_scene_file(&file_path, &palette());
+        fs::remove_file(&file_path).unwrap();
+        result.map_err(|err| err.replace(&file_path, "scene"))
+    }
+
+    #[test]
+    fn test_loads_every_entry() {
+        let scene = load(
+            "valid",
+            "# comment\n\n  cube glass -0.5 0.75 0.5 0.5\n\
+             light 0 2 2 255 120 40 0.8 3\n\
+             light 1 1 1 300 -5 10 1\n\
+             sun 5 -5 -5 255 255 255 0.5\n\
+             spot 0.5 2 0 0 -1 0 15 25 255 240 200 4\n",
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(scene.cubes.len(), 1);
+        assert_eq!(scene.cubes[0].center, Vec3::new(-0.5, 0.75, 0.5));
+        assert_eq!(scene.cubes[0].size, 0.5);
+        assert_eq!(scene.cubes[0].material.refraction_index, palette().glass.refraction_index);
+
+        let lights = &scene.lights;
+        assert_eq!(lights.len(), 4);
+        assert_eq!(lights[0].falloff, Falloff::Range(3.0));
+        assert_eq!(lights[0].color, Color::new(255, 120, 40));
+        assert_eq!(lights[0].kind, LightKind::Point);
+        // Channels outside 0..255 are clamped, no range means no falloff
+        assert_eq!(lights[1].color, Color::new(255, 0, 10));
+        assert_eq!(lights[1].falloff, Falloff::None);
+        assert!(matches!(lights[2].kind, LightKind::Directional(_)));
+        assert_eq!(lights[2].intensity, 0.5);
+        let LightKind::Spot { inner_angle, outer_angle, .. } = lights[3].kind else {
+            panic!("expected a spot light");
+        };
+        assert!((inner_angle - 15f32.to_radians()).abs() < 1e-6);
+        assert!((outer_angle - 25f32.to_radians()).abs() < 1e-6);
+        assert_eq!(lights[3].falloff, Falloff::InverseSquare);
+    }
+
+    #[test]
+    fn test_empty_and_missing_files() {
+        let scene = load("empty", "# nothing here\n\n").unwrap().unwrap();
+        assert!(scene.cubes.is_empty() && scene.lights.is_empty());
+
+        let missing = std::env::temp_dir().join("proyecto_scene_missing.txt");
+        assert!(load_scene_file(&missing.to_string_lossy(), &palette()).unwrap().is_none());
+
+        // Anything else that keeps the file from being read is an error
+        let directory = std::env::temp_dir();
+        assert!(load_scene_file(&directory.to_string_lossy(), &palette()).is_err());
+    }
+
+    #[test]
+    fn test_rejects_broken_lines() {
+        let error = |name: &str, contents: &str| load(name, contents).err().unwrap();
+
+        assert_eq!(error("no_material", "cube\n"), "scene:1: cube needs a material");
+        assert_eq!(error("material", "\ncube gold 0 0 0 1\n"), "scene:2: unknown material gold");
+        assert_eq!(error("cube_count", "cube glass 0 0 1\n"), "scene:1: expected 4 numbers, found 3");
+        assert_eq!(error("light_count", "light 0 0 0 255 255 255\n"), "scene:1: expected 8 numbers, found 6");
+        assert_eq!(error("sun_count", "sun 0 -1 0 255 255 255 1 2\n"), "scene:1: expected 7 numbers, found 8");
+        assert_eq!(error("spot_count", "spot 0 1 0 0 -1 0 15 25\n"), "scene:1: expected 12 numbers, found 8");
+        assert_eq!(error("number", "cube glass 0 zero 0 1\n"), "scene:1: zero: invalid float literal");
+        assert_eq!(error("entry", "# ok\ntree 0 0 0\n"), "scene:2: unknown entry tree");
+    }
+
+    #[test]
+    fn test_watcher_sees_edits_and_removal() {
+        let file_path = scene_file("watched", "cube glass 0 0 0 1\n");
+        let mut watcher = FileWatcher::new(&file_path);
+        assert!(!watcher.changed());
+
+        fs::remove_file(&file_path).unwrap();
+        assert!(watcher.changed());
+        assert!(!watcher.changed());
+
+        fs::write(&file_path, "").unwrap();
+        assert!(watcher.changed());
+        fs::remove_file(&file_path).unwrap();
+    }
+}
@@ -0,0 +1,426 @@
+use std::f32::consts::PI;
+use std::fs::{self, File};
+use std::path::Path;
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, RgbaImage};
+use nalgebra_glm::Vec3;
+use render_core::Camera;
+
+use crate::cube::Cube;
+use crate::denoise::{denoise, DenoiseSettings};
+use crate::gbuffer::{capture_gbuffer, GBuffer};
+use crate::hdr::{HdrBuffer, ToneMapper};
+use crate::light::Light;
+use crate::offline::{render_offline, OfflineSettings};
+use crate::skybox::Skybox;
+
+// Where the camera goes over the sequence
+pub enum CameraPath {
+    // Full turn around the camera's center, using Camera::orbit
+    Orbit,
+    // Catmull-Rom spline through (eye, center) keyframes
+    Keyframes(Vec<(Vec3, Vec3)>),
+}
+
+impl CameraPath {
+    // Keyframe file: one "eye_x eye_y eye_z center_x center_y center_z" per
+    // line, blank lines and lines starting with # are skipped
+    pub fn load_keyframes(file_path: &str) -> Result<CameraPath, String> {
+        let contents = fs::read_to_string(file_path).map_err(|err| format!("{}: {}", file_path, err))?;
+        let mut keyframes = Vec::new();
+
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let values: Vec<f32> = line
+                .split_whitespace()
+                .map(|value| value.parse::<f32>())
+                .collect::<Result<_, _>>()
+                .map_err(|err| format!("{}:{}: {}", file_path, number + 1, err))?;
+            if values.len() != 6 {
+                return Err(format!("{}:{}: expected 6 numbers, found {}", file_path, number + 1, values.len()));
+            }
+            keyframes.push((
+                Vec3::new(values[0], values[1], values[2]),
+                Vec3::new(values[3], values[4], values[5]),
+            ));
+        }
+
+        if keyframes.len() < 2 {
+            return Err(format!("{}: a camera path needs at least 2 keyframes", file_path));
+        }
+        Ok(CameraPath::Keyframes(keyframes))
+    }
+
+    // Camera for frame `frame` of `frames`. Every frame starts again from
+    // `base`, so the result doesn't depend on rendering the frames in order.
+    pub fn camera_at(&self, base: &Camera, frame: u32, frames: u32) -> Camera {
+        let mut camera = base.clone();
+        match self {
+            CameraPath::Orbit => {
+                camera.orbit(2.0 * PI * frame as f32 / frames as f32, 0.0);
+            }
+            CameraPath::Keyframes(keyframes) => {
+                // Last frame lands on the last keyframe
+                let t = frame as f32 / (frames.max(2) - 1) as f32;
+                let (eye, center) = catmull_rom(keyframes, t);
+                camera.look_at(eye, center);
+            }
+        }
+        camera
+    }
+}
+
+fn catmull_rom(keyframes: &[(Vec3, Vec3)], t: f32) -> (Vec3, Vec3) {
+    let segments = keyframes.len() - 1;
+    let position = t.clamp(0.0, 1.0) * segments as f32;
+    let segment = (position.floor() as usize).min(segments - 1);
+    let local = position - segment as f32;
+
+    // End points are repeated so the curve passes through every keyframe
+    let point = |i: isize| keyframes[i.clamp(0, segments as isize) as usize];
+    let i = segment as isize;
+    let (p0, p1, p2, p3) = (point(i - 1), point(i), point(i + 1), point(i + 2));
+
+    let spline = |a: Vec3, b: Vec3, c: Vec3, d: Vec3| {
+        let t2 = local * local;
+        let t3 = t2 * local;
+        0.5 * ((2.0 * b)
+            + (-a + c) * local
+            + (2.0 * a - 5.0 * b + 4.0 * c - d) * t2
+            + (-a + 3.0 * b - 3.0 * c + d) * t3)
+    };
+
+    (spline(p0.0, p1.0, p2.0, p3.0), spline(p0.1, p1.1, p2.1, p3.1))
+}
+
+pub enum SequenceOutput {
+    // frame_0000.png, frame_0001.png, ... inside the directory
+    PngSequence(String),
+    Gif(String),
+}
+
+impl SequenceOutput {
+    // A path ending in .gif makes an animated GIF, anything else is a directory
+    pub fn from_path(path: &str) -> SequenceOutput {
+        if path.to_lowercase().ends_with(".gif") {
+            SequenceOutput::Gif(path.to_string())
+        } else {
+            SequenceOutput::PngSequence(path.to_string())
+        }
+    }
+}
+
+pub struct SequenceSettings {
+    pub frames: u32,
+    pub samples: u32,
+    pub width: usize,
+    pub height: usize,
+    // GIF frame delay
+    pub frame_millis: u32,
+    pub tone_mapper: ToneMapper,
+    // Run the denoiser on every frame, worth it with few samples
+    pub denoise: bool,
+}
+
+// Renders every frame of the path without a window and writes the result.
+// animate moves the objects to a time in seconds, frames are frame_millis
+// apart. Sampling is seeded per pixel and frame, so the same scene and
+// settings always produce the same files.
+pub fn render_sequence<A>(
+    objects: &[Cube],
+    lights: &[Light],
+    skybox: &Skybox,
+    base_camera: &Camera,
+    job: &SequenceJob,
+    animate: A,
+) -> Result<(), String>
+where
+    A: Fn(&mut [Cube], f32),
+{
+    let (path, settings, output) = (&job.path, &job.settings, &job.output);
+    let frames = settings.frames.max(1);
+    let tone_mapper = &settings.tone_mapper;
+    let offline_settings = OfflineSettings::new(settings.samples, 0.0, 0.0);
+    let mut hdr_buffer = HdrBuffer::new(settings.width, settings.height);
+
+    let mut gif = match output {
+        SequenceOutput::Gif(file_path) => {
+            let file = File::create(file_path).map_err(|err| format!("{}: {}", file_path, err))?;
+            let mut encoder = GifEncoder::new(file);
+            encoder.set_repeat(Repeat::Infinite).map_err(|err| err.to_string())?;
+            Some(encoder)
+        }
+        SequenceOutput::PngSequence(directory) => {
+            fs::create_dir_all(directory).map_err(|err| format!("{}: {}", directory, err))?;
+            None
+        }
+    };
+
+    for frame in 0..frames {
+        let camera = path.camera_at(base_camera, frame, frames);
+        let frame_time = frame_time(frame, settings.frame_millis);
+        let animate_frame = |scene: &mut [Cube], time: f32| animate(scene, frame_time + time);
+        render_offline(&mut hdr_buffer, objects, lights, skybox, &offline_settings, |_| camera.clone(), animate_frame);
+        if settings.denoise {
+            let mut gbuffer = GBuffer::new(settings.width, settings.height);
+            capture_gbuffer(&mut gbuffer, objects, &offline_settings, |_| camera.clone(), animate_frame);
+            denoise(&mut hdr_buffer, &gbuffer, &DenoiseSettings::new());
+        }
+
+        match output {
+            SequenceOutput::Gif(_) => {
+                let image = RgbaImage::from_fn(settings.width as u32, settings.height as u32, |x, y| {
+                    let color = tone_mapper.map(hdr_buffer.buffer[y as usize * settings.width + x as usize]);
+                    image::Rgba([color.r, color.g, color.b, 255])
+                });
+                let delay = Delay::from_numer_denom_ms(settings.frame_millis, 1);
+                if let Some(encoder) = gif.as_mut() {
+                    encoder
+                        .encode_frame(Frame::from_parts(image, 0, 0, delay))
+                        .map_err(|err| err.to_string())?;
+                }
+            }
+            SequenceOutput::PngSequence(directory) => {
+                let file_path = Path::new(directory).join(format!("frame_{:04}.png", frame));
+                hdr_buffer
+                    .save_png(&file_path.to_string_lossy(), tone_mapper)
+                    .map_err(|err| format!("{}: {}", file_path.display(), err))?;
+            }
+        }
+
+        println!("Rendered frame {}/{}", frame + 1, frames);
+    }
+
+    Ok(())
+}
+
+pub struct SequenceJob {
+    pub path: CameraPath,
+    pub settings: SequenceSettings,
+    pub output: SequenceOutput,
+}
+
+pub const USAGE: &str = "usage:
+  --turntable <frames> <output>
+  --path <keyframes.txt> <frames> <output>
+options: --samples <n> --size <width>x<height> --delay <ms> --denoise
+output ending in .gif writes an animated GIF, anything else is a PNG directory";
+
+// Command line for headless sequence renders. Ok(None) means no sequence
+// was asked for and the interactive window should open as usual.
+pub fn parse_args(args: &[String], tone_mapper: ToneMapper) -> Result<Option<SequenceJob>, String> {
+    let mut args = args.iter();
+    let path = match args.next().map(String::as_str) {
+        None => return Ok(None),
+        Some("--turntable") => CameraPath::Orbit,
+        Some("--path") => CameraPath::load_keyframes(args.next().ok_or(USAGE)?)?,
+        Some(other) => return Err(format!("unknown argument {}\n{}", other, USAGE)),
+    };
+
+    let frames = parse_number(args.next(), "frames")?;
+    let output = SequenceOutput::from_path(args.next().ok_or(USAGE)?);
+    let mut settings = SequenceSettings {
+        frames,
+        samples: 1,
+        width: 600,
+        height: 400,
+        frame_millis: 40,
+        tone_mapper,
+        denoise: false,
+    };
+
+    while let Some(option) = args.next() {
+        match option.as_str() {
+            "--samples" => settings.samples = parse_number(args.next(), "samples")?,
+            "--denoise" => settings.denoise = true,
+            "--delay" => settings.frame_millis = parse_number(args.next(), "delay")?,
+            "--size" => {
+                let size = args.next().ok_or(USAGE)?;
+                let (width, height) = size.split_once('x').ok_or(format!("bad size {}", size))?;
+                settings.width = parse_number(Some(&width.to_string()), "width")? as usize;
+                settings.height = parse_number(Some(&height.to_string()), "height")? as usize;
+            }
+            other => return Err(format!("unknown option {}\n{}", other, USAGE)),
+        }
+    }
+
+    Ok(Some(SequenceJob { path, settings, output }))
+}
+
+// Seconds into the sequence at the start of frame. In u64, since long
+// sequences with a long delay overflow u32 milliseconds.
+fn frame_time(frame: u32, frame_millis: u32) -> f32 {
+    (frame as u64 * frame_millis as u64) as f32 / 1000.0
+}
+
+fn parse_number(value: Option<&String>, name: &str) -> Result<u32, String> {
+    let value = value.ok_or(USAGE)?;
+    match value.parse::<u32>() {
+        Ok(number) if number > 0 => Ok(number),
+        _ => Err(format!("{} must be a positive integer, got {}", name, value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hdr::ToneOperator;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    fn parse(line: &str) -> Result<Option<SequenceJob>, String> {
+        parse_args(&args(line), ToneMapper::new(ToneOperator::Clamp, 1.0))
+    }
+
+    fn keyframes_file(name: &str, contents: &str) -> String {
+        let file_path = std::env::temp_dir().join(format!("proyecto_keyframes_{}_{}.txt", name, std::process::id()));
+        fs::write(&file_path, contents).unwrap();
+        file_path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_no_arguments_opens_the_window() {
+        assert!(parse("").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_parses_a_turntable_with_options() {
+        let job = parse("--turntable 60 out.gif --samples 4 --size 320x200 --delay 20 --denoise")
+            .unwrap()
+            .unwrap();
+
+        assert!(matches!(job.path, CameraPath::Orbit));
+        assert!(matches!(job.output, SequenceOutput::Gif(ref path) if path == "out.gif"));
+        assert_eq!(job.settings.frames, 60);
+        assert_eq!(job.settings.samples, 4);
+        assert_eq!((job.settings.width, job.settings.height), (320, 200));
+        assert_eq!(job.settings.frame_millis, 20);
+        assert!(job.settings.denoise);
+
+        let job = parse("--turntable 2 frames").unwrap().unwrap();
+        assert!(matches!(job.output, SequenceOutput::PngSequence(ref path) if path == "frames"));
+        assert_eq!((job.settings.samples, job.settings.width, job.settings.frame_millis), (1, 600, 40));
+        assert!(!job.settings.denoise);
+    }
+
+    #[test]
+    fn test_rejects_bad_arguments() {
+        let error = |line: &str| parse(line).err().unwrap();
+
+        assert!(error("--spin 10 out").starts_with("unknown argument --spin\n"));
+        assert!(error("--turntable 10 out --fast").starts_with("unknown option --fast\n"));
+        assert_eq!(error("--turntable 0 out"), "frames must be a positive integer, got 0");
+        assert_eq!(error("--turntable ten out"), "frames must be a positive integer, got ten");
+        assert_eq!(error("--turntable 10 out --samples -1"), "samples must be a positive integer, got -1");
+        assert_eq!(error("--turntable 10 out --size 320"), "bad size 320");
+        assert_eq!(error("--turntable 10 out --size 320x"), "height must be a positive integer, got ");
+        // Missing values print the usage
+        assert_eq!(error("--turntable"), USAGE);
+        assert_eq!(error("--turntable 10"), USAGE);
+        assert_eq!(error("--turntable 10 out --delay"), USAGE);
+        assert_eq!(error("--path"), USAGE);
+    }
+
+    #[test]
+    fn test_catmull_rom_passes_through_the_keyframes() {
+        let keyframes = [
+            (Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0)),
+            (Vec3::new(1.0, 2.0, 0.0), Vec3::new(1.0, 0.0, -1.0)),
+            (Vec3::new(3.0, 2.0, 1.0), Vec3::new(2.0, 0.0, -1.0)),
+            (Vec3::new(4.0, 0.0, 1.0), Vec3::new(3.0, 0.0, -1.0)),
+        ];
+
+        for (i, keyframe) in keyframes.iter().enumerate() {
+            let (eye, center) = catmull_rom(&keyframes, i as f32 / 3.0);
+            assert!((eye - keyframe.0).magnitude() < 1e-5, "{}: {:?}", i, eye);
+            assert!((center - keyframe.1).magnitude() < 1e-5, "{}: {:?}", i, center);
+        }
+
+        // Evenly spaced keyframes on a line give a straight, evenly paced curve
+        let (_, center) = catmull_rom(&keyframes, 0.5);
+        assert!((center - Vec3::new(1.5, 0.0, -1.0)).magnitude() < 1e-5);
+
+        // Outside [0, 1] it stays on the end points
+        assert!((catmull_rom(&keyframes, -1.0).0 - keyframes[0].0).magnitude() < 1e-6);
+        assert!((catmull_rom(&keyframes, 2.0).0 - keyframes[3].0).magnitude() < 1e-5);
+    }
+
+    #[test]
+    fn test_catmull_rom_with_two_keyframes_eases_along_the_line() {
+        let keyframes = [
+            (Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0)),
+            (Vec3::new(2.0, 0.0, 0.0), Vec3::new(2.0, 0.0, -1.0)),
+        ];
+
+        let (eye, center) = catmull_rom(&keyframes, 0.5);
+        assert!((eye - Vec3::new(1.0, 0.0, 0.0)).magnitude() < 1e-5, "{:?}", eye);
+        assert!((center - Vec3::new(1.0, 0.0, -1.0)).magnitude() < 1e-5, "{:?}", center);
+
+        // The repeated end points slow it down at both ends
+        let (early, _) = catmull_rom(&keyframes, 0.25);
+        let (late, _) = catmull_rom(&keyframes, 0.75);
+        assert!(early.x < 0.5 && early.y == 0.0 && early.z == 0.0, "{:?}", early);
+        assert!((early.x + late.x - 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_loads_keyframes() {
+        let file_path = keyframes_file("valid", "# eye center\n0 1 5 0 0 0\n\n  4 1 0   0 0 0  \n");
+
+        let path = CameraPath::load_keyframes(&file_path).unwrap();
+        fs::remove_file(&file_path).unwrap();
+
+        let CameraPath::Keyframes(keyframes) = path else {
+            panic!("expected keyframes");
+        };
+        assert_eq!(keyframes.len(), 2);
+        assert_eq!(keyframes[0], (Vec3::new(0.0, 1.0, 5.0), Vec3::zeros()));
+        assert_eq!(keyframes[1], (Vec3::new(4.0, 1.0, 0.0), Vec3::zeros()));
+    }
+
+    #[test]
+    fn test_rejects_broken_keyframe_files() {
+        let error = |name: &str, contents: &str| {
+            let file_path = keyframes_file(name, contents);
+            let error = CameraPath::load_keyframes(&file_path).err().unwrap();
+            fs::remove_file(&file_path).unwrap();
+            error.replace(&file_path, "path")
+        };
+
+        assert_eq!(error("short", "0 1 5 0 0 0\n1 2 3\n"), "path:2: expected 6 numbers, found 3");
+        assert_eq!(error("number", "# header\n0 1 x 0 0 0\n"), "path:2: invalid float literal");
+        assert_eq!(error("single", "0 1 5 0 0 0\n"), "path: a camera path needs at least 2 keyframes");
+        assert_eq!(error("empty", ""), "path: a camera path needs at least 2 keyframes");
+
+        let missing = std::env::temp_dir().join("proyecto_keyframes_missing.txt");
+        assert!(CameraPath::load_keyframes(&missing.to_string_lossy()).is_err());
+    }
+
+    #[test]
+    fn test_keyframe_path_ends_on_the_last_keyframe() {
+        let path = CameraPath::Keyframes(vec![
+            (Vec3::new(0.0, 0.0, 5.0), Vec3::zeros()),
+            (Vec3::new(5.0, 0.0, 0.0), Vec3::zeros()),
+        ]);
+        let base = Camera::new(Vec3::new(0.0, 0.0, 1.0), Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0), false);
+
+        assert!((path.camera_at(&base, 0, 10).eye - Vec3::new(0.0, 0.0, 5.0)).magnitude() < 1e-5);
+        let last = path.camera_at(&base, 9, 10);
+        assert!((last.eye - Vec3::new(5.0, 0.0, 0.0)).magnitude() < 1e-5);
+        assert!((last.focal_distance - 5.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_frame_time() {
+        assert_eq!(frame_time(0, 40), 0.0);
+        assert_eq!(frame_time(25, 40), 1.0);
+        // Past what u32 milliseconds can hold
+        assert_eq!(frame_time(u32::MAX, 1000), u32::MAX as f32);
+    }
+}
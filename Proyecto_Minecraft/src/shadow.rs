@@ -1,4 +1,7 @@
-use crate::{cube::Cube, light::Light, rayintersect::{Intersect, RayIntersect}};
+use nalgebra_glm::Vec3;
+
+use crate::{cube::Cube, light::Light, rayintersect::Intersect};
+use crate::stats::{record_intersection_tests, record_ray, RayKind};
 
 
 
@@ -7,19 +10,40 @@ pub fn cast_shadow(
     light: &Light,
     objects: &[Cube],
 ) -> f32 {
-    let light_dir = (light.position - intersect.point).normalize();
+    let (light_dir, light_distance) = light.direction_from(&intersect.point);
     let bias = 0.001; 
-    let shadow_ray_origin = intersect.point + intersect.normal * bias;
+    let shadow_ray_origin = intersect.point + intersect.facing_normal() * bias;
     
     let mut shadow_intensity = 0.0;
+    let mut tests = 0;
 
+    record_ray(RayKind::Shadow);
     for object in objects {
-        let shadow_intersect = object.ray_intersect(&shadow_ray_origin, &light_dir);
-        if shadow_intersect.is_intersecting {
+        tests += 1;
+        let shadow_intersect = object.ray_intersect_cutout(&shadow_ray_origin, &light_dir);
+        // Anything past a point or spot light doesn't block it
+        if shadow_intersect.is_intersecting && shadow_intersect.distance < light_distance {
             shadow_intensity = 0.4;
             break;
         }
     }
+    record_intersection_tests(tests);
 
     shadow_intensity
-}
\ No newline at end of file
+}
+
+// Whether a point in free space (not on a surface) has a clear line to the light
+pub fn is_lit(point: &Vec3, light: &Light, objects: &[Cube]) -> bool {
+    let (light_dir, light_distance) = light.direction_from(point);
+
+    record_ray(RayKind::Shadow);
+    let mut tests = 0;
+    let lit = !objects.iter().any(|object| {
+        tests += 1;
+        let shadow_intersect = object.ray_intersect_cutout(point, &light_dir);
+        shadow_intersect.is_intersecting && shadow_intersect.distance < light_distance
+    });
+    record_intersection_tests(tests);
+
+    lit
+}
@@ -0,0 +1,269 @@
+use nalgebra_glm::Vec3;
+use render_core::Color;
+
+use crate::cube::Cube;
+use crate::hdr::HdrColor;
+use crate::light::Light;
+use crate::shadow::is_lit;
+
+// Exponential height fog. Density falls off above `base_height`, so the
+// ground level gets hazier than the tops of the blocks.
+#[derive(Debug, Clone, Copy)]
+pub struct Fog {
+    pub density: f32,
+    pub height_falloff: f32,
+    pub base_height: f32,
+    // Strength of the single scattering light shafts from the main light,
+    // 0.0 turns the ray marching off
+    pub scattering: f32,
+    pub volumetric_steps: u32,
+    // How far rays that miss everything are marched for light shafts
+    pub max_distance: f32,
+}
+
+impl Fog {
+    pub const fn none() -> Self {
+        Fog {
+            density: 0.0,
+            height_falloff: 0.0,
+            base_height: 0.0,
+            scattering: 0.0,
+            volumetric_steps: 0,
+            max_distance: 0.0,
+        }
+    }
+
+    // Fog stays at full density below base_height
+    pub fn density_at(&self, height: f32) -> f32 {
+        self.density * (-self.height_falloff * (height - self.base_height).max(0.0)).exp()
+    }
+
+    // Integral of the density along origin + direction * t for t in [0, distance].
+    // The ray is split where it crosses base_height: constant density below it,
+    // exponential falloff above it.
+    pub fn optical_depth(&self, origin: &Vec3, direction: &Vec3, distance: f32) -> f32 {
+        let crossing = if direction.y.abs() > 1e-6 {
+            (self.base_height - origin.y) / direction.y
+        } else {
+            f32::INFINITY
+        };
+        let split = if crossing > 0.0 { crossing.min(distance) } else { distance };
+
+        if origin.y >= self.base_height {
+            self.falloff_depth(origin.y, direction.y, split) + self.density * (distance - split)
+        } else {
+            self.density * split + self.falloff_depth(self.base_height, direction.y, distance - split)
+        }
+    }
+
+    // Analytic integral of the exponential part, starting at `height`
+    fn falloff_depth(&self, height: f32, direction_y: f32, distance: f32) -> f32 {
+        if distance <= 0.0 {
+            return 0.0;
+        }
+        let falloff = self.height_falloff * direction_y;
+        let start = self.density_at(height);
+        if falloff.abs() < 1e-5 {
+            start * distance
+        } else {
+            start * (1.0 - (-falloff * distance).exp()) / falloff
+        }
+    }
+}
+
+// Background color plus the atmosphere that goes with it
+#[derive(Debug, Clone, Copy)]
+pub struct Skybox {
+    pub color: Color,
+    pub fog: Fog,
+}
+
+impl Skybox {
+    // J key
+    pub const fn day() -> Self {
+        Skybox {
+            color: Color::new(141, 162, 255),
+            fog: Fog {
+                density: 0.03,
+                height_falloff: 0.6,
+                base_height: 0.0,
+                scattering: 0.0,
+                volumetric_steps: 0,
+                max_distance: 0.0,
+            },
+        }
+    }
+
+    // K key, low sun with light shafts
+    pub const fn sunset() -> Self {
+        Skybox {
+            color: Color::new(213, 135, 57),
+            fog: Fog {
+                density: 0.04,
+                height_falloff: 0.4,
+                base_height: 0.0,
+                scattering: 0.2,
+                volumetric_steps: 12,
+                max_distance: 12.0,
+            },
+        }
+    }
+
+    // L key
+    pub const fn night() -> Self {
+        Skybox {
+            color: Color::black(),
+            fog: Fog {
+                density: 0.15,
+                height_falloff: 0.3,
+                base_height: 0.0,
+                scattering: 0.08,
+                volumetric_steps: 12,
+                max_distance: 12.0,
+            },
+        }
+    }
+
+    pub fn background(&self) -> HdrColor {
+        HdrColor::from_srgb(self.color)
+    }
+
+    // Fades `color`, seen `distance` away along the ray, toward the sky color
+    // and adds the light scattered toward the viewer by the fog in between.
+    // Rays that hit nothing pass f32::INFINITY and only get the scattering.
+    pub fn apply_fog(&self, color: HdrColor, origin: &Vec3, direction: &Vec3, distance: f32, lights: &[Light], objects: &[Cube]) -> HdrColor {
+        let fog = &self.fog;
+        if fog.density <= 0.0 {
+            return color;
+        }
+
+        let mut result = if distance.is_finite() {
+            let transmittance = (-fog.optical_depth(origin, direction, distance)).exp();
+            color * transmittance + self.background() * (1.0 - transmittance)
+        } else {
+            color
+        };
+
+        if let Some(light) = lights.first() {
+            if fog.scattering > 0.0 && fog.volumetric_steps > 0 {
+                let march_distance = distance.min(fog.max_distance);
+                result = result + self.in_scattering(origin, direction, march_distance, light, objects);
+            }
+        }
+
+        result
+    }
+
+    // Ray marches the segment and gathers light from the points that can see
+    // the light, attenuated by the fog between each point and the viewer
+    fn in_scattering(&self, origin: &Vec3, direction: &Vec3, distance: f32, light: &Light, objects: &[Cube]) -> HdrColor {
+        let fog = &self.fog;
+        let step = distance / fog.volumetric_steps as f32;
+        let light_color = HdrColor::from_srgb(light.color);
+        let mut scattered = 0.0;
+
+        for i in 0..fog.volumetric_steps {
+            let t = (i as f32 + 0.5) * step;
+            let point = origin + direction * t;
+            let intensity = light.intensity_at(&point);
+            if intensity > 0.0 && is_lit(&point, light, objects) {
+                let transmittance = (-fog.optical_depth(origin, direction, t)).exp();
+                scattered += fog.density_at(point.y) * transmittance * intensity * step;
+            }
+        }
+
+        light_color * (scattered * fog.scattering)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::lock_counters;
+
+    const STEPS: usize = 20_000;
+
+    fn fog(height_falloff: f32) -> Fog {
+        Fog {
+            density: 0.2,
+            height_falloff,
+            base_height: 1.0,
+            scattering: 1.0,
+            volumetric_steps: 400,
+            max_distance: 10.0,
+        }
+    }
+
+    // Midpoint rule along the ray
+    fn numeric_optical_depth(fog: &Fog, origin: &Vec3, direction: &Vec3, distance: f32) -> f32 {
+        let step = distance / STEPS as f32;
+        (0..STEPS)
+            .map(|i| fog.density_at(origin.y + direction.y * (i as f32 + 0.5) * step) * step)
+            .sum()
+    }
+
+    #[test]
+    fn test_optical_depth_matches_numeric_integration() {
+        let up = Vec3::new(0.6, 0.8, 0.0);
+        let down = Vec3::new(0.6, -0.8, 0.0);
+        let level = Vec3::new(1.0, 0.0, 0.0);
+        let above = Vec3::new(0.0, 3.0, 0.0);
+        let below = Vec3::new(0.0, -1.0, 0.0);
+
+        for height_falloff in [0.0, 0.7] {
+            let fog = fog(height_falloff);
+            for (origin, direction, distance) in [
+                (above, up, 5.0),
+                (above, down, 1.5), // stays above base_height
+                (above, down, 6.0), // crosses it on the way down
+                (below, down, 4.0),
+                (below, up, 1.0), // stays below
+                (below, up, 6.0), // crosses it on the way up
+                (above, level, 5.0),
+                (below, level, 5.0),
+            ] {
+                let analytic = fog.optical_depth(&origin, &direction, distance);
+                let numeric = numeric_optical_depth(&fog, &origin, &direction, distance);
+                assert!(
+                    (analytic - numeric).abs() < numeric * 1e-3,
+                    "falloff {} from {:?} along {:?}: {} vs {}",
+                    height_falloff, origin, direction, analytic, numeric
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_in_scattering_matches_numeric_integration() {
+        // Shadow rays toward the light are counted
+        let _counters = lock_counters();
+        let light = Light::directional(Vec3::new(0.0, -1.0, 0.0), Color::new(255, 255, 255), 2.0);
+        let origin = Vec3::new(0.0, 3.0, 0.0);
+        let direction = Vec3::new(0.6, -0.8, 0.0);
+        let distance = 6.0;
+
+        for height_falloff in [0.0, 0.7] {
+            let skybox = Skybox { color: Color::black(), fog: fog(height_falloff) };
+            let scattered = skybox.in_scattering(&origin, &direction, distance, &light, &[]);
+
+            // Light scattered at every point, dimmed by the fog toward the viewer
+            let fog = &skybox.fog;
+            let step = distance / STEPS as f32;
+            let (mut expected, mut depth) = (0.0, 0.0);
+            for i in 0..STEPS {
+                let density = fog.density_at(origin.y + direction.y * (i as f32 + 0.5) * step);
+                expected += density * (-(depth + density * step / 2.0)).exp() * light.intensity * step;
+                depth += density * step;
+            }
+
+            assert!((scattered.r - expected).abs() < expected * 1e-2, "falloff {}: {} vs {}", height_falloff, scattered.r, expected);
+            assert_eq!((scattered.r, scattered.g), (scattered.b, scattered.b));
+        }
+
+        // Without falloff the fog is uniform and the integral has a closed form
+        let skybox = Skybox { color: Color::black(), fog: fog(0.0) };
+        let scattered = skybox.in_scattering(&origin, &direction, distance, &light, &[]);
+        let closed_form = light.intensity * (1.0 - (-skybox.fog.density * distance).exp());
+        assert!((scattered.r - closed_form).abs() < closed_form * 1e-3, "{} vs {}", scattered.r, closed_form);
+    }
+}
@@ -0,0 +1,228 @@
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+// Counters are global atomics so cast_ray and the shadow tests can record
+// them without threading a stats struct through every call
+static PRIMARY_RAYS: AtomicU64 = AtomicU64::new(0);
+static SHADOW_RAYS: AtomicU64 = AtomicU64::new(0);
+static REFLECTION_RAYS: AtomicU64 = AtomicU64::new(0);
+static REFRACTION_RAYS: AtomicU64 = AtomicU64::new(0);
+static TRANSMISSION_RAYS: AtomicU64 = AtomicU64::new(0);
+static INTERSECTION_TESTS: AtomicU64 = AtomicU64::new(0);
+static DEPTH_SUM: AtomicU64 = AtomicU64::new(0);
+static MAX_DEPTH: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RayKind {
+    Primary,
+    Shadow,
+    Reflection,
+    Refraction,
+    // Carries on unbent through a see-through texel
+    Transmission,
+}
+
+pub fn record_ray(kind: RayKind) {
+    let counter = match kind {
+        RayKind::Primary => &PRIMARY_RAYS,
+        RayKind::Shadow => &SHADOW_RAYS,
+        RayKind::Reflection => &REFLECTION_RAYS,
+        RayKind::Refraction => &REFRACTION_RAYS,
+        RayKind::Transmission => &TRANSMISSION_RAYS,
+    };
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn record_intersection_tests(count: usize) {
+    INTERSECTION_TESTS.fetch_add(count as u64, Ordering::Relaxed);
+}
+
+// Recursion depth of a cast_ray call, 0 for primary rays
+pub fn record_depth(depth: u32) {
+    DEPTH_SUM.fetch_add(depth as u64, Ordering::Relaxed);
+    MAX_DEPTH.fetch_max(depth as u64, Ordering::Relaxed);
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RenderStats {
+    pub primary_rays: u64,
+    pub shadow_rays: u64,
+    pub reflection_rays: u64,
+    pub refraction_rays: u64,
+    pub transmission_rays: u64,
+    pub intersection_tests: u64,
+    pub depth_sum: u64,
+    pub max_depth: u64,
+    pub render_time: Duration,
+    pub resolve_time: Duration,
+    pub present_time: Duration,
+}
+
+impl RenderStats {
+    // Collects everything recorded since the last call and resets the counters
+    pub fn take() -> Self {
+        RenderStats {
+            primary_rays: PRIMARY_RAYS.swap(0, Ordering::Relaxed),
+            shadow_rays: SHADOW_RAYS.swap(0, Ordering::Relaxed),
+            reflection_rays: REFLECTION_RAYS.swap(0, Ordering::Relaxed),
+            refraction_rays: REFRACTION_RAYS.swap(0, Ordering::Relaxed),
+            transmission_rays: TRANSMISSION_RAYS.swap(0, Ordering::Relaxed),
+            intersection_tests: INTERSECTION_TESTS.swap(0, Ordering::Relaxed),
+            depth_sum: DEPTH_SUM.swap(0, Ordering::Relaxed),
+            max_depth: MAX_DEPTH.swap(0, Ordering::Relaxed),
+            ..Default::default()
+        }
+    }
+
+    // Rays that went through cast_ray (everything but shadow rays)
+    pub fn traced_rays(&self) -> u64 {
+        self.primary_rays + self.reflection_rays + self.refraction_rays + self.transmission_rays
+    }
+
+    pub fn total_rays(&self) -> u64 {
+        self.traced_rays() + self.shadow_rays
+    }
+
+    pub fn average_depth(&self) -> f32 {
+        ratio(self.depth_sum, self.traced_rays())
+    }
+
+    pub fn tests_per_ray(&self) -> f32 {
+        ratio(self.intersection_tests, self.total_rays())
+    }
+
+    pub fn overlay_lines(&self) -> Vec<String> {
+        vec![
+            format!("PRIMARY {}", self.primary_rays),
+            format!("SHADOW {}", self.shadow_rays),
+            format!("REFLECT {}", self.reflection_rays),
+            format!("REFRACT {}", self.refraction_rays),
+            format!("TRANSMIT {}", self.transmission_rays),
+            format!("DEPTH AVG {:.2} MAX {}", self.average_depth(), self.max_depth),
+            format!("TESTS/RAY {:.1}", self.tests_per_ray()),
+            format!("RENDER {:.1} MS", millis(self.render_time)),
+            format!("RESOLVE {:.1} MS", millis(self.resolve_time)),
+            format!("PRESENT {:.1} MS", millis(self.present_time)),
+        ]
+    }
+
+    // One JSON object per frame, meant to be appended to a .jsonl file
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"primary_rays\":{},\"shadow_rays\":{},\"reflection_rays\":{},\"refraction_rays\":{},\
+             \"transmission_rays\":{},\"intersection_tests\":{},\"average_depth\":{:.4},\"max_depth\":{},\"tests_per_ray\":{:.4},\
+             \"render_ms\":{:.3},\"resolve_ms\":{:.3},\"present_ms\":{:.3}}}",
+            self.primary_rays,
+            self.shadow_rays,
+            self.reflection_rays,
+            self.refraction_rays,
+            self.transmission_rays,
+            self.intersection_tests,
+            self.average_depth(),
+            self.max_depth,
+            self.tests_per_ray(),
+            millis(self.render_time),
+            millis(self.resolve_time),
+            millis(self.present_time),
+        )
+    }
+
+    pub fn append_to(&self, file_path: &str) -> io::Result<()> {
+        let mut file = OpenOptions::new().create(true).append(true).open(file_path)?;
+        writeln!(file, "{}", self.to_json())
+    }
+}
+
+fn ratio(count: u64, total: u64) -> f32 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f32 / total as f32
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+// The counters are shared by every test that casts rays, so the ones that
+// check them hold this while they run
+#[cfg(test)]
+pub fn lock_counters() -> std::sync::MutexGuard<'static, ()> {
+    static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats() -> RenderStats {
+        RenderStats {
+            primary_rays: 4,
+            shadow_rays: 6,
+            reflection_rays: 2,
+            refraction_rays: 1,
+            transmission_rays: 1,
+            intersection_tests: 70,
+            depth_sum: 6,
+            max_depth: 3,
+            render_time: Duration::from_micros(12_500),
+            resolve_time: Duration::from_millis(2),
+            present_time: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_take_collects_and_resets() {
+        let _counters = lock_counters();
+        RenderStats::take();
+
+        record_ray(RayKind::Primary);
+        record_ray(RayKind::Primary);
+        record_ray(RayKind::Shadow);
+        record_ray(RayKind::Reflection);
+        record_ray(RayKind::Refraction);
+        record_ray(RayKind::Transmission);
+        record_intersection_tests(5);
+        record_depth(0);
+        record_depth(3);
+        record_depth(1);
+
+        let stats = RenderStats::take();
+        assert_eq!(
+            (stats.primary_rays, stats.shadow_rays, stats.reflection_rays, stats.refraction_rays, stats.transmission_rays),
+            (2, 1, 1, 1, 1)
+        );
+        assert_eq!((stats.intersection_tests, stats.depth_sum, stats.max_depth), (5, 4, 3));
+
+        let empty = RenderStats::take();
+        assert_eq!((empty.total_rays(), empty.intersection_tests, empty.max_depth), (0, 0, 0));
+    }
+
+    #[test]
+    fn test_ratios() {
+        let stats = stats();
+
+        assert_eq!(stats.traced_rays(), 8);
+        assert_eq!(stats.total_rays(), 14);
+        assert_eq!(stats.average_depth(), 0.75);
+        assert_eq!(stats.tests_per_ray(), 5.0);
+
+        // Nothing traced yet
+        let empty = RenderStats::default();
+        assert_eq!((empty.average_depth(), empty.tests_per_ray()), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_to_json() {
+        assert_eq!(
+            stats().to_json(),
+            "{\"primary_rays\":4,\"shadow_rays\":6,\"reflection_rays\":2,\"refraction_rays\":1,\
+             \"transmission_rays\":1,\"intersection_tests\":70,\"average_depth\":0.7500,\"max_depth\":3,\
+             \"tests_per_ray\":5.0000,\"render_ms\":12.500,\"resolve_ms\":2.000,\"present_ms\":0.000}"
+        );
+    }
+}
@@ -1,4 +1,5 @@
-use crate::framebuffer::Framebuffer;
+use render_core::Framebuffer;
+
 use crate::player::Player;
 
 
@@ -11,7 +12,7 @@ pub struct Intersect{
 
 pub fn cast_ray(
     framebuffer: &mut Framebuffer,
-    maze: &Vec<Vec<char>>,
+    maze: &[Vec<char>],
     player: &Player,
     a: f32,
     block_size: usize,
@@ -53,6 +54,6 @@ pub fn cast_ray(
 
         
 
-        d = d + 1.0;
+        d += 1.0;
     }
 }
\ No newline at end of file
@@ -1,39 +1,50 @@
 use gilrs::Gilrs;
-use minifb::{Key, Window, WindowOptions};
 use nalgebra_glm::Vec2;
+use render_core::{screenshot, window, AssetManager, Framebuffer, Key, Texture};
 use rusttype::{point, Font, Scale};
 use std::{f32::consts::PI, sync::Arc, time::Duration};
-mod framebuffer;
-use framebuffer::Framebuffer;
 mod render;
 use render::load_maze;
 mod player;
-use player::{process_events, GameState, Player};
+use player::{poll_gamepad, process_events, GameState, Player};
 mod castray;
 use castray::cast_ray;
-use once_cell::sync::Lazy;
-mod texture;
-use texture::Texture;
 mod music;
 use music::AudioPlayer;
 use std::time::Instant;
 
 
-static WALL1: Lazy<Arc<Texture>> = Lazy::new(|| Arc::new(Texture::new("assets/cornfields.jpg")));
-static JB1: Lazy<Arc<Texture>> = Lazy::new(|| Arc::new(Texture::new("assets/JB1.png")));
-static JB2: Lazy<Arc<Texture>> = Lazy::new(|| Arc::new(Texture::new("assets/JB2.png")));
-static DOOR: Lazy<Arc<Texture>> = Lazy::new(|| Arc::new(Texture::new("assets/doors.png")));
-static PLAYER: Lazy<Arc<Texture>> = Lazy::new(|| Arc::new(Texture::new("assets/mirror.png")));
+// Textures the game draws with. Built again from the asset manager when one
+// of the files changes on disk.
+struct GameTextures {
+    wall: Arc<Texture>,
+    jb1: Arc<Texture>,
+    jb2: Arc<Texture>,
+    door: Arc<Texture>,
+    player: Arc<Texture>,
+}
+
+impl GameTextures {
+    fn load(assets: &mut AssetManager) -> Self {
+        GameTextures {
+            wall: assets.texture("cornfields.jpg"),
+            jb1: assets.texture("JB1.png"),
+            jb2: assets.texture("JB2.png"),
+            door: assets.texture("doors.png"),
+            player: assets.texture("mirror.png"),
+        }
+    }
+}
 
 
-fn cell_texture_coloring(cell:char, tx:u32, ty:u32) -> u32{
+fn cell_texture_coloring(textures: &GameTextures, cell:char, tx:u32, ty:u32) -> u32{
     let default_color  = 0xFF1155;
 
     match cell {
-        '+' => WALL1.get_pixel_color(tx, ty),
-        '-' => WALL1.get_pixel_color(tx, ty),
-        '|' => WALL1.get_pixel_color(tx, ty),
-        'g' => DOOR.get_pixel_color(tx, ty),
+        '+' => textures.wall.get_pixel(tx, ty).to_hex(),
+        '-' => textures.wall.get_pixel(tx, ty).to_hex(),
+        '|' => textures.wall.get_pixel(tx, ty).to_hex(),
+        'g' => textures.door.get_pixel(tx, ty).to_hex(),
         _ => default_color
     }
 }
@@ -69,9 +80,9 @@ fn render2d(framebuffer: &mut Framebuffer, player: &Player, pathlab: &str){
     let maze = load_maze(pathlab);
     let block_size = 70;
 
-    for row in 0..maze.len(){
-        for col in 0..maze[row].len(){
-            draw_cell(framebuffer, col * block_size, row * block_size, block_size, maze[row][col])
+    for (row, cells) in maze.iter().enumerate(){
+        for (col, &cell) in cells.iter().enumerate(){
+            draw_cell(framebuffer, col * block_size, row * block_size, block_size, cell)
         }
     }
 
@@ -87,7 +98,7 @@ fn render2d(framebuffer: &mut Framebuffer, player: &Player, pathlab: &str){
     
 }
 
-fn render3d(framebuffer: &mut Framebuffer, player: &Player,z_buffer: &mut[f32], path:&str){
+fn render3d(framebuffer: &mut Framebuffer, player: &Player,z_buffer: &mut[f32], path:&str, textures: &GameTextures){
     let maze = load_maze(path);
     let block_size = 70;
     let num_rays = framebuffer.width;
@@ -97,9 +108,6 @@ fn render3d(framebuffer: &mut Framebuffer, player: &Player,z_buffer: &mut[f32],
             if (j % 105) != 0 {
                 framebuffer.set_foreground_color(0x85b1d6); 
             }
-            else if (j % 16) == 6 {
-                framebuffer.set_foreground_color(0xFFFFFF); 
-            }
             else {
                 framebuffer.set_foreground_color(0xFFFFFF);
             }
@@ -118,23 +126,23 @@ fn render3d(framebuffer: &mut Framebuffer, player: &Player,z_buffer: &mut[f32],
 
     framebuffer.set_foreground_color(0xF500F5);
     
-    for i in  0..num_rays {
+    for (i, depth) in z_buffer.iter_mut().enumerate().take(num_rays) {
         let current_ray = i as f32 / num_rays as f32;
         let a = player.a - (player.fov/2.0) + (player.fov * current_ray);
-        let intersect = cast_ray(framebuffer, &maze, &player, a, block_size, false);
+        let intersect = cast_ray(framebuffer, &maze, player, a, block_size, false);
 
         let distance = intersect.distance * (a - player.a).cos();
-        let stake_height = (framebuffer.height as f32 / distance as f32) * 110.0;
+        let stake_height = (framebuffer.height as f32 / distance) * 110.0;
 
         let stake_top = (hh - (stake_height/2.0)) as usize;
         let stake_bottom = (hh + (stake_height/2.0)) as usize;
         
-        z_buffer[i] = distance;
+        *depth = distance;
 
         for y in stake_top..stake_bottom {
             let ty = (y as f32 - stake_top as f32) / (stake_bottom as f32 - stake_top as f32) * 335.0; //335
             let tx = intersect.tx;
-            let color = cell_texture_coloring(intersect.impact, tx as u32, ty as u32);
+            let color = cell_texture_coloring(textures, intersect.impact, tx as u32, ty as u32);
             framebuffer.set_foreground_color(color);
             framebuffer.point(i, y)
         }
@@ -147,7 +155,7 @@ fn render3d(framebuffer: &mut Framebuffer, player: &Player,z_buffer: &mut[f32],
 }
 
 
-fn render_enemy(framebuffer: &mut Framebuffer, player: &Player, pos: &Vec2, z_buffer: &mut [f32]) {
+fn render_enemy(framebuffer: &mut Framebuffer, player: &Player, pos: &Vec2, z_buffer: &mut [f32], texture: &Texture) {
     let sprite_a = (pos.y - player.pos.y).atan2(pos.x - player.pos.x) - player.a;
 
     if sprite_a.abs() > player.fov / 2.0 {
@@ -172,27 +180,27 @@ fn render_enemy(framebuffer: &mut Framebuffer, player: &Player, pos: &Vec2, z_bu
     let start_x = start_x.max(0.0) as usize;
     let start_y = start_y.max(0.0) as usize;
 
-    if end_x <= 0 || start_x >= framebuffer.width {
+    if end_x == 0 || start_x >= framebuffer.width {
         return;
     }
 
-    for x in start_x..end_x {
-        if sprite_d < z_buffer[x] {
+    for (x, depth) in z_buffer.iter_mut().enumerate().take(end_x).skip(start_x) {
+        if sprite_d < *depth {
             for y in start_y..end_y {
                 let tx = ((x - start_x) as f32 * 330.0 / sprite_size) as u32;
                 let ty = ((y - start_y) as f32 * 330.0 / sprite_size) as u32;
-                let color = JB1.get_pixel_color(tx, ty);
+                let color = texture.get_pixel(tx, ty).to_hex();
                 if color != 0xf500ff {
                     framebuffer.set_foreground_color(color);
                     framebuffer.point(x, y);
                 }
             }
-            z_buffer[x] = sprite_d;
+            *depth = sprite_d;
         }
     }
 }
 
-fn render_enemy2(framebuffer: &mut Framebuffer, player: &Player, pos: &Vec2, z_buffer: &mut [f32]) {
+fn render_enemy2(framebuffer: &mut Framebuffer, player: &Player, pos: &Vec2, z_buffer: &mut [f32], texture: &Texture) {
     let sprite_a = (pos.y - player.pos.y).atan2(pos.x - player.pos.x) - player.a;
 
     if sprite_a.abs() > player.fov / 2.0 {
@@ -217,28 +225,28 @@ fn render_enemy2(framebuffer: &mut Framebuffer, player: &Player, pos: &Vec2, z_b
     let start_x = start_x.max(0.0) as usize;
     let start_y = start_y.max(0.0) as usize;
 
-    if end_x <= 0 || start_x >= framebuffer.width {
+    if end_x == 0 || start_x >= framebuffer.width {
         return;
     }
 
-    for x in start_x..end_x {
-        if sprite_d < z_buffer[x] {
+    for (x, depth) in z_buffer.iter_mut().enumerate().take(end_x).skip(start_x) {
+        if sprite_d < *depth {
             for y in start_y..end_y {
                 let tx = ((x - start_x) as f32 * 330.0 / sprite_size) as u32;
                 let ty = ((y - start_y) as f32 * 330.0 / sprite_size) as u32;
-                let color = JB2.get_pixel_color(tx, ty);
+                let color = texture.get_pixel(tx, ty).to_hex();
                 if color != 0xf500ff {
                     framebuffer.set_foreground_color(color);
                     framebuffer.point(x, y);
                 }
             }
-            z_buffer[x] = sprite_d;
+            *depth = sprite_d;
         }
     }
 }
 
 
-fn render_enemies(framebuffer: &mut Framebuffer, player: &Player, z_buffer: &mut[f32]){
+fn render_enemies(framebuffer: &mut Framebuffer, player: &Player, z_buffer: &mut[f32], textures: &GameTextures){
     let enemies = vec![
         Vec2::new(600.0, 800.0), //Que tan lejos izquierda
         Vec2::new(800.0, 400.0), //Que tan lejos derecha 
@@ -246,7 +254,7 @@ fn render_enemies(framebuffer: &mut Framebuffer, player: &Player, z_buffer: &mut
     ];
 
     for enemy in enemies{
-        render_enemy(framebuffer, &player, &enemy, z_buffer);
+        render_enemy(framebuffer, player, &enemy, z_buffer, &textures.jb1);
     }
 
     let enemies = vec![
@@ -256,13 +264,13 @@ fn render_enemies(framebuffer: &mut Framebuffer, player: &Player, z_buffer: &mut
     ];
 
     for enemy in enemies{
-        render_enemy2(framebuffer, &player, &enemy, z_buffer);
+        render_enemy2(framebuffer, player, &enemy, z_buffer, &textures.jb2);
     }
 }
 
-fn render_ui(framebuffer: &mut Framebuffer, time: f32) {
-    let ui_width = 320 as u32;
-    let ui_height = 320 as u32;
+fn render_ui(framebuffer: &mut Framebuffer, time: f32, texture: &Texture) {
+    let ui_width = 320_u32;
+    let ui_height = 320_u32;
     let ui_x = ((framebuffer.width as f32 / 2.0 + 100.0) - (ui_width as f32 / 2.0)) as u32;
     let ui_y = (framebuffer.height as f32 - ui_height as f32) as u32;
 
@@ -288,7 +296,7 @@ fn render_ui(framebuffer: &mut Framebuffer, time: f32) {
             let tx = x - ui_x;
             let ty = y - ui_y;
 
-            let pixel_color = PLAYER.get_pixel_color(tx, ty);
+            let pixel_color = texture.get_pixel(tx, ty).to_hex();
 
             // Shift the color if it's the target color
             let color_to_draw = if pixel_color == 0xF90000 {
@@ -316,16 +324,16 @@ fn render_minimap(framebuffer: &mut Framebuffer, player: &Player, scale: usize,
 
     // Fill the minimap area with white color
     framebuffer.set_foreground_color(0xFFFFFF); // White color
-    for x in 0..(minimap_width as usize) {
-        for y in 0..(minimap_height as usize) {
+    for x in 0..minimap_width {
+        for y in 0..minimap_height {
             framebuffer.point(x, y);
         }
     }
 
     // Draw the maze on the minimap
-    for row in 0..maze.len() {
-        for col in 0..maze[row].len() {
-            draw_cell(framebuffer, col * block_size, row * block_size, block_size, maze[row][col]);
+    for (row, cells) in maze.iter().enumerate() {
+        for (col, &cell) in cells.iter().enumerate() {
+            draw_cell(framebuffer, col * block_size, row * block_size, block_size, cell);
         }
     }
 
@@ -375,7 +383,7 @@ fn draw_menu(framebuffer: &mut Framebuffer, selected_index: usize) {
         }
     }
 
-    let menu_options = vec!["1.Level 1", "2.Level 2", "3.Level 3", "4.Level 4", "5.Quit"];
+    let menu_options = ["1.Level 1", "2.Level 2", "3.Level 3", "4.Level 4", "5.Quit"];
     let menu_x = 425;
     let mut menu_y = 275;
 
@@ -460,19 +468,36 @@ fn main() {
     let framebuffer_height = 900;
     let frame_delay = Duration::from_millis(0);
 
-    let mut gilrs = Gilrs::new().unwrap();
+    // No gamepad support is not a reason to stop, the keyboard still works
+    let mut gilrs = match Gilrs::new() {
+        Ok(gilrs) => Some(gilrs),
+        Err(err) => {
+            eprintln!("Playing without a gamepad: {}", err);
+            None
+        }
+    };
+    let mut assets = AssetManager::from_env();
+    let mut textures = GameTextures::load(&mut assets);
     let mut game_state = GameState::new();
     let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
-    let audioplay = Arc::new(AudioPlayer::new("assets/jb.mp3"));
-    audioplay.clone().play_in_background();
-
-    let mut window = Window::new(
-        "Escape from Bravo",
-        window_width, window_height, WindowOptions::default()
-    ).unwrap();
+    let audioplay = match AudioPlayer::new("assets/jb.mp3") {
+        Ok(player) => Some(player),
+        Err(err) => {
+            eprintln!("Playing without music: {}", err);
+            None
+        }
+    };
+    if let Some(audioplay) = &audioplay {
+        audioplay.play_in_background();
+    }
 
-    window.set_position(100, 100);
-    window.update();
+    let mut window = match window::open("Escape from Bravo", window_width, window_height) {
+        Ok(window) => window,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
 
     framebuffer.set_background_color(0x6b6565);
 
@@ -498,13 +523,18 @@ fn main() {
     while window.is_open() {
         frame_count += 1;
         time += 0.05;
+
+        // Textures edited on disk show up without restarting
+        if !assets.reload_changed().is_empty() {
+            textures = GameTextures::load(&mut assets);
+        }
     
 
         if menu_visible {
             // Render the menu
             framebuffer.clear();
             draw_menu(&mut framebuffer, selected_option);
-            window.update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height).unwrap();
+            window.present(&framebuffer);
 
             // Handle menu input
             if window.is_key_down(Key::Key1) {
@@ -555,29 +585,34 @@ fn main() {
             }
 
             if window.is_key_down(Key::M) {
-                audioplay.clone().stop_in_background();
+                if let Some(audioplay) = &audioplay {
+                    audioplay.stop_in_background();
+                }
             }
 
 
 
-            process_events(&window, &mut player, &load_maze(&pathlab), 70, &mut gilrs, &mut game_state);
+            if let Some(gilrs) = &mut gilrs {
+                window.feed_gamepad(&poll_gamepad(gilrs));
+            }
+            process_events(window.as_ref(), &mut player, &load_maze(pathlab), 70, &mut game_state);
             framebuffer.clear();
 
             let mode = "3D"; // Replace with actual mode logic
             if mode == "2D" {
-                render2d(&mut framebuffer, &player, &pathlab);
+                render2d(&mut framebuffer, &player, pathlab);
             } else {
                 let mut z_buffer = vec![f32::INFINITY; framebuffer.width];
-                render3d(&mut framebuffer, &player, &mut z_buffer, &pathlab);
-                render_enemies(&mut framebuffer, &player, &mut z_buffer);
-                render_ui(&mut framebuffer, time as f32);
-                render_minimap(&mut framebuffer, &player, 5, &pathlab);
+                render3d(&mut framebuffer, &player, &mut z_buffer, pathlab, &textures);
+                render_enemies(&mut framebuffer, &player, &mut z_buffer, &textures);
+                render_ui(&mut framebuffer, time as f32, &textures.player);
+                render_minimap(&mut framebuffer, &player, 5, pathlab);
             }
 
             if is_near_escape_point(player.pos, escape_point, escape_radius) {
                 framebuffer.clear();
                 draw_menucongrat(&mut framebuffer); // Adjust text color and position
-                window.update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height).unwrap();
+                window.present(&framebuffer);
                 if window.is_key_down(Key::Backspace) {
                     break; // Exit the game loop or handle win state
                 }
@@ -596,11 +631,11 @@ fn main() {
                 window.set_title(&title);
             }
 
-            window
-                .update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height)
-                .unwrap();
+            window.present(&framebuffer);
         }
 
+        // Whatever was presented last, the menu or the game
+        screenshot::handle_hotkey(window.as_ref(), &framebuffer);
         std::thread::sleep(frame_delay);
     }
 }
@@ -10,31 +10,32 @@ pub struct AudioPlayer {
 }
 
 impl AudioPlayer {
-    pub fn new(music_file: &str) -> Self {
-        let (stream, stream_handle) = OutputStream::try_default().unwrap();
-        let sink = Sink::try_new(&stream_handle).unwrap();
+    // Fails on machines without a sound device, like a headless test runner
+    pub fn new(music_file: &str) -> Result<Self, String> {
+        let (stream, stream_handle) = OutputStream::try_default().map_err(|err| err.to_string())?;
+        let sink = Sink::try_new(&stream_handle).map_err(|err| err.to_string())?;
 
-        let file = BufReader::new(File::open(music_file).unwrap());
-        let source = Decoder::new(file).unwrap();
+        let file = BufReader::new(File::open(music_file).map_err(|err| format!("{}: {}", music_file, err))?);
+        let source = Decoder::new(file).map_err(|err| format!("{}: {}", music_file, err))?;
         sink.append(source);
         sink.set_volume(0.5);
 
-        AudioPlayer {
+        Ok(AudioPlayer {
             sink: Arc::new(Mutex::new(sink)),
             _stream: stream,
-        }
+        })
     }
 
-    pub fn play_in_background(self: Arc<Self>) {
+    pub fn play_in_background(&self) {
         let sink = Arc::clone(&self.sink);
         thread::spawn(move || {
-            let mut sink = sink.lock().unwrap();
+            let sink = sink.lock().unwrap();
             sink.play();
             sink.sleep_until_end(); // Keep the thread alive until the music ends
         });
     }
 
-    pub fn stop_in_background(self: Arc<Self>) {
+    pub fn stop_in_background(&self) {
         let sink = Arc::clone(&self.sink);
         thread::spawn(move || {
             // Wait for a while before stopping the music (for demonstration)
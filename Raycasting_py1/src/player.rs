@@ -1,7 +1,7 @@
 use std::f32::consts::PI;
-use gilrs::{Axis, Button, Gilrs, Event};
+use gilrs::{Axis, Button, Gilrs, Event, EventType};
 use nalgebra_glm::Vec2;
-use minifb::{Key, Window};
+use render_core::{GamepadAxis, GamepadButton, GamepadEvent, Key, WindowBackend};
 
 pub struct Player {
     pub pos: Vec2,
@@ -24,11 +24,10 @@ impl GameState {
 }
 
 pub fn process_events(
-    window: &Window,
+    window: &dyn WindowBackend,
     player: &mut Player,
-    maze: &Vec<Vec<char>>,
+    maze: &[Vec<char>],
     block_size: usize,
-    gilrs: &mut Gilrs,
     game_state: &mut GameState,
 ) {
     const MOVE_SPEED: f32 = 3.0;
@@ -38,7 +37,7 @@ pub fn process_events(
     const TWO_PI: f32 = PI * 2.0;
 
      // Handle mouse movement
-     let (mouse_x, mouse_y) = window.get_mouse_pos(minifb::MouseMode::Clamp).unwrap_or((0.0, 0.0));
+     let (mouse_x, mouse_y) = window.mouse_position().unwrap_or((0.0, 0.0));
      // Calculate mouse movement delta
      let mouse_delta_x = (mouse_x - game_state.prev_mouse_x) * MOUSE_SENSITIVITY;
     
@@ -77,27 +76,27 @@ pub fn process_events(
         }
     }
 
-    if window.is_key_pressed(Key::NumPad4, minifb::KeyRepeat::No) {
+    if window.is_key_pressed(Key::NumPad4) {
         player.a = 0.0;
     }
-    if window.is_key_pressed(Key::NumPad6, minifb::KeyRepeat::No) {
+    if window.is_key_pressed(Key::NumPad6) {
         player.a = PI;
     }
-    if window.is_key_pressed(Key::NumPad2, minifb::KeyRepeat::No) {
+    if window.is_key_pressed(Key::NumPad2) {
         player.a = 1.5 * PI;
     }
-    if window.is_key_pressed(Key::NumPad8, minifb::KeyRepeat::No) {
+    if window.is_key_pressed(Key::NumPad8) {
         player.a = 0.5 * PI;
     }
 
 
-    while let Some(Event { event, .. }) = gilrs.next_event() {
+    for &event in window.gamepad_events() {
         match event {
-            gilrs::EventType::ButtonPressed(button, _) => {
+            GamepadEvent::Pressed(button) => {
                 match button {
-                    Button::DPadLeft => player.a -= ROTATION_SPEED,
-                    Button::DPadRight => player.a += ROTATION_SPEED,
-                    Button::DPadUp => {
+                    GamepadButton::DPadLeft => player.a -= ROTATION_SPEED,
+                    GamepadButton::DPadRight => player.a += ROTATION_SPEED,
+                    GamepadButton::DPadUp => {
                         new_pos.x += MOVE_SPEED * player.a.cos();
                         new_pos.y += MOVE_SPEED * player.a.sin();
 
@@ -105,7 +104,7 @@ pub fn process_events(
                             player.pos = new_pos;
                         }
                     }
-                    Button::DPadDown => {
+                    GamepadButton::DPadDown => {
                         new_pos.x -= MOVE_SPEED * player.a.cos();
                         new_pos.y -= MOVE_SPEED * player.a.sin();
 
@@ -116,21 +115,17 @@ pub fn process_events(
                     _ => {}
                 }
             }
-            gilrs::EventType::AxisChanged(axis, value, _) => {
+            GamepadEvent::Axis(axis, value) => {
                 match axis {
-                    Axis::LeftStickX => {
-                        if value.abs() > 0.1 {
-                            player.a -= ROTATION_SPEED * value.signum();
-                        }
+                    GamepadAxis::LeftStickX if value.abs() > 0.1 => {
+                        player.a -= ROTATION_SPEED * value.signum();
                     }
-                    Axis::LeftStickY => {
-                        if value.abs() > 0.1 {
-                            new_pos.x += MOVE_SPEED * player.a.cos() * value;
-                            new_pos.y += MOVE_SPEED * player.a.sin() * value;
-
-                            if !is_colliding_with_wall(&new_pos, maze, block_size) {
-                                player.pos = new_pos;
-                            }
+                    GamepadAxis::LeftStickY if value.abs() > 0.1 => {
+                        new_pos.x += MOVE_SPEED * player.a.cos() * value;
+                        new_pos.y += MOVE_SPEED * player.a.sin() * value;
+
+                        if !is_colliding_with_wall(&new_pos, maze, block_size) {
+                            player.pos = new_pos;
                         }
                     }
                     _ => {}
@@ -140,7 +135,7 @@ pub fn process_events(
         }
     }
 
-    fn is_colliding_with_wall(pos: &Vec2, maze: &Vec<Vec<char>>, block_size: usize) -> bool {
+    fn is_colliding_with_wall(pos: &Vec2, maze: &[Vec<char>], block_size: usize) -> bool {
         let x = pos.x as usize / block_size;
         let y = pos.y as usize / block_size;
 
@@ -156,3 +151,55 @@ pub fn process_events(
 
     
 }
+
+// Gamepad events since the last frame, in the form the window records and
+// replays. Buttons and axes the game doesn't know are dropped.
+pub fn poll_gamepad(gilrs: &mut Gilrs) -> Vec<GamepadEvent> {
+    let mut events = Vec::new();
+
+    while let Some(Event { event, .. }) = gilrs.next_event() {
+        match event {
+            EventType::ButtonPressed(button, _) => {
+                if let Some(button) = gamepad_button(button) {
+                    events.push(GamepadEvent::Pressed(button));
+                }
+            }
+            EventType::ButtonReleased(button, _) => {
+                if let Some(button) = gamepad_button(button) {
+                    events.push(GamepadEvent::Released(button));
+                }
+            }
+            EventType::AxisChanged(axis, value, _) => {
+                let axis = match axis {
+                    Axis::LeftStickX => GamepadAxis::LeftStickX,
+                    Axis::LeftStickY => GamepadAxis::LeftStickY,
+                    Axis::RightStickX => GamepadAxis::RightStickX,
+                    Axis::RightStickY => GamepadAxis::RightStickY,
+                    _ => continue,
+                };
+                events.push(GamepadEvent::Axis(axis, value));
+            }
+            _ => {}
+        }
+    }
+
+    events
+}
+
+fn gamepad_button(button: Button) -> Option<GamepadButton> {
+    match button {
+        Button::South => Some(GamepadButton::South),
+        Button::East => Some(GamepadButton::East),
+        Button::West => Some(GamepadButton::West),
+        Button::North => Some(GamepadButton::North),
+        Button::DPadUp => Some(GamepadButton::DPadUp),
+        Button::DPadDown => Some(GamepadButton::DPadDown),
+        Button::DPadLeft => Some(GamepadButton::DPadLeft),
+        Button::DPadRight => Some(GamepadButton::DPadRight),
+        Button::LeftTrigger => Some(GamepadButton::LeftTrigger),
+        Button::RightTrigger => Some(GamepadButton::RightTrigger),
+        Button::Select => Some(GamepadButton::Select),
+        Button::Start => Some(GamepadButton::Start),
+        _ => None,
+    }
+}
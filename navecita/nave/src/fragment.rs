@@ -2,9 +2,9 @@
 
 use std::f32::consts::PI;
 
-use nalgebra_glm::{fract, sin, Vec2, Vec3};
-use rand::{random, rngs::StdRng, Rng, SeedableRng};
-use crate::{colors::Color, uniform::{self, Uniforms}};
+use nalgebra_glm::{Vec2, Vec3};
+use render_core::Color;
+use crate::uniform::Uniforms;
 
 pub struct Fragment {
     pub position: Vec2,
@@ -37,7 +37,7 @@ impl Fragment {
 
 //Para hacer las combinaciones de los planetas
 
-pub fn fragment_shader(fragment: &Fragment, uniform: &Uniforms) -> Color {
+pub fn fragment_shader(fragment: &Fragment, _uniform: &Uniforms) -> Color {
     // Central point of the circles (e.g., the origin or center of the viewport)
     let center = Vec3::new(0.0, 0.0, 0.0); // Adjust as needed for the center of your circles
 
@@ -215,14 +215,7 @@ pub fn combining_eath(fragment: &Fragment, uniform:&Uniforms) -> Color{
     let noise_color = if noise_value < spot_threshold{
         
         if ((noise_value * 10.0) as usize % 2) == 1 {
-
-            if (0.0..=20.0).contains(&variable_time) {
-                base_color2
-            }
-            else {
-                base_color2
-            }
-
+            base_color2
         }
         else {
             spot_color    
@@ -288,7 +281,7 @@ pub fn glow_shader(fragment: &Fragment) -> Color {
     let glow_size = 0.25;
 
 
-    let distance_to_center = (y % stripe_width - stripe_width/2.0);
+    let distance_to_center = y % stripe_width - stripe_width/2.0;
     let glow_intensity = ((1.0 - (distance_to_center / glow_size).min(1.0)) * PI /2.0).sin();
 
     Color::new(
@@ -330,6 +323,7 @@ pub fn background_shader(_fragment: &Fragment) -> Color{
 }
 
 
+#[allow(dead_code)]
 pub fn neon_light_shader(fragment: &Fragment) -> Color{
     let background = background_shader(fragment);
     let glow = glow_shader(fragment);
@@ -347,7 +341,7 @@ pub fn random_color_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color{
 
     let seed = uniforms.time as usize;
 
-    let galaxy_colors = vec![
+    let galaxy_colors = [
         Color::new(140, 140, 140), // Medium gray
         Color::new(100, 100, 100), // Medium dark gray
         Color::new(60, 60, 60),    // Dark gray
@@ -384,7 +378,7 @@ pub fn sun_shader(fragment: &Fragment, uniform:&Uniforms) -> Color{
     let glow = glow_shader(fragment);
     
     
-    let noise_color = if noise_value < spot_threshold{
+    if noise_value < spot_threshold{
         
         if ((noise_value * 10.0) as usize % 2) == 1 {
 
@@ -392,8 +386,7 @@ pub fn sun_shader(fragment: &Fragment, uniform:&Uniforms) -> Color{
                 base_color2
             }
             else {
-                let glow_color = base_color2.blend_screen(&glow);
-                glow_color
+                base_color2.blend_screen(&glow)
             }
 
         }
@@ -405,8 +398,5 @@ pub fn sun_shader(fragment: &Fragment, uniform:&Uniforms) -> Color{
 
         base_color
         
-    };
-
-
-    noise_color
+    }
 }
\ No newline at end of file
@@ -1,13 +1,8 @@
-use colors::Color;
-use fastnoise_lite::{FastNoiseLite, NoiseType};
 use fragment::Fragment;
-use minifb::{Key, Window, WindowOptions};
-use nalgebra_glm::{dot, Mat4, Vec3};
+use nalgebra_glm::{dot, Vec3};
+use render_core::{screenshot, window, Camera, Color, Framebuffer, Key};
 use std::{f32::consts::PI, time::Duration};
 use vertex::Vertex;
-mod framebuffer;
-use framebuffer::Framebuffer;
-mod colors;
 mod fragment;
 mod uniform;
 mod vertex;
@@ -18,8 +13,6 @@ use uniform::{
 mod obj;
 mod vertexshader;
 use obj::Obj;
-mod camera;
-use camera::Camera;
 
 fn calculate_bounding_box(v1: &Vec3, v2: &Vec3, v3: &Vec3) -> (i32, i32, i32, i32) {
     let min_x = v1.x.min(v2.x).min(v3.x).floor() as i32;
@@ -65,7 +58,7 @@ pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> Vec<Fragment> {
             let (w1, w2, w3) = barycentric_coordinates(&point, &a, &b, &c, triangle_area);
 
             // Check if the point is inside the triangle
-            if w1 >= 0.0 && w1 <= 1.0 && w2 >= 0.0 && w2 <= 1.0 && w3 >= 0.0 && w3 <= 1.0 {
+            if (0.0..=1.0).contains(&w1) && (0.0..=1.0).contains(&w2) && (0.0..=1.0).contains(&w3) {
                 // Interpolate normal
                 // let normal = v1.transformed_normal * w1 + v2.transformed_normal * w2 + v3.transformed_normal * w3;
                 let normal = v1.transformed_normal;
@@ -107,22 +100,22 @@ fn main() {
     let window_width = 800;
     let window_height = 600;
     let mut framebuffer = Framebuffer::new(window_width, window_height);
-    let start_time = std::time::Instant::now();
+    framebuffer.set_background_color(0x191970);
 
-    let mut window = Window::new(
-        "Mother Zeta Fallut 3",
-        window_width,
-        window_height,
-        WindowOptions::default(),
-    )
-    .unwrap();
+    let mut window = match window::open("Mother Zeta Fallut 3", window_width, window_height) {
+        Ok(window) => window,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
 
     let mut obj = Obj::load("./assets/sphere-1.obj").expect("Failed to load .obj file");
     let mut vertex_array = obj.get_vertex_array();
 
-    let mut translation = Vec3::new(0.0, 0.0, 0.0);
+    let translation = Vec3::new(0.0, 0.0, 0.0);
     let scale_factor = 1.0f32;
-    let mut rotation_angles = Vec3::new(0.0, 0.0, 0.0);
+    let rotation_angles = Vec3::new(0.0, 0.0, 0.0);
 
     let mut camera = Camera::new(
         Vec3::new(0.0, 0.0, 5.0),
@@ -131,16 +124,10 @@ fn main() {
         false,
     );
 
-    //CAMARA
-    let mut eye = Vec3::new(0.0, 0.0, 5.0);
-    let mut center = Vec3::new(0.0, 0.0, 0.0);
-    let up = Vec3::new(0.0, 1.0, 0.0);
-
     let mut blend_type = "2";
 
     while window.is_open() && !window.is_key_down(Key::Escape) {
-        let elapsed = start_time.elapsed();
-        let time = elapsed.as_secs_f32() as u32; //
+        let time = window.time() as u32;
 
         let movement_speed = 1.0;
         let rotation_speed = PI / 50.0;
@@ -241,7 +228,7 @@ fn main() {
         let projection_matrix =
             create_perspective_matrix(window_width as f32, window_height as f32); // Set up perspective projection
         let viewport_matrix = create_viewport_matrix(window_width as f32, window_height as f32);
-        let mut noise = create_noise(&blend_type);
+        let noise = create_noise(blend_type);
 
         let uniforms = Uniforms {
             model_matrix,
@@ -249,14 +236,13 @@ fn main() {
             projection_matrix,
             viewport_matrix,
             time,
-            noise: noise,
+            noise,
         };
 
-        render(&mut framebuffer, &uniforms, &vertex_array, &blend_type); // Render using the framebuffer
+        render(&mut framebuffer, &uniforms, &vertex_array, blend_type); // Render using the framebuffer
 
-        window
-            .update_with_buffer(&framebuffer.buffer, framebuffer.width, framebuffer.height)
-            .unwrap();
+        screenshot::handle_hotkey(window.as_ref(), &framebuffer);
+        window.present(&framebuffer);
         std::thread::sleep(Duration::from_millis(5)); // Control frame rate
     }
 }
@@ -1,5 +1,4 @@
 // obj.rs
-use tobj;
 use nalgebra_glm::{Vec2, Vec3};
 use crate::vertex::Vertex;
 
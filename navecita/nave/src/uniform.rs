@@ -1,13 +1,13 @@
 use std::f32::consts::PI;
 
-use crate::fragment::{combining_eath, combining_nubes_shader, fragment_shader, neon_light_shader, random_color_shader, static_pattern_shader, sun_shader, survivingmars
+use crate::fragment::{combining_eath, combining_nubes_shader, fragment_shader, random_color_shader, static_pattern_shader, sun_shader, survivingmars
 };
-use crate::framebuffer::Framebuffer;
 use crate::triangle;
 use crate::vertex::Vertex;
 use crate::vertexshader::vertex_shader;
 use fastnoise_lite::{FastNoiseLite, NoiseType};
-use nalgebra_glm::{look_at, perspective, scaling, translation, Mat4, Vec3};
+use nalgebra_glm::{look_at, perspective, Mat4, Vec3};
+use render_core::Framebuffer;
 
 pub struct Uniforms {
     pub model_matrix: Mat4,
@@ -54,50 +54,57 @@ pub fn render(
         let x = fragment.position.x as usize;
         let y = fragment.position.y as usize;
         if x < framebuffer.width && y < framebuffer.height {
-            // let shaded_color = fragment_shader(&fragment, &uniforms);
+            // let shaded_color = fragment_shader(&fragment, uniforms);
 
             //Saturno
             if blend_mode == "1" {
-                let shaded_color = fragment_shader(&fragment, &uniforms);
+                let shaded_color = fragment_shader(&fragment, uniforms);
                 let color = shaded_color.to_hex();
-                framebuffer.point(x, y, color, fragment.depth);
+                framebuffer.set_foreground_color(color);
+                framebuffer.point_with_depth(x, y, fragment.depth);
             }
 
             //SOL
             if blend_mode == "2" {
-                let shaded_color = sun_shader(&fragment, &uniforms);
+                let shaded_color = sun_shader(&fragment, uniforms);
                 let color = shaded_color.to_hex();
-                framebuffer.point(x, y, color, fragment.depth);
+                framebuffer.set_foreground_color(color);
+                framebuffer.point_with_depth(x, y, fragment.depth);
             }
 
             if blend_mode == "3" {
-                let shaded_color = combining_nubes_shader(&fragment, &uniforms);
+                let shaded_color = combining_nubes_shader(&fragment, uniforms);
                 let color = shaded_color.to_hex();
-                framebuffer.point(x, y, color, fragment.depth);
+                framebuffer.set_foreground_color(color);
+                framebuffer.point_with_depth(x, y, fragment.depth);
             }
 
                 //Tierra
             if blend_mode == "4" {
-                let shaded_color = combining_eath(&fragment, &uniforms);
+                let shaded_color = combining_eath(&fragment, uniforms);
                 let color = shaded_color.to_hex();
-                framebuffer.point(x, y, color, fragment.depth);
+                framebuffer.set_foreground_color(color);
+                framebuffer.point_with_depth(x, y, fragment.depth);
             }
             if blend_mode == "5" {
-                let shaded_color = survivingmars(&fragment, &uniforms);
+                let shaded_color = survivingmars(&fragment, uniforms);
                 let color = shaded_color.to_hex();
-                framebuffer.point(x, y, color, fragment.depth);
+                framebuffer.set_foreground_color(color);
+                framebuffer.point_with_depth(x, y, fragment.depth);
             }
 
             if blend_mode == "6" {
-                let shaded_color = random_color_shader(&fragment, &uniforms);
+                let shaded_color = random_color_shader(&fragment, uniforms);
                 let color = shaded_color.to_hex();
-                framebuffer.point(x, y, color, fragment.depth);
+                framebuffer.set_foreground_color(color);
+                framebuffer.point_with_depth(x, y, fragment.depth);
             }
 
             if blend_mode == "7" {
-                let shaded_color = static_pattern_shader(&fragment, &uniforms);
+                let shaded_color = static_pattern_shader(&fragment, uniforms);
                 let color = shaded_color.to_hex();
-                framebuffer.point(x, y, color, fragment.depth);
+                framebuffer.set_foreground_color(color);
+                framebuffer.point_with_depth(x, y, fragment.depth);
             }
         }
     }
@@ -1,7 +1,7 @@
 // vertex.rs
 
 use nalgebra_glm::{Vec2, Vec3};
-use crate::colors::Color;
+use render_core::Color;
 
 #[derive(Clone, Debug)]
 pub struct Vertex {
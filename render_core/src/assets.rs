@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use crate::texture::Texture;
+
+// Folder textures are looked up in unless ASSET_ROOT says otherwise
+pub const DEFAULT_ASSET_ROOT: &str = "assets";
+
+struct CachedTexture {
+    texture: Arc<Texture>,
+    // None when the file couldn't be read and the fallback is cached instead
+    modified: Option<SystemTime>,
+}
+
+// Loads each texture once by file name and hands out shared copies. Files
+// that are missing or broken are reported and replaced by Texture::missing().
+pub struct AssetManager {
+    root: PathBuf,
+    textures: HashMap<String, CachedTexture>,
+    missing: Arc<Texture>,
+}
+
+impl AssetManager {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        AssetManager {
+            root: root.into(),
+            textures: HashMap::new(),
+            missing: Arc::new(Texture::missing()),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        AssetManager::new(std::env::var("ASSET_ROOT").unwrap_or_else(|_| DEFAULT_ASSET_ROOT.to_string()))
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.root.join(name)
+    }
+
+    fn modified(&self, name: &str) -> Option<SystemTime> {
+        fs::metadata(self.path(name)).and_then(|metadata| metadata.modified()).ok()
+    }
+
+    // The cached texture, loading it on first use
+    pub fn load(&mut self, name: &str) -> Result<Arc<Texture>, String> {
+        if let Some(cached) = self.textures.get(name) {
+            return Ok(cached.texture.clone());
+        }
+
+        let texture = Arc::new(Texture::load(&self.path(name).to_string_lossy())?);
+        let modified = self.modified(name);
+        self.textures.insert(name.to_string(), CachedTexture { texture: texture.clone(), modified });
+        Ok(texture)
+    }
+
+    // load, but a failure is printed once and the fallback takes its place
+    pub fn texture(&mut self, name: &str) -> Arc<Texture> {
+        match self.load(name) {
+            Ok(texture) => texture,
+            Err(err) => {
+                eprintln!("Missing texture: {}", err);
+                let texture = self.missing.clone();
+                self.textures.insert(name.to_string(), CachedTexture { texture: texture.clone(), modified: None });
+                texture
+            }
+        }
+    }
+
+    // Reloads textures whose file changed (or showed up) since they were
+    // loaded and returns the names that were reloaded. Copies handed out
+    // before keep the old image, so their holders have to ask again.
+    pub fn reload_changed(&mut self) -> Vec<String> {
+        let changed: Vec<String> = self
+            .textures
+            .iter()
+            .filter(|(name, cached)| {
+                let modified = self.modified(name);
+                modified.is_some() && modified != cached.modified
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        let mut reloaded = Vec::new();
+        for name in changed {
+            let modified = self.modified(&name);
+            match Texture::load(&self.path(&name).to_string_lossy()) {
+                Ok(texture) => {
+                    self.textures.insert(name.clone(), CachedTexture { texture: Arc::new(texture), modified });
+                    reloaded.push(name);
+                }
+                Err(err) => {
+                    // Keep the old image until the file changes again
+                    eprintln!("Could not reload texture: {}", err);
+                    if let Some(cached) = self.textures.get_mut(&name) {
+                        cached.modified = modified;
+                    }
+                }
+            }
+        }
+
+        reloaded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use image::{Rgba, RgbaImage};
+    use std::fs::File;
+    use std::time::Duration;
+
+    fn asset_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("render_core_assets_{}_{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_png(path: &PathBuf, color: [u8; 4]) {
+        RgbaImage::from_pixel(2, 2, Rgba(color)).save(path).unwrap();
+    }
+
+    #[test]
+    fn test_missing_files_fall_back() {
+        let dir = asset_dir("missing");
+        let mut assets = AssetManager::new(&dir);
+
+        assert!(assets.load("nope.png").is_err());
+        let texture = assets.texture("nope.png");
+        assert!(Arc::ptr_eq(&texture, &assets.missing));
+        // Cached, so asking again doesn't go back to the disk
+        assert!(Arc::ptr_eq(&assets.load("nope.png").unwrap(), &assets.missing));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_repeated_loads_share_one_texture() {
+        let dir = asset_dir("dedup");
+        write_png(&dir.join("red.png"), [255, 0, 0, 255]);
+        let mut assets = AssetManager::new(&dir);
+
+        let first = assets.texture("red.png");
+        let second = assets.texture("red.png");
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(first.get_pixel(0, 0), Color::new(255, 0, 0));
+        assert_eq!(assets.textures.len(), 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_reload_picks_up_newer_files() {
+        let dir = asset_dir("reload");
+        let path = dir.join("block.png");
+        write_png(&path, [255, 0, 0, 255]);
+        let mut assets = AssetManager::new(&dir);
+        let old = assets.texture("block.png");
+        let fallback = assets.texture("later.png");
+
+        assert!(assets.reload_changed().is_empty());
+
+        // A newer modification time than the one cached, without waiting for
+        // the clock to tick over
+        write_png(&path, [0, 0, 255, 255]);
+        let newer = assets.textures["block.png"].modified.unwrap() + Duration::from_secs(5);
+        File::options().write(true).open(&path).unwrap().set_modified(newer).unwrap();
+        // A file that was missing shows up
+        write_png(&dir.join("later.png"), [0, 255, 0, 255]);
+
+        let mut reloaded = assets.reload_changed();
+        reloaded.sort();
+        assert_eq!(reloaded, vec!["block.png".to_string(), "later.png".to_string()]);
+        assert_eq!(assets.texture("block.png").get_pixel(0, 0), Color::new(0, 0, 255));
+        assert_eq!(assets.texture("later.png").get_pixel(0, 0), Color::new(0, 255, 0));
+        // Copies handed out before keep the old image
+        assert_eq!(old.get_pixel(0, 0), Color::new(255, 0, 0));
+        assert!(Arc::ptr_eq(&fallback, &assets.missing));
+
+        assert!(assets.reload_changed().is_empty());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
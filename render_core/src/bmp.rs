@@ -0,0 +1,226 @@
+use std::fs;
+use std::io;
+
+// Size of the file header plus the BITMAPINFOHEADER this module writes
+const HEADER_SIZE: usize = 54;
+
+// 32-bit uncompressed BMP. Rows are stored bottom-up, the way a positive
+// height tells readers to expect them.
+pub fn write_bmp_file(file_path: &str, buffer: &[u32], width: usize, height: usize) -> io::Result<()> {
+    fs::write(file_path, encode_bmp(buffer, width, height))
+}
+
+pub fn encode_bmp(buffer: &[u32], width: usize, height: usize) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_SIZE + buffer.len() * 4);
+
+    // BMP file header
+    bytes.extend_from_slice(b"BM");
+    bytes.extend_from_slice(&((HEADER_SIZE + buffer.len() * 4) as u32).to_le_bytes());
+    bytes.extend_from_slice(&[0; 4]);
+    bytes.extend_from_slice(&(HEADER_SIZE as u32).to_le_bytes());
+
+    // DIB header
+    bytes.extend_from_slice(&40u32.to_le_bytes());
+    bytes.extend_from_slice(&(width as i32).to_le_bytes());
+    bytes.extend_from_slice(&(height as i32).to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&32u16.to_le_bytes());
+    bytes.extend_from_slice(&[0; 24]);
+
+    // Pixel data, 0x00RRGGBB is BGRA in little endian
+    for row in buffer.chunks(width.max(1)).rev() {
+        for &pixel in row {
+            bytes.extend_from_slice(&pixel.to_le_bytes());
+        }
+    }
+
+    bytes
+}
+
+// Pixels as 0xRRGGBB from the top left, with the width and height
+pub fn read_bmp_file(file_path: &str) -> io::Result<(Vec<u32>, usize, usize)> {
+    decode_bmp(&fs::read(file_path)?).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", file_path, err)))
+}
+
+// Uncompressed 24 and 32-bit BMPs, bottom-up (positive height) or top-down
+// (negative height), including 32-bit ones with BI_BITFIELDS channel masks.
+// Alpha is dropped.
+pub fn decode_bmp(bytes: &[u8]) -> Result<(Vec<u32>, usize, usize), String> {
+    let u16_at = |offset: usize| bytes.get(offset..offset + 2).map(|b| u16::from_le_bytes([b[0], b[1]]));
+    let u32_at = |offset: usize| bytes.get(offset..offset + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+    let truncated = || "truncated header".to_string();
+
+    if bytes.get(0..2) != Some(b"BM".as_slice()) {
+        return Err("not a BMP file".to_string());
+    }
+    let pixel_offset = u32_at(10).ok_or_else(truncated)? as usize;
+    let dib_size = u32_at(14).ok_or_else(truncated)? as usize;
+    if dib_size < 40 {
+        return Err(format!("unsupported DIB header of {} bytes", dib_size));
+    }
+
+    let width = u32_at(18).ok_or_else(truncated)? as i32;
+    let height = u32_at(22).ok_or_else(truncated)? as i32;
+    let bits_per_pixel = u16_at(28).ok_or_else(truncated)?;
+    let compression = u32_at(30).ok_or_else(truncated)?;
+    if width <= 0 || height == 0 {
+        return Err(format!("invalid size {}x{}", width, height));
+    }
+    let (width, top_down) = (width as usize, height < 0);
+    let height = height.unsigned_abs() as usize;
+
+    // Where each channel sits in a 32-bit pixel
+    let masks = match (bits_per_pixel, compression) {
+        (24, 0) | (32, 0) => [0x00FF0000, 0x0000FF00, 0x000000FF],
+        // Masks follow a 40-byte header, and are part of the larger ones
+        (32, 3) => [
+            u32_at(54).ok_or_else(truncated)?,
+            u32_at(58).ok_or_else(truncated)?,
+            u32_at(62).ok_or_else(truncated)?,
+        ],
+        _ => return Err(format!("unsupported {}-bit BMP with compression {}", bits_per_pixel, compression)),
+    };
+
+    let bytes_per_pixel = bits_per_pixel as usize / 8;
+    let too_large = || format!("image of {}x{} is too large", width, height);
+    // Rows are padded to a multiple of 4 bytes
+    let row_size = width.checked_mul(bytes_per_pixel).ok_or_else(too_large)?.div_ceil(4) * 4;
+    let data_size = row_size.checked_mul(height).ok_or_else(too_large)?;
+    let pixel_count = width.checked_mul(height).ok_or_else(too_large)?;
+    // Every pixel takes at least 3 bytes of the file, so once the pixel
+    // data is known to be there the buffer can't outgrow the file
+    let pixel_data = pixel_offset
+        .checked_add(data_size)
+        .and_then(|end| bytes.get(pixel_offset..end))
+        .ok_or_else(|| "truncated pixel data".to_string())?;
+
+    let mut buffer = vec![0; pixel_count];
+    for (row_index, row) in pixel_data.chunks_exact(row_size).enumerate() {
+        let y = if top_down { row_index } else { height - 1 - row_index };
+        for x in 0..width {
+            let pixel = &row[x * bytes_per_pixel..(x + 1) * bytes_per_pixel];
+            buffer[y * width + x] = if bytes_per_pixel == 3 {
+                u32::from_le_bytes([pixel[0], pixel[1], pixel[2], 0])
+            } else {
+                let value = u32::from_le_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]);
+                let [r, g, b] = masks.map(|mask| channel(value, mask));
+                (r << 16) | (g << 8) | b
+            };
+        }
+    }
+
+    Ok((buffer, width, height))
+}
+
+// Value of the channel under mask, scaled to 0..=255. Done in u64 since
+// a mask can be up to 32 bits wide.
+fn channel(value: u32, mask: u32) -> u32 {
+    if mask == 0 {
+        return 0;
+    }
+    let shifted = ((value & mask) >> mask.trailing_zeros()) as u64;
+    let max = (mask >> mask.trailing_zeros()) as u64;
+    (shifted * 255 / max) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PIXELS: [u32; 6] = [0xFF0000, 0x00FF00, 0x0000FF, 0x123456, 0xFFFFFF, 0x000000];
+
+    // A BMP written the way other programs do, with row padding and
+    // either row order
+    fn other_bmp(bits_per_pixel: u16, top_down: bool, bitfields: bool) -> Vec<u8> {
+        let (width, height) = (3usize, 2usize);
+        let bytes_per_pixel = bits_per_pixel as usize / 8;
+        let row_size = (width * bytes_per_pixel).div_ceil(4) * 4;
+        let pixel_offset = if bitfields { 54 + 12 } else { 54 };
+
+        let mut bytes = b"BM".to_vec();
+        bytes.extend_from_slice(&((pixel_offset + row_size * height) as u32).to_le_bytes());
+        bytes.extend_from_slice(&[0; 4]);
+        bytes.extend_from_slice(&(pixel_offset as u32).to_le_bytes());
+        bytes.extend_from_slice(&40u32.to_le_bytes());
+        bytes.extend_from_slice(&(width as i32).to_le_bytes());
+        let height_field = if top_down { -(height as i32) } else { height as i32 };
+        bytes.extend_from_slice(&height_field.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&bits_per_pixel.to_le_bytes());
+        bytes.extend_from_slice(&(if bitfields { 3u32 } else { 0 }).to_le_bytes());
+        bytes.extend_from_slice(&[0; 20]);
+        if bitfields {
+            // RGBA byte order, the reverse of the usual BGRA
+            for mask in [0x000000FFu32, 0x0000FF00, 0x00FF0000] {
+                bytes.extend_from_slice(&mask.to_le_bytes());
+            }
+        }
+
+        let rows: Vec<&[u32]> = if top_down { PIXELS.chunks(width).collect() } else { PIXELS.chunks(width).rev().collect() };
+        for row in rows {
+            let start = bytes.len();
+            for &pixel in row {
+                let [b, g, r, _] = pixel.to_le_bytes();
+                match (bytes_per_pixel, bitfields) {
+                    (3, _) => bytes.extend_from_slice(&[b, g, r]),
+                    (_, false) => bytes.extend_from_slice(&[b, g, r, 0xFF]),
+                    (_, true) => bytes.extend_from_slice(&[r, g, b, 0xFF]),
+                }
+            }
+            bytes.resize(start + row_size, 0);
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let bytes = encode_bmp(&PIXELS, 3, 2);
+
+        assert_eq!(decode_bmp(&bytes).unwrap(), (PIXELS.to_vec(), 3, 2));
+    }
+
+    #[test]
+    fn test_reads_every_variant() {
+        for (bits_per_pixel, top_down, bitfields) in
+            [(24, false, false), (24, true, false), (32, false, false), (32, true, false), (32, false, true)]
+        {
+            let decoded = decode_bmp(&other_bmp(bits_per_pixel, top_down, bitfields));
+            assert_eq!(decoded, Ok((PIXELS.to_vec(), 3, 2)), "{}-bit, top down {}, bitfields {}", bits_per_pixel, top_down, bitfields);
+        }
+    }
+
+    #[test]
+    fn test_rejects_broken_files() {
+        let bytes = other_bmp(24, false, false);
+
+        assert_eq!(decode_bmp(b"PNG").unwrap_err(), "not a BMP file");
+        assert_eq!(decode_bmp(&bytes[..20]).unwrap_err(), "truncated header");
+        assert_eq!(decode_bmp(&bytes[..bytes.len() - 1]).unwrap_err(), "truncated pixel data");
+        let mut paletted = bytes.clone();
+        paletted[28] = 8;
+        assert_eq!(decode_bmp(&paletted).unwrap_err(), "unsupported 8-bit BMP with compression 0");
+
+        // The largest size the header allows, whose pixel data overflows
+        // usize on 32-bit targets and runs past the file on 64-bit ones
+        let mut huge = other_bmp(32, false, false);
+        huge[18..22].copy_from_slice(&i32::MAX.to_le_bytes());
+        huge[22..26].copy_from_slice(&i32::MIN.to_le_bytes());
+        let error = decode_bmp(&huge).unwrap_err();
+        let too_large = format!("image of {}x{} is too large", i32::MAX, 1u64 << 31);
+        assert!(error == too_large || error == "truncated pixel data", "{}", error);
+
+        // Sizes that fit but claim far more pixels than the file holds
+        let mut oversized = bytes.clone();
+        oversized[18..22].copy_from_slice(&100_000i32.to_le_bytes());
+        oversized[22..26].copy_from_slice(&100_000i32.to_le_bytes());
+        assert_eq!(decode_bmp(&oversized).unwrap_err(), "truncated pixel data");
+
+        // A channel mask covering all 32 bits
+        let mut wide_mask = other_bmp(32, false, true);
+        for offset in [54, 58, 62] {
+            wide_mask[offset..offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        }
+        let (pixels, _, _) = decode_bmp(&wide_mask).unwrap();
+        assert_eq!(pixels[4], 0xFFFFFF);
+    }
+}
@@ -0,0 +1,211 @@
+use std::f32::consts::PI;
+
+use nalgebra_glm::{rotate_vec3, Vec3};
+
+// Look-at camera that orbits around its center
+#[derive(Debug, Clone)]
+pub struct Camera {
+    pub eye: Vec3,
+    pub center: Vec3,
+    pub up: Vec3,
+    // Set by every move, cleared by check_change, so callers only redraw when needed
+    pub has_change: bool,
+    // Vertical field of view in radians
+    pub fov: f32,
+    // Thin lens: aperture is the lens diameter, 0.0 keeps the pinhole model
+    pub aperture: f32,
+    // Distance to the plane in focus. look_at, orbit and zoom reset it to the
+    // distance to the center, so whatever the camera looks at stays sharp.
+    pub focal_distance: f32,
+}
+
+impl Camera {
+    pub fn new(eye: Vec3, center: Vec3, up: Vec3, has_change: bool) -> Self {
+        Camera {
+            eye,
+            center,
+            up,
+            has_change,
+            fov: PI / 3.0,
+            aperture: 0.0,
+            focal_distance: (center - eye).magnitude(),
+        }
+    }
+
+    pub fn with_fov(mut self, fov: f32) -> Self {
+        self.set_fov(fov);
+        self
+    }
+
+    // Camera space (x right, y up, -z forward) to world space
+    pub fn basis_change(&self, vector: &Vec3) -> Vec3 {
+        let forward = (self.center - self.eye).normalize();
+        let right = forward.cross(&self.up).normalize();
+        let up = right.cross(&forward).normalize();
+
+        let rotated = vector.x * right + vector.y * up - vector.z * forward;
+        rotated.normalize()
+    }
+
+    // Direction of the ray through a point of the screen, both in [-1, 1]
+    pub fn ray_direction(&self, screen_x: f32, screen_y: f32, aspect_ratio: f32) -> Vec3 {
+        let scale = (self.fov / 2.0).tan();
+        self.basis_change(&Vec3::new(screen_x * aspect_ratio * scale, screen_y * scale, -1.0))
+    }
+
+    // Origin and direction of the ray through screen point (screen_x, screen_y)
+    // in [-1, 1]. lens_x/lens_y is a point on the unit disk that picks where on
+    // the lens the ray starts; every lens point converges on the focal plane.
+    pub fn primary_ray(&self, screen_x: f32, screen_y: f32, aspect_ratio: f32, lens_x: f32, lens_y: f32) -> (Vec3, Vec3) {
+        let direction = self.ray_direction(screen_x, screen_y, aspect_ratio);
+
+        if self.aperture <= 0.0 {
+            return (self.eye, direction);
+        }
+
+        let forward = (self.center - self.eye).normalize();
+        let right = forward.cross(&self.up).normalize();
+        let up = right.cross(&forward).normalize();
+
+        // Distance along the ray to the plane of focus, which is perpendicular to forward
+        let focus_point = self.eye + direction * (self.focal_distance / direction.dot(&forward));
+        let lens_radius = self.aperture / 2.0;
+        let origin = self.eye + right * (lens_x * lens_radius) + up * (lens_y * lens_radius);
+
+        (origin, (focus_point - origin).normalize())
+    }
+
+    // Pose between self (t = 0.0) and other (t = 1.0), used for camera motion blur
+    pub fn interpolate(&self, other: &Camera, t: f32) -> Camera {
+        Camera {
+            eye: self.eye.lerp(&other.eye, t),
+            center: self.center.lerp(&other.center, t),
+            up: self.up.lerp(&other.up, t).normalize(),
+            has_change: false,
+            fov: self.fov + (other.fov - self.fov) * t,
+            aperture: self.aperture + (other.aperture - self.aperture) * t,
+            focal_distance: self.focal_distance + (other.focal_distance - self.focal_distance) * t,
+        }
+    }
+
+    pub fn look_at(&mut self, eye: Vec3, center: Vec3) {
+        self.eye = eye;
+        self.center = center;
+        self.focal_distance = (center - eye).magnitude();
+        self.has_change = true;
+    }
+
+    pub fn orbit(&mut self, delta_yaw: f32, delta_pitch: f32) {
+        let radius_vector = self.eye - self.center;
+        let radius = radius_vector.magnitude();
+
+        let current_yaw = radius_vector.z.atan2(radius_vector.x);
+        let radius_xz = (radius_vector.x * radius_vector.x + radius_vector.z * radius_vector.z).sqrt();
+        let current_pitch = (-radius_vector.y).atan2(radius_xz);
+
+        let new_yaw = (current_yaw + delta_yaw) % (2.0 * PI);
+        let new_pitch = (current_pitch + delta_pitch).clamp(-PI / 2.0 + 0.1, PI / 2.0 - 0.1);
+
+        self.eye = self.center + Vec3::new(
+            radius * new_yaw.cos() * new_pitch.cos(),
+            -radius * new_pitch.sin(),
+            radius * new_yaw.sin() * new_pitch.cos(),
+        );
+        self.focal_distance = radius;
+        self.has_change = true;
+    }
+
+    // Moves the eye toward the center, never onto or past it
+    pub fn zoom(&mut self, delta: f32) {
+        let to_center = self.center - self.eye;
+        let distance = to_center.magnitude();
+        let new_distance = (distance - delta).max(0.1);
+        self.eye = self.center - to_center / distance * new_distance;
+        self.focal_distance = new_distance;
+        self.has_change = true;
+    }
+
+    // Turns the view around the eye, keeping the distance to the center.
+    // direction.x looks left and right, direction.y up and down.
+    pub fn move_center(&mut self, direction: Vec3) {
+        let radius_vector = self.center - self.eye;
+        let radius = radius_vector.magnitude();
+
+        let angle_x = direction.x * 0.05;
+        let angle_y = direction.y * 0.05;
+
+        let rotated = rotate_vec3(&radius_vector, angle_x, &Vec3::new(0.0, 1.0, 0.0));
+
+        let right = rotated.cross(&self.up).normalize();
+        let final_rotated = rotate_vec3(&rotated, angle_y, &right);
+
+        self.center = self.eye + final_rotated.normalize() * radius;
+        self.has_change = true;
+    }
+
+    pub fn set_fov(&mut self, fov: f32) {
+        self.fov = fov.clamp(PI / 18.0, PI * 0.9);
+        self.has_change = true;
+    }
+
+    pub fn check_change(&mut self) -> bool {
+        if self.has_change {
+            self.has_change = false;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn camera() -> Camera {
+        Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), false)
+    }
+
+    #[test]
+    fn test_center_of_the_screen_looks_at_the_center() {
+        let direction = camera().ray_direction(0.0, 0.0, 4.0 / 3.0);
+
+        assert!((direction - Vec3::new(0.0, 0.0, -1.0)).magnitude() < 1e-6);
+    }
+
+    #[test]
+    fn test_orbit_keeps_the_distance() {
+        let mut camera = camera();
+
+        camera.orbit(0.7, -0.4);
+
+        assert!(((camera.eye - camera.center).magnitude() - 5.0).abs() < 1e-4);
+        assert!(camera.check_change());
+        assert!(!camera.check_change());
+    }
+
+    #[test]
+    fn test_zoom_stops_before_the_center() {
+        let mut camera = camera();
+
+        camera.zoom(100.0);
+
+        assert!(((camera.eye - camera.center).magnitude() - 0.1).abs() < 1e-6);
+        assert!(camera.eye.z > 0.0);
+    }
+
+    #[test]
+    fn test_focus_follows_the_center() {
+        let mut camera = camera();
+
+        camera.zoom(2.0);
+        assert!((camera.focal_distance - 3.0).abs() < 1e-6);
+
+        camera.focal_distance = 10.0;
+        camera.orbit(0.3, 0.2);
+        assert!((camera.focal_distance - 3.0).abs() < 1e-4);
+
+        camera.look_at(Vec3::new(0.0, 4.0, 0.0), Vec3::new(0.0, 0.0, 0.0));
+        assert!((camera.focal_distance - 4.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,255 @@
+use std::ops::{Add, Mul};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Color { r, g, b }
+    }
+
+    pub const fn black() -> Self {
+        Color::new(0, 0, 0)
+    }
+
+    pub const fn white() -> Self {
+        Color::new(255, 255, 255)
+    }
+
+    // 0xRRGGBB, the layout the framebuffer stores
+    pub const fn from_hex(hex: u32) -> Self {
+        Color::new(((hex >> 16) & 0xFF) as u8, ((hex >> 8) & 0xFF) as u8, (hex & 0xFF) as u8)
+    }
+
+    pub fn to_hex(self) -> u32 {
+        ((self.r as u32) << 16) | ((self.g as u32) << 8) | (self.b as u32)
+    }
+
+    pub fn is_black(&self) -> bool {
+        self.r == 0 && self.g == 0 && self.b == 0
+    }
+
+    // Straight line from self (t = 0.0) to other (t = 1.0)
+    pub fn lerp(&self, other: &Color, t: f32) -> Color {
+        let channel = |a: u8, b: u8| (a as f32 + t * (b as f32 - a as f32)).round().clamp(0.0, 255.0) as u8;
+        Color::new(channel(self.r, other.r), channel(self.g, other.g), channel(self.b, other.b))
+    }
+
+    // Mix of three colors by barycentric weights, for shading triangles
+    pub fn interpolate(v1: &Color, v2: &Color, v3: &Color, w1: f32, w2: f32, w3: f32) -> Color {
+        let channel = |a: u8, b: u8, c: u8| {
+            (a as f32 * w1 + b as f32 * w2 + c as f32 * w3).round().clamp(0.0, 255.0) as u8
+        };
+        Color::new(
+            channel(v1.r, v2.r, v3.r),
+            channel(v1.g, v2.g, v3.g),
+            channel(v1.b, v2.b, v3.b),
+        )
+    }
+
+    pub fn adjust_brightness(&self, factor: f32) -> Color {
+        let channel = |c: u8| (c as f32 * factor).clamp(0.0, 255.0) as u8;
+        Color::new(channel(self.r), channel(self.g), channel(self.b))
+    }
+
+    // Blend modes: self is the base layer, blend the one painted over it
+
+    // The blend color wherever it isn't black
+    pub fn blend_normal(&self, blend: &Color) -> Color {
+        if blend.is_black() {
+            *self
+        } else {
+            *blend
+        }
+    }
+
+    // Tints by the blend color, white leaves the base unchanged
+    pub fn blend_multiply(&self, blend: &Color) -> Color {
+        self.per_channel(blend, |base, blend| base * blend / 255)
+    }
+
+    // Saturates at white
+    pub fn blend_add(&self, blend: &Color) -> Color {
+        self.per_channel(blend, |base, blend| (base + blend).min(255))
+    }
+
+    // Black in the blend leaves the base unchanged
+    pub fn blend_subtract(&self, blend: &Color) -> Color {
+        if blend.is_black() {
+            *self
+        } else {
+            self.per_channel(blend, |base, blend| base.saturating_sub(blend))
+        }
+    }
+
+    // Inverse of multiply, only ever brightens
+    pub fn blend_screen(&self, blend: &Color) -> Color {
+        self.per_channel(blend, |base, blend| 255 - (255 - base) * (255 - blend) / 255)
+    }
+
+    // Multiply where the base is dark, screen where it is light
+    pub fn blend_overlay(&self, blend: &Color) -> Color {
+        self.per_channel(blend, overlay_channel)
+    }
+
+    // Overlay with the layers swapped, the blend decides multiply or screen
+    pub fn blend_hard_light(&self, blend: &Color) -> Color {
+        self.per_channel(blend, |base, blend| overlay_channel(blend, base))
+    }
+
+    pub fn blend_soft_light(&self, blend: &Color) -> Color {
+        self.per_channel(blend, |base, blend| {
+            let b = base as f32 / 255.0;
+            let s = blend as f32 / 255.0;
+            let result = if s < 0.5 {
+                b - (1.0 - 2.0 * s) * b * (1.0 - b)
+            } else {
+                b + (2.0 * s - 1.0) * (((b - 0.5).abs() * 16.0 + 12.0) * b - 3.0)
+            };
+            (result * 255.0).round().clamp(0.0, 255.0) as u32
+        })
+    }
+
+    pub fn blend_darken(&self, blend: &Color) -> Color {
+        self.per_channel(blend, |base, blend| base.min(blend))
+    }
+
+    pub fn blend_lighten(&self, blend: &Color) -> Color {
+        self.per_channel(blend, |base, blend| base.max(blend))
+    }
+
+    pub fn blend_color_dodge(&self, blend: &Color) -> Color {
+        self.per_channel(blend, |base, blend| {
+            if blend == 255 {
+                255
+            } else {
+                (base * 255 / (255 - blend)).min(255)
+            }
+        })
+    }
+
+    pub fn blend_color_burn(&self, blend: &Color) -> Color {
+        // A black blend burns all the way down
+        self.per_channel(blend, |base, blend| {
+            ((255 - base) * 255).checked_div(blend).map_or(0, |burn| 255 - burn.min(255))
+        })
+    }
+
+    pub fn blend_difference(&self, blend: &Color) -> Color {
+        self.per_channel(blend, |base, blend| base.abs_diff(blend))
+    }
+
+    // Like difference, but lower contrast
+    pub fn blend_exclusion(&self, blend: &Color) -> Color {
+        self.per_channel(blend, |base, blend| base + blend - 2 * base * blend / 255)
+    }
+
+    // Applies a blend function to each channel pair. The channels are widened
+    // to u32 so the formulas can't overflow, and results land back in 0..=255.
+    fn per_channel(&self, blend: &Color, function: impl Fn(u32, u32) -> u32) -> Color {
+        let channel = |base: u8, blend: u8| function(base as u32, blend as u32).min(255) as u8;
+        Color::new(channel(self.r, blend.r), channel(self.g, blend.g), channel(self.b, blend.b))
+    }
+}
+
+fn overlay_channel(base: u32, blend: u32) -> u32 {
+    if base < 128 {
+        2 * base * blend / 255
+    } else {
+        255 - 2 * (255 - base) * (255 - blend) / 255
+    }
+}
+
+// Channels saturate at 255
+impl Add for Color {
+    type Output = Color;
+
+    fn add(self, other: Color) -> Color {
+        Color::new(
+            self.r.saturating_add(other.r),
+            self.g.saturating_add(other.g),
+            self.b.saturating_add(other.b),
+        )
+    }
+}
+
+impl Mul<f32> for Color {
+    type Output = Color;
+
+    fn mul(self, factor: f32) -> Color {
+        self.adjust_brightness(factor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_round_trip() {
+        let color = Color::from_hex(0xFFA500);
+
+        assert_eq!(color, Color::new(255, 165, 0));
+        assert_eq!(color.to_hex(), 0xFFA500);
+    }
+
+    #[test]
+    fn test_add_and_mul_saturate() {
+        let color = Color::new(100, 150, 200);
+
+        assert_eq!(color + Color::new(50, 75, 100), Color::new(150, 225, 255));
+        assert_eq!(color * 1.5, Color::new(150, 225, 255));
+        assert_eq!(color * -1.5, Color::black());
+    }
+
+    #[test]
+    fn test_lerp_ends() {
+        let a = Color::new(10, 20, 30);
+        let b = Color::new(200, 100, 0);
+
+        assert_eq!(a.lerp(&b, 0.0), a);
+        assert_eq!(a.lerp(&b, 1.0), b);
+        assert_eq!(a.lerp(&b, 0.5), Color::new(105, 60, 15));
+    }
+
+    #[test]
+    fn test_multiply_and_screen_identities() {
+        let color = Color::new(12, 128, 240);
+
+        assert_eq!(color.blend_multiply(&Color::white()), color);
+        assert_eq!(color.blend_multiply(&Color::black()), Color::black());
+        assert_eq!(color.blend_screen(&Color::black()), color);
+        assert_eq!(color.blend_screen(&Color::white()), Color::white());
+    }
+
+    #[test]
+    fn test_overlay_and_hard_light_swap_layers() {
+        let base = Color::new(40, 200, 128);
+        let blend = Color::new(220, 30, 90);
+
+        assert_eq!(base.blend_hard_light(&blend), blend.blend_overlay(&base));
+    }
+
+    #[test]
+    fn test_extreme_channels_stay_in_range() {
+        let colors = [Color::black(), Color::white(), Color::new(1, 128, 254)];
+        for base in &colors {
+            for blend in &colors {
+                // Overflow would panic in a debug build
+                base.blend_add(blend);
+                base.blend_subtract(blend);
+                base.blend_overlay(blend);
+                base.blend_soft_light(blend);
+                base.blend_color_dodge(blend);
+                base.blend_color_burn(blend);
+                base.blend_difference(blend);
+                base.blend_exclusion(blend);
+            }
+        }
+        assert_eq!(Color::white().blend_difference(&Color::new(1, 128, 254)), Color::new(254, 127, 1));
+    }
+}
@@ -0,0 +1,254 @@
+use std::io;
+use std::path::Path;
+
+use crate::bmp::{read_bmp_file, write_bmp_file};
+use crate::color::Color;
+use crate::png::write_png_file;
+use crate::ppm::write_ppm_file;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageFormat {
+    Png,
+    Bmp,
+    // Binary PPM (P6)
+    Ppm,
+    // Plain text PPM (P3)
+    AsciiPpm,
+}
+
+impl ImageFormat {
+    // By extension: .png, .bmp or .ppm for binary PPM. Anything else is an
+    // error rather than a file whose name lies about what's in it.
+    pub fn from_path(file_path: &str) -> io::Result<ImageFormat> {
+        let extension = Path::new(file_path).extension().and_then(|extension| extension.to_str());
+        match extension.map(|extension| extension.to_ascii_lowercase()).as_deref() {
+            Some("png") => Ok(ImageFormat::Png),
+            Some("bmp") => Ok(ImageFormat::Bmp),
+            Some("ppm") => Ok(ImageFormat::Ppm),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{}: unsupported image format, expected .png, .bmp or .ppm", file_path),
+            )),
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Bmp => "bmp",
+            ImageFormat::Ppm | ImageFormat::AsciiPpm => "ppm",
+        }
+    }
+}
+
+pub struct Framebuffer {
+    // 0xRRGGBB per pixel, row by row from the top left
+    pub buffer: Vec<u32>,
+    // Depth of what each pixel shows, f32::INFINITY where nothing was drawn
+    pub z_buffer: Vec<f32>,
+    pub width: usize,
+    pub height: usize,
+    background_color: u32,
+    foreground_color: u32,
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        let background_color = 0x00FF00;
+        let foreground_color = 0xFF00FF;
+
+        Self {
+            buffer: vec![background_color; width * height],
+            z_buffer: vec![f32::INFINITY; width * height],
+            width,
+            height,
+            background_color,
+            foreground_color,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.buffer.fill(self.background_color);
+        self.z_buffer.fill(f32::INFINITY);
+    }
+
+    // Paints with the foreground color, points outside the buffer are ignored
+    pub fn point(&mut self, x: usize, y: usize) {
+        if x < self.width && y < self.height {
+            self.buffer[y * self.width + x] = self.foreground_color;
+        }
+    }
+
+    // Mixes the foreground color into what is already there, coverage 0.0
+    // keeps the pixel and 1.0 paints it like point. For anti-aliased edges.
+    pub fn blend_point(&mut self, x: usize, y: usize, coverage: f32) {
+        if x >= self.width || y >= self.height || coverage <= 0.0 {
+            return;
+        }
+        let index = y * self.width + x;
+        let behind = Color::from_hex(self.buffer[index]);
+        self.buffer[index] = behind.lerp(&Color::from_hex(self.foreground_color), coverage.min(1.0)).to_hex();
+    }
+
+    // Like point, but only if nothing closer was drawn there yet. Returns
+    // whether the pixel was painted.
+    pub fn point_with_depth(&mut self, x: usize, y: usize, depth: f32) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        let index = y * self.width + x;
+        if depth < self.z_buffer[index] {
+            self.z_buffer[index] = depth;
+            self.buffer[index] = self.foreground_color;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn get_pixel(&self, x: usize, y: usize) -> Option<u32> {
+        (x < self.width && y < self.height).then(|| self.buffer[y * self.width + x])
+    }
+
+    pub fn get_depth(&self, x: usize, y: usize) -> Option<f32> {
+        (x < self.width && y < self.height).then(|| self.z_buffer[y * self.width + x])
+    }
+
+    pub fn set_background_color(&mut self, color: u32) {
+        self.background_color = color;
+        self.clear()
+    }
+
+    pub fn set_foreground_color(&mut self, color: u32) {
+        self.foreground_color = color;
+    }
+
+    // Format picked by the extension, see ImageFormat::from_path
+    pub fn render_buffer(&self, file_path: &str) -> io::Result<()> {
+        self.save(file_path, ImageFormat::from_path(file_path)?)
+    }
+
+    pub fn save(&self, file_path: &str, format: ImageFormat) -> io::Result<()> {
+        match format {
+            ImageFormat::Png => write_png_file(file_path, &self.buffer, self.width, self.height),
+            ImageFormat::Bmp => write_bmp_file(file_path, &self.buffer, self.width, self.height),
+            ImageFormat::Ppm => write_ppm_file(file_path, &self.buffer, self.width, self.height, true),
+            ImageFormat::AsciiPpm => write_ppm_file(file_path, &self.buffer, self.width, self.height, false),
+        }
+    }
+
+    // A framebuffer holding the image of a BMP file, for comparing renders
+    // against reference images. See bmp::decode_bmp for the variants it reads.
+    pub fn load_bmp(file_path: &str) -> io::Result<Framebuffer> {
+        let (buffer, width, height) = read_bmp_file(file_path)?;
+        let mut framebuffer = Framebuffer::new(width, height);
+        framebuffer.buffer = buffer;
+        Ok(framebuffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point_inside_and_outside() {
+        let mut framebuffer = Framebuffer::new(4, 3);
+        framebuffer.set_foreground_color(0x123456);
+
+        framebuffer.point(3, 2);
+        framebuffer.point(4, 0);
+        framebuffer.point(0, 3);
+
+        assert_eq!(framebuffer.get_pixel(3, 2), Some(0x123456));
+        assert_eq!(framebuffer.buffer.iter().filter(|&&pixel| pixel == 0x123456).count(), 1);
+        assert_eq!(framebuffer.get_pixel(4, 0), None);
+    }
+
+    #[test]
+    fn test_blend_point_mixes_by_coverage() {
+        let mut framebuffer = Framebuffer::new(3, 1);
+        framebuffer.set_background_color(0x000000);
+        framebuffer.set_foreground_color(0xFF8040);
+
+        framebuffer.blend_point(0, 0, 0.5);
+        framebuffer.blend_point(1, 0, 2.0);
+        framebuffer.blend_point(2, 0, 0.0);
+        framebuffer.blend_point(3, 0, 1.0);
+
+        assert_eq!(framebuffer.buffer, vec![0x804020, 0xFF8040, 0x000000]);
+    }
+
+    #[test]
+    fn test_depth_keeps_the_closest() {
+        let mut framebuffer = Framebuffer::new(2, 2);
+
+        framebuffer.set_foreground_color(0xFF0000);
+        assert!(framebuffer.point_with_depth(1, 1, 5.0));
+        framebuffer.set_foreground_color(0x00FF00);
+        assert!(!framebuffer.point_with_depth(1, 1, 7.0));
+        assert_eq!(framebuffer.get_pixel(1, 1), Some(0xFF0000));
+
+        framebuffer.set_foreground_color(0x0000FF);
+        assert!(framebuffer.point_with_depth(1, 1, 2.0));
+        assert_eq!(framebuffer.get_pixel(1, 1), Some(0x0000FF));
+        assert_eq!(framebuffer.get_depth(1, 1), Some(2.0));
+    }
+
+    #[test]
+    fn test_clear_resets_color_and_depth() {
+        let mut framebuffer = Framebuffer::new(3, 3);
+        framebuffer.point_with_depth(0, 0, 1.0);
+
+        framebuffer.set_background_color(0x101010);
+
+        assert!(framebuffer.buffer.iter().all(|&pixel| pixel == 0x101010));
+        assert!(framebuffer.z_buffer.iter().all(|depth| depth.is_infinite()));
+    }
+
+    #[test]
+    fn test_files_round_trip() {
+        let directory = std::env::temp_dir().join(format!("render_core_framebuffer_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = |name: &str| directory.join(name).to_string_lossy().to_string();
+
+        let mut framebuffer = Framebuffer::new(5, 3);
+        for (index, pixel) in framebuffer.buffer.iter_mut().enumerate() {
+            *pixel = (index as u32 * 0x0F1E2D) & 0xFFFFFF;
+        }
+
+        framebuffer.render_buffer(&path("image.bmp")).unwrap();
+        assert_eq!(Framebuffer::load_bmp(&path("image.bmp")).unwrap().buffer, framebuffer.buffer);
+
+        framebuffer.render_buffer(&path("image.png")).unwrap();
+        let png = image::open(path("image.png")).unwrap().to_rgb8();
+        let png_pixels: Vec<u32> = png.pixels().map(|p| (p[0] as u32) << 16 | (p[1] as u32) << 8 | p[2] as u32).collect();
+        assert_eq!(png_pixels, framebuffer.buffer);
+
+        framebuffer.render_buffer(&path("image.ppm")).unwrap();
+        framebuffer.save(&path("ascii.ppm"), ImageFormat::AsciiPpm).unwrap();
+        let binary = image::open(path("image.ppm")).unwrap().to_rgb8();
+        let ascii = image::open(path("ascii.ppm")).unwrap().to_rgb8();
+        assert_eq!(binary, png);
+        assert_eq!(ascii, png);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_format_comes_from_the_extension() {
+        assert_eq!(ImageFormat::from_path("shot.PNG").unwrap(), ImageFormat::Png);
+        assert_eq!(ImageFormat::from_path("frames/frame_00001.bmp").unwrap(), ImageFormat::Bmp);
+        assert_eq!(ImageFormat::from_path("out.ppm").unwrap(), ImageFormat::Ppm);
+
+        for file_path in ["shot.jpg", "shot", "archive.png.gz"] {
+            let err = ImageFormat::from_path(file_path).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{}", file_path);
+        }
+
+        // Nothing gets written under a name it doesn't match
+        let file_path = std::env::temp_dir().join(format!("render_core_framebuffer_{}.jpg", std::process::id()));
+        assert!(Framebuffer::new(2, 2).render_buffer(&file_path.to_string_lossy()).is_err());
+        assert!(!file_path.exists());
+    }
+}
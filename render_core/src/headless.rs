@@ -0,0 +1,232 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::framebuffer::Framebuffer;
+use crate::script::{parse_script, ScriptEvent, MOUSE_BUTTONS};
+use crate::window::{GamepadEvent, Key, MouseButton, WindowBackend};
+
+// Length of a frame when the script doesn't say what time it is
+const FRAME_TIME: f32 = 1.0 / 60.0;
+
+// Replays an input script (see script.rs) instead of reading a real window.
+// Frames count calls to present, events of frame 0 are there before the
+// first one. The window closes at `quit`, or after the frame of the last event.
+pub struct HeadlessWindow {
+    width: usize,
+    height: usize,
+    pub title: String,
+    // Sorted by frame
+    events: Vec<(usize, ScriptEvent)>,
+    next_event: usize,
+    frame: usize,
+    time: f32,
+    keys_down: HashSet<Key>,
+    keys_pressed: HashSet<Key>,
+    keys_repeated: HashSet<Key>,
+    mouse: Option<(f32, f32)>,
+    // Same order as MOUSE_BUTTONS
+    mouse_down: [bool; 3],
+    gamepad_events: Vec<GamepadEvent>,
+    open: bool,
+    frame_dump: Option<PathBuf>,
+}
+
+impl HeadlessWindow {
+    pub fn new(width: usize, height: usize, mut events: Vec<(usize, ScriptEvent)>) -> Self {
+        events.sort_by_key(|(frame, _)| *frame);
+        let mut window = HeadlessWindow {
+            width,
+            height,
+            title: String::new(),
+            events,
+            next_event: 0,
+            frame: 0,
+            time: 0.0,
+            keys_down: HashSet::new(),
+            keys_pressed: HashSet::new(),
+            keys_repeated: HashSet::new(),
+            mouse: None,
+            mouse_down: [false; 3],
+            gamepad_events: Vec::new(),
+            open: true,
+            frame_dump: None,
+        };
+        window.apply_events();
+        window
+    }
+
+    pub fn load(file_path: &str, width: usize, height: usize) -> Result<Self, String> {
+        let source = fs::read_to_string(file_path).map_err(|err| format!("{}: {}", file_path, err))?;
+        let events = parse_script(&source).map_err(|err| format!("{}:{}", file_path, err))?;
+        Ok(HeadlessWindow::new(width, height, events))
+    }
+
+    // Writes each presented frame as frame_00000.bmp, frame_00001.bmp, ...
+    pub fn with_frame_dump(mut self, directory: impl Into<PathBuf>) -> Result<Self, String> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory).map_err(|err| format!("{}: {}", directory.display(), err))?;
+        self.frame_dump = Some(directory);
+        Ok(self)
+    }
+
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    fn apply_events(&mut self) {
+        self.keys_pressed.clear();
+        self.keys_repeated.clear();
+        self.gamepad_events.clear();
+
+        while let Some(&(frame, event)) = self.events.get(self.next_event) {
+            if frame > self.frame {
+                break;
+            }
+            match event {
+                ScriptEvent::Press(key) => {
+                    if self.keys_down.insert(key) {
+                        self.keys_pressed.insert(key);
+                    }
+                }
+                ScriptEvent::Release(key) => {
+                    self.keys_down.remove(&key);
+                }
+                ScriptEvent::Tap(key) => {
+                    self.keys_pressed.insert(key);
+                }
+                ScriptEvent::Repeat(key) => {
+                    self.keys_repeated.insert(key);
+                }
+                ScriptEvent::Mouse(x, y) => {
+                    self.mouse = Some((x.clamp(0.0, self.width as f32), y.clamp(0.0, self.height as f32)));
+                }
+                ScriptEvent::MouseLeave => self.mouse = None,
+                ScriptEvent::MouseDown(button) => self.mouse_down[mouse_index(button)] = true,
+                ScriptEvent::MouseUp(button) => self.mouse_down[mouse_index(button)] = false,
+                ScriptEvent::Gamepad(gamepad_event) => self.gamepad_events.push(gamepad_event),
+                ScriptEvent::Time(seconds) => self.time = seconds,
+                ScriptEvent::Quit => self.open = false,
+            }
+            self.next_event += 1;
+        }
+
+        let last_frame = self.events.last().map_or(0, |(frame, _)| *frame);
+        if self.frame > last_frame {
+            self.open = false;
+        }
+    }
+}
+
+fn mouse_index(button: MouseButton) -> usize {
+    MOUSE_BUTTONS.iter().position(|&other| other == button).unwrap_or(0)
+}
+
+impl WindowBackend for HeadlessWindow {
+    fn is_open(&self) -> bool {
+        self.open
+    }
+
+    fn present(&mut self, framebuffer: &Framebuffer) {
+        if let Some(directory) = &self.frame_dump {
+            let path = directory.join(format!("frame_{:05}.bmp", self.frame));
+            if let Err(err) = framebuffer.render_buffer(&path.to_string_lossy()) {
+                eprintln!("{}: {}", path.display(), err);
+            }
+        }
+        self.frame += 1;
+        self.time += FRAME_TIME;
+        self.apply_events();
+    }
+
+    fn is_key_down(&self, key: Key) -> bool {
+        self.keys_down.contains(&key)
+    }
+
+    fn is_key_pressed(&self, key: Key) -> bool {
+        self.keys_pressed.contains(&key)
+    }
+
+    // The frame a key goes down and the frames with a `repeat` line for it
+    fn is_key_repeated(&self, key: Key) -> bool {
+        self.keys_pressed.contains(&key) || self.keys_repeated.contains(&key)
+    }
+
+    fn mouse_position(&self) -> Option<(f32, f32)> {
+        self.mouse
+    }
+
+    fn is_mouse_down(&self, button: MouseButton) -> bool {
+        self.mouse_down[mouse_index(button)]
+    }
+
+    fn time(&self) -> f32 {
+        self.time
+    }
+
+    // The script decides what the gamepad does, live events are ignored
+    fn feed_gamepad(&mut self, _events: &[GamepadEvent]) {}
+
+    fn gamepad_events(&self) -> &[GamepadEvent] {
+        &self.gamepad_events
+    }
+
+    fn set_title(&mut self, title: &str) {
+        self.title = title.to_string();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window::GamepadButton;
+
+    #[test]
+    fn test_script_drives_input_frame_by_frame() {
+        let events = parse_script("0 press W\n1 tap P\n1 gamepad press South\n2 release W\n3 quit\n").unwrap();
+        let mut window = HeadlessWindow::new(4, 4, events);
+        let framebuffer = Framebuffer::new(4, 4);
+
+        assert!(window.is_key_down(Key::W) && window.is_key_pressed(Key::W));
+        window.present(&framebuffer);
+        assert!(window.is_key_down(Key::W) && !window.is_key_pressed(Key::W));
+        // Up again by the time the frame sees it
+        assert!(window.is_key_pressed(Key::P) && !window.is_key_down(Key::P));
+        assert_eq!(window.gamepad_events(), &[GamepadEvent::Pressed(GamepadButton::South)]);
+        window.present(&framebuffer);
+        assert!(!window.is_key_down(Key::W) && !window.is_key_down(Key::P));
+        assert!(window.gamepad_events().is_empty());
+        assert!(window.is_open());
+        window.present(&framebuffer);
+        assert!(!window.is_open());
+    }
+
+    #[test]
+    fn test_repeats_only_last_their_frame() {
+        let events = parse_script("0 press PageUp\n2 repeat PageUp\n3 release PageUp\n").unwrap();
+        let mut window = HeadlessWindow::new(4, 4, events);
+        let framebuffer = Framebuffer::new(4, 4);
+
+        let mut repeated = Vec::new();
+        while window.is_open() {
+            repeated.push(window.is_key_repeated(Key::PageUp));
+            window.present(&framebuffer);
+        }
+
+        assert_eq!(repeated, [true, false, true, false]);
+    }
+
+    #[test]
+    fn test_time_steps_unless_the_script_sets_it() {
+        let mut window = HeadlessWindow::new(4, 4, parse_script("2 time 10\n4 quit\n").unwrap());
+        let framebuffer = Framebuffer::new(4, 4);
+
+        assert_eq!(window.time(), 0.0);
+        window.present(&framebuffer);
+        assert_eq!(window.time(), FRAME_TIME);
+        window.present(&framebuffer);
+        assert_eq!(window.time(), 10.0);
+        window.present(&framebuffer);
+        assert_eq!(window.time(), 10.0 + FRAME_TIME);
+    }
+}
@@ -0,0 +1,25 @@
+// Pieces every renderer in the repo needs: the pixel buffer, colors,
+// textures and their cache, the orbiting look-at camera, and the window
+// the main loops draw into along with its headless and recording stand-ins,
+// plus saving what it shows as an image
+
+pub mod assets;
+pub mod bmp;
+pub mod camera;
+pub mod color;
+pub mod framebuffer;
+pub mod headless;
+pub mod png;
+pub mod ppm;
+pub mod recording;
+pub mod screenshot;
+pub mod script;
+pub mod texture;
+pub mod window;
+
+pub use assets::AssetManager;
+pub use camera::Camera;
+pub use color::Color;
+pub use framebuffer::{Framebuffer, ImageFormat};
+pub use texture::Texture;
+pub use window::{GamepadAxis, GamepadButton, GamepadEvent, Key, MouseButton, WindowBackend};
@@ -0,0 +1,12 @@
+use std::io;
+
+use image::RgbImage;
+
+// 8-bit RGB PNG of a 0xRRGGBB buffer, top row first
+pub fn write_png_file(file_path: &str, buffer: &[u32], width: usize, height: usize) -> io::Result<()> {
+    let image = RgbImage::from_fn(width as u32, height as u32, |x, y| {
+        let pixel = buffer[y as usize * width + x as usize];
+        image::Rgb([(pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8])
+    });
+    image.save_with_format(file_path, image::ImageFormat::Png).map_err(io::Error::other)
+}
@@ -0,0 +1,57 @@
+use std::fs;
+use std::io;
+
+// Netpbm color image of a 0xRRGGBB buffer. Binary (P6) is compact, ASCII
+// (P3) can be read and diffed as text.
+pub fn write_ppm_file(file_path: &str, buffer: &[u32], width: usize, height: usize, binary: bool) -> io::Result<()> {
+    fs::write(file_path, encode_ppm(buffer, width, height, binary))
+}
+
+pub fn encode_ppm(buffer: &[u32], width: usize, height: usize, binary: bool) -> Vec<u8> {
+    let rgb = |pixel: u32| [(pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8];
+
+    if binary {
+        let mut bytes = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+        for &pixel in buffer {
+            bytes.extend_from_slice(&rgb(pixel));
+        }
+        return bytes;
+    }
+
+    // Five pixels per line keeps lines under the 70 characters the format allows
+    let mut text = format!("P3\n{} {}\n255\n", width, height);
+    for pixels in buffer.chunks(5) {
+        let line: Vec<String> = pixels
+            .iter()
+            .map(|&pixel| {
+                let [r, g, b] = rgb(pixel);
+                format!("{} {} {}", r, g, b)
+            })
+            .collect();
+        text.push_str(&line.join("  "));
+        text.push('\n');
+    }
+    text.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binary_and_ascii_hold_the_same_pixels() {
+        let pixels = [0xFF8000, 0x00FF7F, 0x010203, 0xFFFFFF, 0, 0, 0x0A0B0C];
+
+        let binary = encode_ppm(&pixels, 7, 1, true);
+        let ascii = String::from_utf8(encode_ppm(&pixels, 7, 1, false)).unwrap();
+
+        assert_eq!(&binary[..11], b"P6\n7 1\n255\n");
+        assert_eq!(&binary[11..14], &[0xFF, 0x80, 0x00]);
+        assert_eq!(binary.len(), 11 + 7 * 3);
+        assert_eq!(
+            ascii,
+            "P3\n7 1\n255\n255 128 0  0 255 127  1 2 3  255 255 255  0 0 0\n0 0 0  10 11 12\n"
+        );
+        assert!(ascii.lines().all(|line| line.len() <= 70));
+    }
+}
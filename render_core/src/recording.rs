@@ -0,0 +1,218 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::framebuffer::Framebuffer;
+use crate::script::{ScriptEvent, KEYS, MOUSE_BUTTONS};
+use crate::window::{GamepadEvent, Key, MouseButton, WindowBackend};
+
+// Passes everything through to another window and writes down the input of
+// every frame as an input script, so HEADLESS_SCRIPT can replay it later.
+// The time of each frame is written too, for animations driven by time().
+pub struct RecordingWindow {
+    inner: Box<dyn WindowBackend>,
+    file_path: String,
+    // None once writing failed, the error is only reported once
+    file: Option<BufWriter<File>>,
+    frame: usize,
+    keys_down: HashSet<Key>,
+    mouse: Option<(f32, f32)>,
+    mouse_down: [bool; 3],
+}
+
+impl RecordingWindow {
+    pub fn new(inner: Box<dyn WindowBackend>, file_path: &str) -> Result<Self, String> {
+        let mut file = BufWriter::new(File::create(file_path).map_err(|err| format!("{}: {}", file_path, err))?);
+        writeln!(file, "# recorded input, replay it with HEADLESS_SCRIPT={}", file_path)
+            .map_err(|err| format!("{}: {}", file_path, err))?;
+
+        let mut window = RecordingWindow {
+            inner,
+            file_path: file_path.to_string(),
+            file: Some(file),
+            frame: 0,
+            keys_down: HashSet::new(),
+            mouse: None,
+            mouse_down: [false; 3],
+        };
+        window.record_input();
+        Ok(window)
+    }
+
+    // Changes since the last frame
+    fn record_input(&mut self) {
+        let mut events = vec![ScriptEvent::Time(self.inner.time())];
+
+        for &key in KEYS {
+            let down = self.inner.is_key_down(key);
+            if down && self.keys_down.insert(key) {
+                events.push(ScriptEvent::Press(key));
+            } else if !down && self.keys_down.remove(&key) {
+                events.push(ScriptEvent::Release(key));
+            } else if !down && self.inner.is_key_pressed(key) {
+                // Went down and up again between two frames
+                events.push(ScriptEvent::Tap(key));
+            } else if down && self.inner.is_key_repeated(key) {
+                // Held since an earlier frame, the first press already counts
+                events.push(ScriptEvent::Repeat(key));
+            }
+        }
+
+        let mouse = self.inner.mouse_position();
+        if mouse != self.mouse {
+            events.push(match mouse {
+                Some((x, y)) => ScriptEvent::Mouse(x, y),
+                None => ScriptEvent::MouseLeave,
+            });
+            self.mouse = mouse;
+        }
+
+        for (index, &button) in MOUSE_BUTTONS.iter().enumerate() {
+            let down = self.inner.is_mouse_down(button);
+            if down != self.mouse_down[index] {
+                events.push(if down { ScriptEvent::MouseDown(button) } else { ScriptEvent::MouseUp(button) });
+                self.mouse_down[index] = down;
+            }
+        }
+
+        self.write(&events);
+    }
+
+    fn write(&mut self, events: &[ScriptEvent]) {
+        let Some(file) = &mut self.file else {
+            return;
+        };
+        let result = events.iter().try_for_each(|event| writeln!(file, "{} {}", self.frame, event));
+        if let Err(err) = result {
+            eprintln!("Stopped recording input, {}: {}", self.file_path, err);
+            self.file = None;
+        }
+    }
+}
+
+impl WindowBackend for RecordingWindow {
+    fn is_open(&self) -> bool {
+        self.inner.is_open()
+    }
+
+    fn present(&mut self, framebuffer: &Framebuffer) {
+        // Gamepad events belong to the frame they were fed in
+        let gamepad: Vec<ScriptEvent> = self.inner.gamepad_events().iter().map(|&event| ScriptEvent::Gamepad(event)).collect();
+        self.write(&gamepad);
+
+        self.inner.present(framebuffer);
+        self.frame += 1;
+        self.record_input();
+    }
+
+    fn is_key_down(&self, key: Key) -> bool {
+        self.inner.is_key_down(key)
+    }
+
+    fn is_key_pressed(&self, key: Key) -> bool {
+        self.inner.is_key_pressed(key)
+    }
+
+    fn is_key_repeated(&self, key: Key) -> bool {
+        self.inner.is_key_repeated(key)
+    }
+
+    fn mouse_position(&self) -> Option<(f32, f32)> {
+        self.inner.mouse_position()
+    }
+
+    fn is_mouse_down(&self, button: MouseButton) -> bool {
+        self.inner.is_mouse_down(button)
+    }
+
+    fn time(&self) -> f32 {
+        self.inner.time()
+    }
+
+    fn feed_gamepad(&mut self, events: &[GamepadEvent]) {
+        self.inner.feed_gamepad(events);
+    }
+
+    fn gamepad_events(&self) -> &[GamepadEvent] {
+        self.inner.gamepad_events()
+    }
+
+    fn set_title(&mut self, title: &str) {
+        self.inner.set_title(title);
+    }
+}
+
+// The replay stops where the recording did
+impl Drop for RecordingWindow {
+    fn drop(&mut self) {
+        self.write(&[ScriptEvent::Quit]);
+        if let Some(file) = &mut self.file {
+            if let Err(err) = file.flush() {
+                eprintln!("{}: {}", self.file_path, err);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless::HeadlessWindow;
+    use crate::script::parse_script;
+    use crate::window::GamepadButton;
+
+    #[test]
+    fn test_recording_a_replay_gives_the_same_input() {
+        let script = "0 press Left\n1 mouse 3 2\n2 tap P\n2 repeat Left\n2 gamepad press DPadUp\n3 release Left\n\
+                      3 mouse_down Right\n4 mouse_leave\n5 quit\n";
+        let file_path = std::env::temp_dir().join(format!("render_core_recording_{}.txt", std::process::id()));
+        let file_path = file_path.to_string_lossy().to_string();
+        let framebuffer = Framebuffer::new(4, 4);
+
+        let mut expected = Vec::new();
+        let mut recorded = Vec::new();
+        {
+            let replay = HeadlessWindow::new(4, 4, parse_script(script).unwrap());
+            let mut window = RecordingWindow::new(Box::new(replay), &file_path).unwrap();
+            while window.is_open() {
+                expected.push(frame_input(&window));
+                window.present(&framebuffer);
+            }
+        }
+        let mut window = HeadlessWindow::load(&file_path, 4, 4).unwrap();
+        while window.is_open() {
+            recorded.push(frame_input(&window));
+            window.present(&framebuffer);
+        }
+        std::fs::remove_file(&file_path).unwrap();
+
+        assert_eq!(recorded, expected);
+        assert_eq!(expected.len(), 5);
+        assert_eq!(expected[2].4, vec![GamepadEvent::Pressed(GamepadButton::DPadUp)]);
+        // A tap is pressed but never down, the same as a quick keystroke in a live window
+        assert_eq!((expected[2].0.as_slice(), expected[2].1.as_slice()), (&[Key::Left][..], &[Key::P][..]));
+        // The mouse is gone once it leaves, not stuck where it was last seen
+        let mouse: Vec<Option<(f32, f32)>> = expected.iter().map(|input| input.2).collect();
+        assert_eq!(mouse, [None, Some((3.0, 2.0)), Some((3.0, 2.0)), Some((3.0, 2.0)), None]);
+        // Presses count as repeats too, Left being held repeats on frame 2
+        let repeats: Vec<&[Key]> = expected.iter().map(|input| input.6.as_slice()).collect();
+        assert_eq!(repeats, [&[Key::Left][..], &[], &[Key::P, Key::Left], &[], &[]]);
+    }
+
+    type FrameInput = (Vec<Key>, Vec<Key>, Option<(f32, f32)>, bool, Vec<GamepadEvent>, u32, Vec<Key>);
+
+    fn frame_input(window: &dyn WindowBackend) -> FrameInput {
+        let down = KEYS.iter().copied().filter(|&key| window.is_key_down(key)).collect();
+        let pressed = KEYS.iter().copied().filter(|&key| window.is_key_pressed(key)).collect();
+        let repeated = KEYS.iter().copied().filter(|&key| window.is_key_repeated(key)).collect();
+        (
+            down,
+            pressed,
+            window.mouse_position(),
+            window.is_mouse_down(MouseButton::Right),
+            window.gamepad_events().to_vec(),
+            window.time().to_bits(),
+            repeated,
+        )
+    }
+}
@@ -0,0 +1,119 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::framebuffer::{Framebuffer, ImageFormat};
+use crate::window::{Key, WindowBackend};
+
+// Folder screenshots are saved in unless SCREENSHOT_DIR says otherwise
+pub const DEFAULT_SCREENSHOT_DIR: &str = "screenshots";
+
+// F12 saves a PNG of the framebuffer, Shift+F12 a BMP. Call it once a frame
+// with what is about to be presented.
+pub fn handle_hotkey(window: &dyn WindowBackend, framebuffer: &Framebuffer) -> Option<PathBuf> {
+    if !window.is_key_pressed(Key::F12) {
+        return None;
+    }
+    let shift = window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift);
+    let format = if shift { ImageFormat::Bmp } else { ImageFormat::Png };
+
+    match capture(framebuffer, format) {
+        Ok(path) => {
+            println!("Saved {}", path.display());
+            Some(path)
+        }
+        Err(err) => {
+            eprintln!("Could not save the screenshot: {}", err);
+            None
+        }
+    }
+}
+
+// Saves the framebuffer as screenshot_<date>_<time>.<format> in SCREENSHOT_DIR
+// and returns where it went
+pub fn capture(framebuffer: &Framebuffer, format: ImageFormat) -> io::Result<PathBuf> {
+    let directory = std::env::var("SCREENSHOT_DIR").unwrap_or_else(|_| DEFAULT_SCREENSHOT_DIR.to_string());
+    capture_to(framebuffer, Path::new(&directory), format)
+}
+
+pub fn capture_to(framebuffer: &Framebuffer, directory: &Path, format: ImageFormat) -> io::Result<PathBuf> {
+    std::fs::create_dir_all(directory)?;
+
+    // Two shots within the same millisecond get numbered instead of overwritten
+    let name = format!("screenshot_{}", timestamp(SystemTime::now()));
+    let mut path = directory.join(format!("{}.{}", name, format.extension()));
+    let mut copy = 2;
+    while path.exists() {
+        path = directory.join(format!("{}_{}.{}", name, copy, format.extension()));
+        copy += 1;
+    }
+
+    framebuffer.save(&path.to_string_lossy(), format)?;
+    Ok(path)
+}
+
+// UTC as YYYYMMDD_HHMMSS_mmm, which sorts the same as the time
+fn timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    let second_of_day = seconds % 86_400;
+
+    format!(
+        "{:04}{:02}{:02}_{:02}{:02}{:02}_{:03}",
+        year,
+        month,
+        day,
+        second_of_day / 3600,
+        second_of_day / 60 % 60,
+        second_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+// Date of a day counted from 1970-01-01, in the proleptic Gregorian calendar
+// (Howard Hinnant's days_from_civil, run backwards)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_timestamp_is_utc_date_and_time() {
+        assert_eq!(timestamp(UNIX_EPOCH), "19700101_000000_000");
+        // 2024-02-29 23:59:58.250 UTC, a leap day
+        let time = UNIX_EPOCH + Duration::from_millis(1_709_251_198_250);
+        assert_eq!(timestamp(time), "20240229_235958_250");
+    }
+
+    #[test]
+    fn test_capture_never_overwrites() {
+        let directory = std::env::temp_dir().join(format!("render_core_screenshots_{}", std::process::id()));
+        let mut framebuffer = Framebuffer::new(3, 2);
+        framebuffer.set_background_color(0x336699);
+
+        let first = capture_to(&framebuffer, &directory, ImageFormat::Png).unwrap();
+        let second = capture_to(&framebuffer, &directory, ImageFormat::Png).unwrap();
+        let bmp = capture_to(&framebuffer, &directory, ImageFormat::Bmp).unwrap();
+
+        assert_ne!(first, second);
+        let image = image::open(&first).unwrap().to_rgb8();
+        assert_eq!(image.dimensions(), (3, 2));
+        assert_eq!(image.get_pixel(2, 1).0, [0x33, 0x66, 0x99]);
+        assert_eq!(&std::fs::read(&bmp).unwrap()[..2], b"BM");
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
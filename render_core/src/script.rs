@@ -0,0 +1,177 @@
+use std::fmt;
+
+use crate::window::{GamepadAxis, GamepadButton, GamepadEvent, Key, MouseButton};
+
+// One line of an input script, `frame event`:
+//
+//     # orbit right for a second, pause the game, then turn up the exposure
+//     0 press Right
+//     60 release Right
+//     61 tap P
+//     62 press PageUp
+//     70 repeat PageUp
+//     72 release PageUp
+//     80 mouse 320 240
+//     80 mouse_down Left
+//     85 mouse_up Left
+//     86 mouse_leave
+//     90 gamepad press DPadUp
+//     92 gamepad axis LeftStickY 0.8
+//     95 time 1.5
+//     99 quit
+//
+// Recordings are written in the same format, so they replay as scripts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScriptEvent {
+    Press(Key),
+    Release(Key),
+    // Pressed and released again before the frame, like a quick keystroke:
+    // is_key_pressed sees it but is_key_down never does
+    Tap(Key),
+    // Key repeat from the OS while the key is held down
+    Repeat(Key),
+    Mouse(f32, f32),
+    MouseDown(MouseButton),
+    MouseUp(MouseButton),
+    // The pointer left the window, mouse_position is None until it's back
+    MouseLeave,
+    Gamepad(GamepadEvent),
+    // Seconds since the start, without it every frame adds 1/60
+    Time(f32),
+    Quit,
+}
+
+impl fmt::Display for ScriptEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScriptEvent::Press(key) => write!(f, "press {:?}", key),
+            ScriptEvent::Release(key) => write!(f, "release {:?}", key),
+            ScriptEvent::Tap(key) => write!(f, "tap {:?}", key),
+            ScriptEvent::Repeat(key) => write!(f, "repeat {:?}", key),
+            ScriptEvent::Mouse(x, y) => write!(f, "mouse {} {}", x, y),
+            ScriptEvent::MouseDown(button) => write!(f, "mouse_down {:?}", button),
+            ScriptEvent::MouseUp(button) => write!(f, "mouse_up {:?}", button),
+            ScriptEvent::MouseLeave => write!(f, "mouse_leave"),
+            ScriptEvent::Gamepad(GamepadEvent::Pressed(button)) => write!(f, "gamepad press {:?}", button),
+            ScriptEvent::Gamepad(GamepadEvent::Released(button)) => write!(f, "gamepad release {:?}", button),
+            ScriptEvent::Gamepad(GamepadEvent::Axis(axis, value)) => write!(f, "gamepad axis {:?} {}", axis, value),
+            ScriptEvent::Time(seconds) => write!(f, "time {}", seconds),
+            ScriptEvent::Quit => write!(f, "quit"),
+        }
+    }
+}
+
+// Events of a script sorted by frame, errors start with the line number
+pub fn parse_script(source: &str) -> Result<Vec<(usize, ScriptEvent)>, String> {
+    let mut events = Vec::new();
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let words: Vec<&str> = line.split('#').next().unwrap_or("").split_whitespace().collect();
+        if words.is_empty() {
+            continue;
+        }
+
+        let frame = words[0]
+            .parse::<usize>()
+            .map_err(|_| format!("{}: expected a frame number, got '{}'", line_number, words[0]))?;
+        let event = parse_event(&words[1..]).map_err(|err| format!("{}: {}", line_number, err))?;
+        events.push((frame, event));
+    }
+
+    events.sort_by_key(|(frame, _)| *frame);
+    Ok(events)
+}
+
+fn parse_event(words: &[&str]) -> Result<ScriptEvent, String> {
+    let number = |word: &str| word.parse::<f32>().map_err(|_| format!("expected a number, got '{}'", word));
+    let key = |name: &str| key_from_name(name).ok_or_else(|| format!("unknown key '{}'", name));
+    let mouse_button = |name: &str| mouse_button_from_name(name).ok_or_else(|| format!("unknown mouse button '{}'", name));
+    let gamepad_button = |name: &str| gamepad_button_from_name(name).ok_or_else(|| format!("unknown gamepad button '{}'", name));
+
+    match words {
+        ["press", name] => Ok(ScriptEvent::Press(key(name)?)),
+        ["release", name] => Ok(ScriptEvent::Release(key(name)?)),
+        ["tap", name] => Ok(ScriptEvent::Tap(key(name)?)),
+        ["repeat", name] => Ok(ScriptEvent::Repeat(key(name)?)),
+        ["mouse", x, y] => Ok(ScriptEvent::Mouse(number(x)?, number(y)?)),
+        ["mouse_down", name] => Ok(ScriptEvent::MouseDown(mouse_button(name)?)),
+        ["mouse_up", name] => Ok(ScriptEvent::MouseUp(mouse_button(name)?)),
+        ["mouse_leave"] => Ok(ScriptEvent::MouseLeave),
+        ["gamepad", "press", name] => Ok(ScriptEvent::Gamepad(GamepadEvent::Pressed(gamepad_button(name)?))),
+        ["gamepad", "release", name] => Ok(ScriptEvent::Gamepad(GamepadEvent::Released(gamepad_button(name)?))),
+        ["gamepad", "axis", name, value] => {
+            let axis = gamepad_axis_from_name(name).ok_or_else(|| format!("unknown gamepad axis '{}'", name))?;
+            Ok(ScriptEvent::Gamepad(GamepadEvent::Axis(axis, number(value)?.clamp(-1.0, 1.0))))
+        }
+        ["time", seconds] => Ok(ScriptEvent::Time(number(seconds)?)),
+        ["quit"] => Ok(ScriptEvent::Quit),
+        _ => Err("expected press, release, tap or repeat KEY, mouse X Y, mouse_leave, mouse_down or mouse_up \
+                  BUTTON, gamepad press|release BUTTON, gamepad axis AXIS VALUE, time SECONDS or quit"
+            .to_string()),
+    }
+}
+
+// Names are those of the enum variants, `W`, `Key1`, `Left`, `NumPadPlus`...
+pub fn key_from_name(name: &str) -> Option<Key> {
+    KEYS.iter().copied().find(|key| format!("{:?}", key) == name)
+}
+
+fn mouse_button_from_name(name: &str) -> Option<MouseButton> {
+    MOUSE_BUTTONS.iter().copied().find(|button| format!("{:?}", button) == name)
+}
+
+fn gamepad_button_from_name(name: &str) -> Option<GamepadButton> {
+    GamepadButton::ALL.iter().copied().find(|button| format!("{:?}", button) == name)
+}
+
+fn gamepad_axis_from_name(name: &str) -> Option<GamepadAxis> {
+    GamepadAxis::ALL.iter().copied().find(|axis| format!("{:?}", axis) == name)
+}
+
+pub const MOUSE_BUTTONS: [MouseButton; 3] = [MouseButton::Left, MouseButton::Middle, MouseButton::Right];
+
+// Every key minifb knows, for name lookups and for recording
+pub const KEYS: &[Key] = &[
+    Key::Key0, Key::Key1, Key::Key2, Key::Key3, Key::Key4,
+    Key::Key5, Key::Key6, Key::Key7, Key::Key8, Key::Key9,
+    Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I,
+    Key::J, Key::K, Key::L, Key::M, Key::N, Key::O, Key::P, Key::Q, Key::R,
+    Key::S, Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z,
+    Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6, Key::F7, Key::F8,
+    Key::F9, Key::F10, Key::F11, Key::F12, Key::F13, Key::F14, Key::F15,
+    Key::Down, Key::Left, Key::Right, Key::Up,
+    Key::Apostrophe, Key::Backquote, Key::Backslash, Key::Comma, Key::Equal,
+    Key::LeftBracket, Key::Minus, Key::Period, Key::RightBracket, Key::Semicolon,
+    Key::Slash, Key::Backspace, Key::Delete, Key::End, Key::Enter, Key::Escape,
+    Key::Home, Key::Insert, Key::Menu, Key::PageDown, Key::PageUp, Key::Pause,
+    Key::Space, Key::Tab, Key::NumLock, Key::CapsLock, Key::ScrollLock,
+    Key::LeftShift, Key::RightShift, Key::LeftCtrl, Key::RightCtrl,
+    Key::NumPad0, Key::NumPad1, Key::NumPad2, Key::NumPad3, Key::NumPad4,
+    Key::NumPad5, Key::NumPad6, Key::NumPad7, Key::NumPad8, Key::NumPad9,
+    Key::NumPadDot, Key::NumPadSlash, Key::NumPadAsterisk, Key::NumPadMinus,
+    Key::NumPadPlus, Key::NumPadEnter, Key::LeftAlt, Key::RightAlt,
+    Key::LeftSuper, Key::RightSuper,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_print_the_way_they_parse() {
+        let source = "0 press NumPadPlus\n0 mouse 12.5 40\n2 mouse_down Right\n2 mouse_leave\n3 gamepad press DPadLeft\n\
+                      3 gamepad axis LeftStickY -0.25\n4 time 0.5\n5 tap Key1\n5 repeat Up\n6 quit\n";
+        let events = parse_script(source).unwrap();
+        let printed: String = events.iter().map(|(frame, event)| format!("{} {}\n", frame, event)).collect();
+
+        assert_eq!(printed, source);
+    }
+
+    #[test]
+    fn test_errors_name_the_line() {
+        assert_eq!(parse_script("0 press Nope").unwrap_err(), "1: unknown key 'Nope'");
+        assert_eq!(parse_script("# header\nsoon quit").unwrap_err(), "2: expected a frame number, got 'soon'");
+        assert!(parse_script("0 gamepad axis LeftStickX far").unwrap_err().starts_with("1: expected a number"));
+    }
+}
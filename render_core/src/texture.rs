@@ -0,0 +1,100 @@
+use image::{DynamicImage, GenericImageView, ImageReader, Pixel, RgbaImage};
+use nalgebra_glm::Vec3;
+
+use crate::color::Color;
+
+#[derive(Debug, Clone)]
+pub struct Texture {
+    image: DynamicImage,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Texture {
+    pub fn load(file_path: &str) -> Result<Texture, String> {
+        let image = ImageReader::open(file_path)
+            .map_err(|err| format!("{}: {}", file_path, err))?
+            .decode()
+            .map_err(|err| format!("{}: {}", file_path, err))?;
+        Ok(Texture::from_image(image))
+    }
+
+    pub fn from_image(image: DynamicImage) -> Texture {
+        let (width, height) = image.dimensions();
+        Texture { image, width, height }
+    }
+
+    // Magenta and black checkerboard that stands in for textures that failed to load
+    pub fn missing() -> Texture {
+        let image = RgbaImage::from_fn(64, 64, |x, y| {
+            if (x / 16 + y / 16) % 2 == 0 {
+                image::Rgba([255, 0, 255, 255])
+            } else {
+                image::Rgba([0, 0, 0, 255])
+            }
+        });
+        Texture::from_image(DynamicImage::ImageRgba8(image))
+    }
+
+    // Texel by pixel coordinates. They wrap around, so textures smaller than
+    // what the caller expects (like the fallback) repeat instead of panicking.
+    pub fn get_pixel(&self, x: u32, y: u32) -> Color {
+        let pixel = self.image.get_pixel(x % self.width, y % self.height).to_rgb();
+        Color::new(pixel[0], pixel[1], pixel[2])
+    }
+
+    // Texel by normalized coordinates, (0, 0) is the top left and anything
+    // outside [0, 1] is clamped to the edge
+    pub fn get_pixel_color(&self, u: f32, v: f32) -> Color {
+        let (x, y) = self.texel(u, v);
+        self.get_pixel(x, y)
+    }
+
+    // Alpha in [0, 1], textures without an alpha channel are opaque
+    pub fn get_alpha(&self, u: f32, v: f32) -> f32 {
+        let (x, y) = self.texel(u, v);
+        self.image.get_pixel(x, y)[3] as f32 / 255.0
+    }
+
+    // Luminance in [0, 1], for height maps
+    pub fn get_height(&self, u: f32, v: f32) -> f32 {
+        let color = self.get_pixel_color(u, v);
+        (0.2126 * color.r as f32 + 0.7152 * color.g as f32 + 0.0722 * color.b as f32) / 255.0
+    }
+
+    // Tangent space normal stored as RGB, each channel mapped from [0, 255] to [-1, 1]
+    pub fn get_normal(&self, u: f32, v: f32) -> Vec3 {
+        let color = self.get_pixel_color(u, v);
+        let decode = |c: u8| c as f32 / 255.0 * 2.0 - 1.0;
+        Vec3::new(decode(color.r), decode(color.g), decode(color.b)).normalize()
+    }
+
+    fn texel(&self, u: f32, v: f32) -> (u32, u32) {
+        let x = (u.clamp(0.0, 1.0) * (self.width as f32 - 1.0)).floor() as u32;
+        let y = (v.clamp(0.0, 1.0) * (self.height as f32 - 1.0)).floor() as u32;
+        (x, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_is_a_checkerboard() {
+        let texture = Texture::missing();
+
+        assert_eq!(texture.get_pixel(0, 0), Color::new(255, 0, 255));
+        assert_eq!(texture.get_pixel(16, 0), Color::black());
+        assert_eq!(texture.get_pixel(16, 16), Color::new(255, 0, 255));
+    }
+
+    #[test]
+    fn test_coordinates_wrap_and_clamp() {
+        let texture = Texture::missing();
+
+        assert_eq!(texture.get_pixel(64 + 16, 0), texture.get_pixel(16, 0));
+        assert_eq!(texture.get_pixel_color(-1.0, 2.0), texture.get_pixel_color(0.0, 1.0));
+        assert_eq!(texture.get_alpha(0.5, 0.5), 1.0);
+    }
+}
@@ -0,0 +1,182 @@
+use std::time::Instant;
+
+pub use minifb::{Key, MouseButton};
+use minifb::{KeyRepeat, MouseMode, Window, WindowOptions};
+
+use crate::framebuffer::Framebuffer;
+use crate::headless::HeadlessWindow;
+use crate::recording::RecordingWindow;
+
+// Everything the main loops need from a window, so they can run on screen
+// or headless without changing
+pub trait WindowBackend {
+    fn is_open(&self) -> bool;
+    // Shows the framebuffer and collects the input for the next frame
+    fn present(&mut self, framebuffer: &Framebuffer);
+    fn is_key_down(&self, key: Key) -> bool;
+    // Only true on the frame the key went down
+    fn is_key_pressed(&self, key: Key) -> bool;
+    // Like is_key_pressed, but also true on the key repeats of a held key
+    fn is_key_repeated(&self, key: Key) -> bool;
+    // Cursor in window pixels, clamped to the window
+    fn mouse_position(&self) -> Option<(f32, f32)>;
+    fn is_mouse_down(&self, button: MouseButton) -> bool;
+    // Seconds since the window opened. Animations should use this rather
+    // than the wall clock, so a replay looks the same as the recording.
+    fn time(&self) -> f32;
+    // minifb doesn't read gamepads, so apps poll them themselves and hand
+    // the events over every frame. That way gamepad input is recorded and
+    // replayed like the rest.
+    fn feed_gamepad(&mut self, events: &[GamepadEvent]);
+    // Gamepad events of the current frame
+    fn gamepad_events(&self) -> &[GamepadEvent];
+    fn set_title(&mut self, title: &str);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GamepadButton {
+    // Face buttons by position, A/B/X/Y on an Xbox pad
+    South,
+    East,
+    West,
+    North,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+}
+
+impl GamepadButton {
+    pub const ALL: [GamepadButton; 12] = [
+        GamepadButton::South,
+        GamepadButton::East,
+        GamepadButton::West,
+        GamepadButton::North,
+        GamepadButton::DPadUp,
+        GamepadButton::DPadDown,
+        GamepadButton::DPadLeft,
+        GamepadButton::DPadRight,
+        GamepadButton::LeftTrigger,
+        GamepadButton::RightTrigger,
+        GamepadButton::Select,
+        GamepadButton::Start,
+    ];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+}
+
+impl GamepadAxis {
+    pub const ALL: [GamepadAxis; 4] = [
+        GamepadAxis::LeftStickX,
+        GamepadAxis::LeftStickY,
+        GamepadAxis::RightStickX,
+        GamepadAxis::RightStickY,
+    ];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GamepadEvent {
+    Pressed(GamepadButton),
+    Released(GamepadButton),
+    // Stick position in [-1, 1]
+    Axis(GamepadAxis, f32),
+}
+
+// A real window, unless the environment asks for something else:
+// HEADLESS_SCRIPT replays that input script without a display, writing every
+// frame into HEADLESS_FRAMES if that is set too, and INPUT_RECORD writes the
+// input of every frame to a file that replays as a script.
+pub fn open(title: &str, width: usize, height: usize) -> Result<Box<dyn WindowBackend>, String> {
+    let mut window: Box<dyn WindowBackend> = match std::env::var("HEADLESS_SCRIPT") {
+        Ok(script) => {
+            let mut window = HeadlessWindow::load(&script, width, height)?;
+            if let Ok(frames) = std::env::var("HEADLESS_FRAMES") {
+                window = window.with_frame_dump(frames)?;
+            }
+            Box::new(window)
+        }
+        Err(_) => Box::new(MinifbWindow::new(title, width, height)?),
+    };
+    window.set_title(title);
+
+    match std::env::var("INPUT_RECORD") {
+        Ok(file_path) => Ok(Box::new(RecordingWindow::new(window, &file_path)?)),
+        Err(_) => Ok(window),
+    }
+}
+
+pub struct MinifbWindow {
+    window: Window,
+    start: Instant,
+    gamepad_events: Vec<GamepadEvent>,
+}
+
+impl MinifbWindow {
+    pub fn new(title: &str, width: usize, height: usize) -> Result<Self, String> {
+        let window = Window::new(title, width, height, WindowOptions::default()).map_err(|err| err.to_string())?;
+        Ok(MinifbWindow {
+            window,
+            start: Instant::now(),
+            gamepad_events: Vec::new(),
+        })
+    }
+}
+
+impl WindowBackend for MinifbWindow {
+    fn is_open(&self) -> bool {
+        self.window.is_open()
+    }
+
+    fn present(&mut self, framebuffer: &Framebuffer) {
+        self.gamepad_events.clear();
+        self.window
+            .update_with_buffer(&framebuffer.buffer, framebuffer.width, framebuffer.height)
+            .unwrap();
+    }
+
+    fn is_key_down(&self, key: Key) -> bool {
+        self.window.is_key_down(key)
+    }
+
+    fn is_key_pressed(&self, key: Key) -> bool {
+        self.window.is_key_pressed(key, KeyRepeat::No)
+    }
+
+    fn is_key_repeated(&self, key: Key) -> bool {
+        self.window.is_key_pressed(key, KeyRepeat::Yes)
+    }
+
+    fn mouse_position(&self) -> Option<(f32, f32)> {
+        self.window.get_mouse_pos(MouseMode::Clamp)
+    }
+
+    fn is_mouse_down(&self, button: MouseButton) -> bool {
+        self.window.get_mouse_down(button)
+    }
+
+    fn time(&self) -> f32 {
+        self.start.elapsed().as_secs_f32()
+    }
+
+    fn feed_gamepad(&mut self, events: &[GamepadEvent]) {
+        self.gamepad_events.extend_from_slice(events);
+    }
+
+    fn gamepad_events(&self) -> &[GamepadEvent] {
+        &self.gamepad_events
+    }
+
+    fn set_title(&mut self, title: &str) {
+        self.window.set_title(title);
+    }
+}